@@ -0,0 +1,31 @@
+//! Throughput over a year of KTPA reports
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::io::Read;
+
+use weather_reports::batch::{parse_reports, parse_timestamped_lines};
+
+fn ktpa(c: &mut Criterion) {
+    let mut archive = String::new();
+    zstd::Decoder::new(&include_bytes!("../tests/ktpa.txt.zst")[..])
+        .unwrap()
+        .read_to_string(&mut archive)
+        .unwrap();
+    let reports = archive
+        .lines()
+        .filter_map(|line| line.get(12..))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("ktpa");
+    group.throughput(Throughput::Elements(reports.len() as u64));
+    group.sample_size(10);
+    group.bench_function("parse_reports", |b| {
+        b.iter(|| parse_reports(reports.iter().copied()).count())
+    });
+    group.bench_function("parse_timestamped_lines", |b| {
+        b.iter(|| parse_timestamped_lines(archive.as_bytes()).count())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ktpa);
+criterion_main!(benches);
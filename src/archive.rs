@@ -0,0 +1,351 @@
+//! Readers for archived report histories
+//!
+//! Archives store each report with the full time it was received, which resolves the day of month in the report into
+//! an absolute time. Two layouts are supported:
+//!
+//! * [timestamped_lines]: one report per line, prefixed with `YYYYMMDDHHMM`
+//! * [noaa_cycle]: NOAA cycle and station files, where a `YYYY/MM/DD HH:MM` line precedes each report
+//!
+//! ```
+//! use weather_reports::archive::timestamped_lines;
+//!
+//! let archive = "202008010053 METAR KTPA 010053Z 35005KT 10SM SCT055 29/21 A2999=\n";
+//! let (time, report) = timestamped_lines(archive.as_bytes()).next().unwrap().unwrap();
+//! assert_eq!(time.to_rfc3339(), "2020-08-01T00:53:00+00:00");
+//! assert_eq!(report.identifier, "KTPA");
+//! ```
+use chrono::{NaiveDateTime, TimeZone};
+use chrono_tz::{Greenwich, Tz};
+use peg::{error::ParseError, str::LineCol};
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Read},
+};
+
+use crate::{parse::metar, tokens::MetarReport};
+
+/// A report with the absolute time it was observed
+///
+/// The time is the report's observation time, resolved against the archive timestamp.
+/// If the report has no observation time, the archive timestamp is used instead.
+pub type ArchivedReport = (chrono::DateTime<Tz>, MetarReport<'static>);
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    /// A line that should have started with a timestamp did not
+    Timestamp {
+        line: usize,
+    },
+    Parse {
+        /// Line the report starts on, starting at 1
+        line: usize,
+        /// Report exactly as parsed, which the error location refers to
+        report: String,
+        error: ParseError<LineCol>,
+    },
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read archive: {}", err),
+            Self::Timestamp { line } => write!(f, "missing timestamp on line {}", line),
+            Self::Parse { line, error, .. } => {
+                write!(f, "could not parse report on line {}: {}", line, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse { error, .. } => Some(error),
+            Self::Timestamp { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Reads reports prefixed with a `YYYYMMDDHHMM` timestamp, one per line
+///
+/// Blank lines are skipped.
+pub fn timestamped_lines<R: BufRead>(reader: R) -> TimestampedLines<R> {
+    TimestampedLines {
+        lines: reader.lines(),
+        line: 0,
+    }
+}
+
+pub struct TimestampedLines<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> Iterator for TimestampedLines<R> {
+    type Item = Result<ArchivedReport, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(err) => return Some(Err(err.into())),
+            };
+            self.line += 1;
+            if text.trim().is_empty() {
+                continue;
+            }
+            return Some(timestamped_line(self.line, &text));
+        }
+    }
+}
+
+/// Parses a single non-blank line of [timestamped_lines]
+pub(crate) fn timestamped_line(line: usize, text: &str) -> Result<ArchivedReport, ArchiveError> {
+    match text
+        .get(..12)
+        .and_then(|prefix| parse_timestamp(prefix, "%Y%m%d%H%M"))
+    {
+        Some(timestamp) => resolve(timestamp, line, &text[12..]),
+        None => Err(ArchiveError::Timestamp { line }),
+    }
+}
+
+/// Reads NOAA cycle or station files, where each report follows a `YYYY/MM/DD HH:MM` line
+///
+/// Reports may span several lines and end at a blank line or the next timestamp.
+pub fn noaa_cycle<R: BufRead>(reader: R) -> NoaaCycle<R> {
+    NoaaCycle {
+        lines: reader.lines(),
+        line: 0,
+        pending: None,
+    }
+}
+
+pub struct NoaaCycle<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    /// Timestamp read while finishing the previous report
+    pending: Option<(chrono::DateTime<Tz>, usize)>,
+}
+
+impl<R: BufRead> Iterator for NoaaCycle<R> {
+    type Item = Result<ArchivedReport, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (timestamp, timestamp_line) = match self.pending.take() {
+            Some(pending) => pending,
+            None => loop {
+                let text = match self.lines.next()? {
+                    Ok(text) => text,
+                    Err(err) => return Some(Err(err.into())),
+                };
+                self.line += 1;
+                if text.trim().is_empty() {
+                    continue;
+                }
+                match parse_timestamp(text.trim(), "%Y/%m/%d %H:%M") {
+                    Some(timestamp) => break (timestamp, self.line),
+                    None => return Some(Err(ArchiveError::Timestamp { line: self.line })),
+                }
+            },
+        };
+
+        let mut report = String::new();
+        for text in &mut self.lines {
+            let text = match text {
+                Ok(text) => text,
+                Err(err) => return Some(Err(err.into())),
+            };
+            self.line += 1;
+            if let Some(next) = parse_timestamp(text.trim(), "%Y/%m/%d %H:%M") {
+                self.pending = Some((next, self.line));
+                break;
+            }
+            if text.trim().is_empty() {
+                if report.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if !report.is_empty() {
+                report.push('\n');
+            }
+            report.push_str(&text);
+        }
+        Some(resolve(timestamp, timestamp_line + 1, &report))
+    }
+}
+
+fn parse_timestamp(text: &str, format: &str) -> Option<chrono::DateTime<Tz>> {
+    NaiveDateTime::parse_from_str(text, format)
+        .ok()
+        .map(|timestamp| Greenwich.from_utc_datetime(&timestamp))
+}
+
+fn resolve(
+    timestamp: chrono::DateTime<Tz>,
+    line: usize,
+    text: &str,
+) -> Result<ArchivedReport, ArchiveError> {
+    match metar(text) {
+        Ok(report) => Ok((
+            report
+                .observation_time
+                .and_then(|observation_time| observation_time.resolve(&timestamp))
+                .unwrap_or(timestamp),
+            report.into_owned(),
+        )),
+        Err(error) => Err(ArchiveError::Parse {
+            line,
+            report: text.to_string(),
+            error,
+        }),
+    }
+}
+
+/// Wraps a reader to decompress zstd or gzip input, detected by its magic number
+///
+/// Other input is passed through unchanged.
+/// Decompression requires the `zstd` and `gzip` features respectively.
+pub fn decompress<'a>(reader: impl Read + 'a) -> io::Result<Box<dyn BufRead + 'a>> {
+    const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+    const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(unsupported("zstd"));
+    }
+    if magic.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        )));
+        #[cfg(not(feature = "gzip"))]
+        return Err(unsupported("gzip"));
+    }
+    Ok(Box::new(reader))
+}
+
+#[cfg(not(all(feature = "zstd", feature = "gzip")))]
+fn unsupported(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} input requires the {} feature", feature, feature),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::into_rich_diagnostic;
+
+    #[test]
+    fn read_timestamped_lines() {
+        let archive =
+            "202008010053 METAR KTPA 010053Z 35005KT 10SM SCT055 BKN120 29/21 A2999 RMK AO2 $=
+
+202009010000 METAR KTPA 312356Z 00000KT 10SM FEW030 27/24 A3001=
+202009010100 METAR KTPA 0100Z garbage
+METAR KTPA 010153Z 01006KT 10SM FEW055 29/22 A3000
+";
+        let reports = timestamped_lines(archive.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(reports.len(), 4);
+
+        let (time, report) = reports[0].as_ref().unwrap();
+        assert_eq!(time.to_rfc3339(), "2020-08-01T00:53:00+00:00");
+        assert_eq!(report.identifier, "KTPA");
+        // Observed on the last day of the previous month
+        let (time, _) = reports[1].as_ref().unwrap();
+        assert_eq!(time.to_rfc3339(), "2020-08-31T23:56:00+00:00");
+        assert!(matches!(
+            reports[2],
+            Err(ArchiveError::Parse { line: 4, .. })
+        ));
+        assert!(matches!(
+            reports[3],
+            Err(ArchiveError::Timestamp { line: 5 })
+        ));
+    }
+
+    #[test]
+    fn diagnose_parse_errors() {
+        let archive = "202008010053 METAR KTPA 010053Z 35005KT 10SMM SCT055 29/21 A2999
+202008010153 METAR KTPA 010153Z é garbage
+";
+        let errors = timestamped_lines(archive.as_bytes())
+            .map(|report| match report {
+                Err(ArchiveError::Parse { report, error, .. }) => (report, error),
+                other => panic!("{:?}", other),
+            })
+            .collect::<Vec<_>>();
+
+        let (report, error) = &errors[0];
+        let diagnostic = into_rich_diagnostic(report, error);
+        assert_eq!(&report[diagnostic.labels[0].range.clone()], "10SMM");
+        let (report, error) = &errors[1];
+        let diagnostic = into_rich_diagnostic(report, error);
+        assert!(report[diagnostic.labels[0].range.clone()].starts_with('é'));
+    }
+
+    #[test]
+    fn read_noaa_cycle() {
+        let archive = "2021/08/29 03:53
+KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004 RMK AO2 SLP179 T01940094
+
+2021/08/29 03:56
+KBFI 290356Z 00000KT 10SM CLR 18/09 A3004
+RMK AO2 SLP180
+2021/09/01 00:00
+KPAE 312355Z 36005KT 10SM FEW050 17/10 A3003
+";
+        let reports = noaa_cycle(archive.as_bytes())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reports
+                .iter()
+                .map(|(time, report)| (time.to_rfc3339(), report.identifier.to_string()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("2021-08-29T03:53:00+00:00".to_string(), "KSEA".to_string()),
+                ("2021-08-29T03:56:00+00:00".to_string(), "KBFI".to_string()),
+                ("2021-08-31T23:55:00+00:00".to_string(), "KPAE".to_string()),
+            ]
+        );
+        assert_eq!(reports[1].1.remark.as_deref(), Some("RMK AO2 SLP180"));
+    }
+
+    #[test]
+    fn decompress_input() {
+        let mut plain = String::new();
+        decompress("KSEA 290353Z".as_bytes())
+            .unwrap()
+            .read_to_string(&mut plain)
+            .unwrap();
+        assert_eq!(plain, "KSEA 290353Z");
+
+        let compressed = zstd::encode_all("KSEA 290353Z".as_bytes(), 0).unwrap();
+        let result = decompress(compressed.as_slice());
+        if cfg!(feature = "zstd") {
+            let mut decompressed = String::new();
+            result.unwrap().read_to_string(&mut decompressed).unwrap();
+            assert_eq!(decompressed, "KSEA 290353Z");
+        } else {
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Parsing many reports at once
+//!
+//! Reports are parsed in parallel with the `rayon` feature, and sequentially otherwise.
+//! Input is parsed in chunks, so it does not need to fit in memory at once.
+//! Results are always in the same order as their input.
+//!
+//! ```
+//! use weather_reports::batch::{parse_reports, BatchStats};
+//!
+//! let mut stats = BatchStats::default();
+//! parse_reports(["KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004", "KSEA 2903Z garbage"])
+//!     .for_each(|result| stats.add(&result));
+//! assert_eq!(stats.percentage(), 50.);
+//! assert_eq!(stats.most_common_columns(1), vec![(10, 1)]);
+//! ```
+use peg::{error::ParseError, str::LineCol};
+use std::collections::BTreeMap;
+
+use crate::{parse::metar, tokens::MetarReport};
+
+#[cfg(feature = "chrono_helpers")]
+pub use archive::*;
+
+/// Reports parsed together
+const CHUNK_REPORTS: usize = 4096;
+
+/// Parses each report as a METAR
+pub fn parse_reports<'a, I: IntoIterator<Item = &'a str>>(
+    reports: I,
+) -> ParsedReports<'a, I::IntoIter> {
+    ParsedReports {
+        reports: reports.into_iter(),
+        parsed: vec![].into_iter(),
+    }
+}
+
+pub struct ParsedReports<'a, I> {
+    reports: I,
+    parsed: std::vec::IntoIter<Result<MetarReport<'a>, ParseError<LineCol>>>,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for ParsedReports<'a, I> {
+    type Item = Result<MetarReport<'a>, ParseError<LineCol>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(result) = self.parsed.next() {
+            return Some(result);
+        }
+        let chunk = self
+            .reports
+            .by_ref()
+            .take(CHUNK_REPORTS)
+            .collect::<Vec<_>>();
+        if chunk.is_empty() {
+            return None;
+        }
+        self.parsed = map_in_order(chunk, metar).into_iter();
+        self.parsed.next()
+    }
+}
+
+fn map_in_order<T: Send, U: Send>(items: Vec<T>, f: impl Fn(T) -> U + Sync + Send) -> Vec<U> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        items.into_par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    items.into_iter().map(f).collect()
+}
+
+#[cfg(feature = "chrono_helpers")]
+mod archive {
+    use std::io::{self, BufRead};
+
+    use super::{map_in_order, BatchStats};
+    use crate::archive::{timestamped_line, ArchiveError, ArchivedReport};
+
+    /// Lines read and parsed together
+    const CHUNK_LINES: usize = 4096;
+
+    /// Like [timestamped_lines](crate::archive::timestamped_lines), but parses chunks of lines at once
+    pub fn parse_timestamped_lines<R: BufRead>(reader: R) -> ParsedLines<R> {
+        ParsedLines {
+            lines: reader.lines(),
+            line: 0,
+            parsed: vec![].into_iter(),
+        }
+    }
+
+    pub struct ParsedLines<R> {
+        lines: io::Lines<R>,
+        line: usize,
+        parsed: std::vec::IntoIter<Result<ArchivedReport, ArchiveError>>,
+    }
+
+    impl<R: BufRead> Iterator for ParsedLines<R> {
+        type Item = Result<ArchivedReport, ArchiveError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(result) = self.parsed.next() {
+                    return Some(result);
+                }
+                let mut chunk = vec![];
+                let mut read = 0;
+                for text in self.lines.by_ref().take(CHUNK_LINES) {
+                    read += 1;
+                    self.line += 1;
+                    match text {
+                        Ok(text) if text.trim().is_empty() => {}
+                        text => chunk.push((self.line, text)),
+                    }
+                }
+                if read == 0 {
+                    return None;
+                }
+                self.parsed = map_in_order(chunk, |(line, text)| match text {
+                    Ok(text) => timestamped_line(line, &text),
+                    Err(err) => Err(err.into()),
+                })
+                .into_iter();
+            }
+        }
+    }
+
+    impl BatchStats {
+        /// Like [BatchStats::add], where lines without a timestamp and read errors are failures without a position
+        pub fn add_archived(&mut self, result: &Result<ArchivedReport, ArchiveError>) {
+            match result {
+                Ok(_) => self.total += 1,
+                Err(ArchiveError::Parse { error, .. }) => self.add::<()>(&Err(error.clone())),
+                Err(_) => {
+                    self.total += 1;
+                    self.failures += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Aggregate outcome of parsing many reports
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BatchStats {
+    pub total: usize,
+    pub failures: usize,
+    /// Number of failures at each column, starting at 1
+    pub failure_columns: BTreeMap<usize, usize>,
+    /// Number of failures expecting each set of tokens
+    pub expected: BTreeMap<String, usize>,
+}
+
+impl BatchStats {
+    pub fn add<T>(&mut self, result: &Result<T, ParseError<LineCol>>) {
+        self.total += 1;
+        if let Err(err) = result {
+            self.failures += 1;
+            *self.failure_columns.entry(err.location.column).or_default() += 1;
+            *self.expected.entry(err.expected.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Percentage of reports that were parsed
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.
+        } else {
+            100. - self.failures as f64 / self.total as f64 * 100.
+        }
+    }
+
+    /// Columns with the most failures and their counts, most common first
+    pub fn most_common_columns(&self, n: usize) -> Vec<(usize, usize)> {
+        most_common(&self.failure_columns, n)
+            .map(|(column, count)| (*column, count))
+            .collect()
+    }
+
+    /// Expected token sets with the most failures and their counts, most common first
+    pub fn most_common_expected(&self, n: usize) -> Vec<(&str, usize)> {
+        most_common(&self.expected, n)
+            .map(|(expected, count)| (expected.as_str(), count))
+            .collect()
+    }
+}
+
+fn most_common<K>(counts: &BTreeMap<K, usize>, n: usize) -> impl Iterator<Item = (&K, usize)> {
+    let mut counts = counts
+        .iter()
+        .map(|(key, count)| (key, *count))
+        .collect::<Vec<_>>();
+    // Stable, so ties stay in key order
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts.into_iter().take(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::MilitaryTime;
+
+    #[test]
+    fn parse_in_order() {
+        let reports = [
+            "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004",
+            "KSEA 2903Z garbage",
+            "KBFI 290356Z 00000KT 10SM CLR 18/09 A3004",
+            "KBFI 2903Z garbage",
+            "KPAE 290355Z garbage",
+        ];
+        let results = parse_reports(reports).collect::<Vec<_>>();
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.as_ref().ok().map(|report| &*report.identifier))
+                .collect::<Vec<_>>(),
+            vec![Some("KSEA"), None, Some("KBFI"), None, None]
+        );
+
+        let mut stats = BatchStats::default();
+        results.iter().for_each(|result| stats.add(result));
+        assert_eq!((stats.total, stats.failures), (5, 3));
+        assert_eq!(stats.percentage(), 40.);
+        assert_eq!(stats.most_common_columns(1), vec![(10, 2)]);
+        assert_eq!(stats.most_common_expected(3).len(), 2);
+    }
+
+    #[test]
+    fn parse_chunks_in_order() {
+        let reports = (0..10_000)
+            .map(|minute| {
+                format!(
+                    "KSEA 29{:02}{:02}Z 01008KT 10SM SCT200 19/09 A3004",
+                    minute / 60 % 24,
+                    minute % 60
+                )
+            })
+            .collect::<Vec<_>>();
+        let results = parse_reports(reports.iter().map(String::as_str)).collect::<Vec<_>>();
+        assert_eq!(results.len(), 10_000);
+        assert_eq!(
+            results[9_999]
+                .as_ref()
+                .unwrap()
+                .observation_time
+                .unwrap()
+                .time,
+            MilitaryTime {
+                hour: 22,
+                minute: 39
+            }
+        );
+    }
+
+    #[cfg(feature = "chrono_helpers")]
+    #[test]
+    fn parse_archive_in_order() {
+        use crate::archive::ArchiveError;
+
+        let archive = (0..10_000)
+            .map(|minute| {
+                format!(
+                    "20200801{:02}{:02} METAR KTPA 01{:02}{:02}Z 35005KT 10SM SCT055 29/21 A2999\n",
+                    minute / 60 % 24,
+                    minute % 60,
+                    minute / 60 % 24,
+                    minute % 60
+                )
+            })
+            .chain(["\n\nKTPA 010053Z\n".to_string()])
+            .collect::<String>();
+        let results = parse_timestamped_lines(archive.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(results.len(), 10_001);
+        let (time, _) = results[9_999].as_ref().unwrap();
+        assert_eq!(time.to_rfc3339(), "2020-08-01T22:39:00+00:00");
+        assert!(matches!(
+            results[10_000],
+            Err(ArchiveError::Timestamp { line: 10_003 })
+        ));
+
+        let mut stats = BatchStats::default();
+        results.iter().for_each(|result| stats.add_archived(result));
+        assert_eq!((stats.total, stats.failures), (10_001, 1));
+        assert!(stats.failure_columns.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use weather_reports::archive::decompress;
+
+/// Text read from stdin or a file
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+/// Reads every path, where `-` is stdin and directories are read recursively in name order
+///
+/// Each file in a `.tar` or `.tar.zst` archive is a separate source.
+pub fn read_sources(paths: &[PathBuf]) -> io::Result<Vec<Source>> {
+    let mut sources = vec![];
+    for path in paths {
+        if path == Path::new("-") {
+            let mut text = String::default();
+            io::stdin().read_to_string(&mut text)?;
+            sources.push(Source {
+                name: "<stdin>".to_string(),
+                text,
+            });
+        } else {
+            read_path(path, &mut sources)?;
+        }
+    }
+    Ok(sources)
+}
+
+fn read_path(path: &Path, sources: &mut Vec<Source>) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for entry in entries {
+            read_path(&entry, sources)?;
+        }
+    } else if path.to_string_lossy().ends_with(".tar.zst") {
+        read_tar(decompress(fs::File::open(path)?)?, sources)?;
+    } else if path.to_string_lossy().ends_with(".tar") {
+        read_tar(fs::File::open(path)?, sources)?;
+    } else {
+        sources.push(read_file(path)?);
+    }
+    Ok(())
+}
+
+/// zstd and gzip files are decompressed
+fn read_file(path: &Path) -> io::Result<Source> {
+    let mut bytes = vec![];
+    decompress(fs::File::open(path)?)?.read_to_end(&mut bytes)?;
+    Ok(Source {
+        name: path.display().to_string(),
+        text: String::from_utf8_lossy(&bytes).into_owned(),
+    })
+}
+
+fn read_tar(archive: impl Read, sources: &mut Vec<Source>) -> io::Result<()> {
+    for entry in tar::Archive::new(archive).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let mut bytes = vec![];
+        entry.read_to_end(&mut bytes)?;
+        sources.push(Source {
+            name: entry.path()?.display().to_string(),
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+        });
+    }
+    Ok(())
+}
+
+/// A single report within a [Source]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Report<'a> {
+    /// Starting at 1
+    pub line: usize,
+    /// Byte offset of the report in its source
+    pub offset: usize,
+    pub text: &'a str,
+}
+
+/// Splits text into reports
+///
+/// Reports end at an `=`, a blank line or a line that does not start with whitespace,
+/// so indented lines continue the previous report as in WMO bulletins.
+pub fn reports(text: &str) -> Vec<Report<'_>> {
+    let mut reports = vec![];
+    let mut current: Option<Report> = None;
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let line_offset = offset;
+        offset += line.len();
+
+        let is_continuation = line.starts_with(char::is_whitespace) && !line.trim().is_empty();
+        if !is_continuation {
+            reports.extend(current.take());
+        }
+        let content = line.trim_start();
+        if content.trim_end().is_empty() {
+            continue;
+        }
+        let content_offset = line_offset + line.len() - content.len();
+        let report = current.get_or_insert(Report {
+            line: index + 1,
+            offset: content_offset,
+            text: "",
+        });
+        let end = match content.find('=') {
+            Some(terminator) => content_offset + terminator,
+            None => content_offset + content.trim_end().len(),
+        };
+        report.text = &text[report.offset..end];
+        if content.contains('=') {
+            reports.extend(current.take());
+        }
+    }
+    reports.extend(current);
+    reports.retain(|report| !report.text.trim().is_empty());
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_reports() {
+        let text = "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004\n\nMETAR CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990 RMK CI5\n   SLP122 DENSITY ALT 3500FT=\nKTPA 290353Z 00000KT 10SM CLR 27/24 A3001= ignored\n";
+        let reports = reports(text);
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| (report.line, report.text))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004"),
+                (
+                    3,
+                    "METAR CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990 RMK CI5\n   SLP122 DENSITY ALT 3500FT"
+                ),
+                (5, "KTPA 290353Z 00000KT 10SM CLR 27/24 A3001"),
+            ]
+        );
+        for report in reports {
+            assert_eq!(&text[report.offset..][..report.text.len()], report.text);
+        }
+    }
+}
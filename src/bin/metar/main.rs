@@ -0,0 +1,300 @@
+//! Decodes, validates and explains METAR/SPECI reports
+//!
+//! Reports are read from stdin, files or directories, one per line or terminated by `=`.
+use clap::{Parser, Subcommand, ValueEnum};
+use codespan_reporting::{
+    files::SimpleFile,
+    term::{
+        emit,
+        termcolor::{ColorChoice, StandardStream},
+    },
+};
+use std::{
+    error::Error,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use weather_reports::{
+    corpus::CoverageReport,
+    describe::{describe_with_catalog, Catalog, UnitSystem},
+    parse::{into_rich_diagnostic, metar, metar_with_recovery},
+    tokens::MetarReport,
+};
+
+mod input;
+mod stats;
+
+use input::{read_sources, reports, Source};
+
+/// Decode, validate and explain METAR/SPECI reports
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the decoded form of each report
+    Decode {
+        #[arg(long, value_enum, default_value_t = Format::Pretty)]
+        format: Format,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Check that every report parses, printing diagnostics for those that do not
+    Validate {
+        /// Only print the summary
+        #[arg(long, short)]
+        quiet: bool,
+        /// Skip groups that cannot be parsed to report every error in a report
+        #[arg(long)]
+        recover: bool,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Describe each report in plain language
+    Explain {
+        /// Language code of the description, i.e. `fr`
+        #[arg(long, default_value = "en")]
+        language: String,
+        #[arg(long, value_enum, default_value_t = Units::Aviation)]
+        units: Units,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Measure coverage over saved Ogimet pages, one entry per page
+    ///
+    /// Prints the coverage as tab separated values, which can be saved as a baseline for later runs.
+    Corpus {
+        /// Saved coverage to compare against, exiting with an error if coverage regressed
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        /// Print diagnostics for reports that could not be parsed
+        #[arg(long)]
+        diagnostics: bool,
+        #[arg(long, value_enum, default_value_t = Format::Pretty)]
+        format: Format,
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Count how often each group is present in the reports
+    Stats {
+        #[arg(long, value_enum, default_value_t = Format::Pretty)]
+        format: Format,
+        #[command(flatten)]
+        input: Input,
+    },
+}
+
+#[derive(clap::Args)]
+struct Input {
+    /// Files or directories to read, where `-` is stdin
+    #[arg(default_value = "-")]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Pretty,
+    /// One JSON value per line
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Units {
+    Metric,
+    Aviation,
+    Imperial,
+}
+
+impl From<Units> for UnitSystem {
+    fn from(units: Units) -> Self {
+        match units {
+            Units::Metric => UnitSystem::METRIC,
+            Units::Aviation => UnitSystem::AVIATION,
+            Units::Imperial => UnitSystem::IMPERIAL,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let input = match &cli.command {
+        Command::Decode { input, .. }
+        | Command::Validate { input, .. }
+        | Command::Explain { input, .. }
+        | Command::Corpus { input, .. }
+        | Command::Stats { input, .. } => input,
+    };
+    let sources = match read_sources(&input.paths) {
+        Ok(sources) => sources,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut stdout = io::stdout().lock();
+    let success = match cli.command {
+        Command::Decode { format, .. } => for_each_report(&sources, |report| match format {
+            Format::Pretty => writeln!(stdout, "{:#?}", report),
+            Format::Json => writeln!(stdout, "{}", serde_json::to_string(report).unwrap()),
+        }),
+        Command::Validate { quiet, recover, .. } => {
+            let mut parsed = 0;
+            let mut total = 0;
+            for source in &sources {
+                for report in reports(&source.text) {
+                    total += 1;
+                    let errors = if recover {
+                        metar_with_recovery(report.text).1
+                    } else {
+                        metar(report.text).err().into_iter().collect()
+                    };
+                    if errors.is_empty() {
+                        parsed += 1;
+                    } else if !quiet {
+                        for err in &errors {
+                            emit_error(&source.name, &source.text, report.offset, err);
+                        }
+                    }
+                }
+            }
+            eprintln!("{} of {} reports parsed", parsed, total);
+            Ok(parsed == total)
+        }
+        Command::Explain {
+            language, units, ..
+        } => {
+            let catalog = match Catalog::for_language(&language) {
+                Some(catalog) => catalog,
+                None => {
+                    eprintln!("error: no catalog for language {:?}", language);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let units = units.into();
+            for_each_report(&sources, |report| {
+                writeln!(stdout, "{}", describe_with_catalog(report, &units, catalog))
+            })
+        }
+        Command::Corpus {
+            baseline,
+            diagnostics,
+            format,
+            ..
+        } => {
+            let baseline = match baseline.map(|path| read_baseline(&path)).transpose() {
+                Ok(baseline) => baseline,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let report = CoverageReport::of_ogimet_pages(
+                sources
+                    .iter()
+                    .map(|source| (source.name.as_str(), source.text.as_str())),
+                |name, report, err| {
+                    if diagnostics {
+                        emit_error(name, report, 0, err)
+                    }
+                },
+            );
+            match format {
+                Format::Pretty => write!(stdout, "{}", report),
+                Format::Json => writeln!(stdout, "{}", serde_json::to_string(&report).unwrap()),
+            }
+            .map(|_| match baseline {
+                Some(baseline) => {
+                    let changes = report.diff(&baseline);
+                    for change in &changes {
+                        eprintln!("{}", change);
+                    }
+                    eprintln!(
+                        "total: {:.2}% -> {:.2}%",
+                        baseline.total().percentage(),
+                        report.total().percentage()
+                    );
+                    !changes.iter().any(|change| change.is_regression())
+                }
+                None => true,
+            })
+        }
+        Command::Stats { format, .. } => {
+            let mut stats = stats::Stats::default();
+            for source in &sources {
+                for report in reports(&source.text) {
+                    stats.add(metar(report.text).ok().as_ref());
+                }
+            }
+            match format {
+                Format::Pretty => write!(stdout, "{}", stats),
+                Format::Json => writeln!(stdout, "{}", serde_json::to_string(&stats).unwrap()),
+            }
+            .map(|_| true)
+        }
+    };
+
+    match success {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // Output was closed early, i.e. piped into `head`
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Parses every report, printing diagnostics for failures
+///
+/// Returns whether all reports were parsed.
+fn for_each_report(
+    sources: &[Source],
+    mut f: impl FnMut(&MetarReport) -> io::Result<()>,
+) -> io::Result<bool> {
+    let mut success = true;
+    for source in sources {
+        for report in reports(&source.text) {
+            match metar(report.text) {
+                Ok(parsed) => f(&parsed)?,
+                Err(err) => {
+                    success = false;
+                    emit_error(&source.name, &source.text, report.offset, &err);
+                }
+            }
+        }
+    }
+    Ok(success)
+}
+
+fn read_baseline(path: &Path) -> Result<CoverageReport, Box<dyn Error>> {
+    Ok(fs::read_to_string(path)?.parse()?)
+}
+
+fn emit_error(
+    name: &str,
+    text: &str,
+    offset: usize,
+    err: &peg::error::ParseError<peg::str::LineCol>,
+) {
+    let mut diagnostic = into_rich_diagnostic(&text[offset..], err);
+    for label in &mut diagnostic.labels {
+        label.range = label.range.start + offset..label.range.end + offset;
+    }
+    let mut writer = StandardStream::stderr(ColorChoice::Auto);
+    emit(
+        &mut writer,
+        &codespan_reporting::term::Config::default(),
+        &SimpleFile::new(name, text),
+        &diagnostic,
+    )
+    .unwrap();
+}
@@ -0,0 +1,134 @@
+use serde::Serialize;
+use std::fmt;
+
+use weather_reports::tokens::MetarReport;
+
+/// How often each group of a report is present
+#[derive(Default, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub parsed: usize,
+    pub groups: Vec<Group>,
+}
+
+#[derive(Serialize)]
+pub struct Group {
+    pub name: &'static str,
+    /// Number of parsed reports that include the group
+    pub count: usize,
+}
+
+impl Stats {
+    /// Counts a report, or a failure if it could not be parsed
+    pub fn add(&mut self, report: Option<&MetarReport>) {
+        self.total += 1;
+        let report = match report {
+            Some(report) => report,
+            None => return,
+        };
+        self.parsed += 1;
+
+        let groups = [
+            ("report name", report.kind.is_some()),
+            ("observation time", report.observation_time.is_some()),
+            (
+                "validity range",
+                report.observation_validity_range.is_some(),
+            ),
+            ("observation flags", !report.observation_flags.is_empty()),
+            ("wind", report.wind.is_some()),
+            ("visibility", report.visibility.is_some()),
+            (
+                "runway visual range",
+                !report.runway_visibilities.is_empty(),
+            ),
+            ("runway state", !report.runway_reports.is_empty()),
+            ("weather", !report.weather.is_empty()),
+            ("cloud cover", !report.cloud_cover.is_empty()),
+            ("CAVOK", report.cavok),
+            ("temperatures", report.temperatures.is_some()),
+            ("pressure", report.pressure.is_some()),
+            (
+                "accumulated rainfall",
+                report.accumulated_rainfall.is_some(),
+            ),
+            ("color", report.color.is_some()),
+            ("recent weather", !report.recent_weather.is_empty()),
+            ("wind shear", !report.wind_shear.is_empty()),
+            ("water conditions", report.water_conditions.is_some()),
+            ("trends", !report.trends.is_empty()),
+            ("remark", report.remark.is_some()),
+            ("maintenance needed", report.maintenance_needed),
+        ];
+        if self.groups.is_empty() {
+            self.groups = groups
+                .iter()
+                .map(|(name, _)| Group { name, count: 0 })
+                .collect();
+        }
+        for (group, (_, present)) in self.groups.iter_mut().zip(groups) {
+            group.count += present as usize;
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} reports parsed ({:.2}% coverage)",
+            self.parsed,
+            self.total,
+            percentage(self.parsed, self.total)
+        )?;
+        for group in &self.groups {
+            writeln!(
+                f,
+                "{:>22} {:>8} {:>7.2}%",
+                group.name,
+                group.count,
+                percentage(group.count, self.parsed)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn percentage(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.
+    } else {
+        count as f64 / total as f64 * 100.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use weather_reports::parse::metar;
+
+    #[test]
+    fn count_groups() {
+        let mut stats = Stats::default();
+        for report in [
+            "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004",
+            "KSEA 290453Z 01008KT 2SM -RA OVC010 19/09 A3004 RMK AO2",
+            "KSEA 2903Z garbage",
+        ] {
+            stats.add(metar(report).ok().as_ref());
+        }
+        assert_eq!((stats.total, stats.parsed), (3, 2));
+        let count = |name| {
+            stats
+                .groups
+                .iter()
+                .find(|group| group.name == name)
+                .unwrap()
+                .count
+        };
+        assert_eq!(count("cloud cover"), 2);
+        assert_eq!(count("weather"), 1);
+        assert_eq!(count("remark"), 1);
+        assert_eq!(count("trends"), 0);
+    }
+}
@@ -0,0 +1,340 @@
+//! Parser coverage over a corpus of saved reports
+//!
+//! A [CoverageReport] can be saved and compared against later runs, so grammar changes can be judged by
+//! how much coverage they gain or lose.
+//!
+//! ```
+//! use weather_reports::corpus::{Coverage, CoverageReport};
+//!
+//! let baseline: CoverageReport = "name\ttotal\tfailures\nCanada.txt\t10\t2\n".parse().unwrap();
+//! let current = CoverageReport {
+//!     entries: vec![Coverage::of("Canada.txt", ["CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990"], |_, _| {})],
+//! };
+//! let changes = current.diff(&baseline);
+//! assert_eq!(changes.len(), 1);
+//! assert!(!changes[0].is_regression());
+//! ```
+use peg::{error::ParseError, str::LineCol};
+use std::{fmt, str::FromStr};
+
+use crate::{
+    ogimet,
+    parse::{metar, sao},
+};
+
+/// Number of reports in a page or file and how many could not be parsed
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Coverage {
+    /// Country or station the reports are from, usually the name of the file
+    pub name: String,
+    pub total: usize,
+    pub failures: usize,
+}
+
+impl Coverage {
+    /// Parses each report, calling `on_failure` with the reports that could not be parsed
+    ///
+    /// Legacy SAO observations are parsed as such instead of as METARs.
+    pub fn of<'a>(
+        name: impl Into<String>,
+        reports: impl IntoIterator<Item = &'a str>,
+        mut on_failure: impl FnMut(&'a str, &ParseError<LineCol>),
+    ) -> Self {
+        let mut coverage = Self {
+            name: name.into(),
+            total: 0,
+            failures: 0,
+        };
+        for report in reports {
+            coverage.total += 1;
+            if let Err(err) = parse_report(report) {
+                coverage.failures += 1;
+                on_failure(report, &err);
+            }
+        }
+        coverage
+    }
+
+    /// Percentage of reports that were parsed
+    pub fn percentage(&self) -> f64 {
+        if self.total == 0 {
+            100.
+        } else {
+            100. - self.failures as f64 / self.total as f64 * 100.
+        }
+    }
+}
+
+fn parse_report(report: &str) -> Result<(), ParseError<LineCol>> {
+    let is_sao = matches!(
+        report.split_whitespace().nth(1),
+        Some("SA") | Some("SP") | Some("RS")
+    );
+    if is_sao {
+        sao(report).map(drop)
+    } else {
+        metar(report).map(drop)
+    }
+}
+
+/// Coverage of several countries or stations
+///
+/// Written and read as tab separated values with a header, one [Coverage] per line.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CoverageReport {
+    pub entries: Vec<Coverage>,
+}
+
+impl CoverageReport {
+    /// [Coverage::of] each saved Ogimet page, given as its name and HTML
+    ///
+    /// Pages without any reports are skipped.
+    pub fn of_ogimet_pages<'a>(
+        pages: impl IntoIterator<Item = (&'a str, &'a str)>,
+        mut on_failure: impl FnMut(&'a str, &'a str, &ParseError<LineCol>),
+    ) -> Self {
+        Self {
+            entries: pages
+                .into_iter()
+                .filter_map(|(name, html)| {
+                    if html.contains("No METAR/SPECI reports") {
+                        return None;
+                    }
+                    Some(Coverage::of(
+                        name,
+                        ogimet::reports(html).map(|report| report.text),
+                        |report, err| on_failure(name, report, err),
+                    ))
+                })
+                .collect(),
+        }
+    }
+
+    /// Sum of all entries
+    pub fn total(&self) -> Coverage {
+        Coverage {
+            name: "total".to_string(),
+            total: self.entries.iter().map(|entry| entry.total).sum(),
+            failures: self.entries.iter().map(|entry| entry.failures).sum(),
+        }
+    }
+
+    /// Entries that differ from the baseline, including ones that were added or removed
+    pub fn diff(&self, baseline: &Self) -> Vec<CoverageChange> {
+        let mut changes = vec![];
+        for entry in &self.entries {
+            let before = baseline
+                .entries
+                .iter()
+                .find(|before| before.name == entry.name);
+            if before != Some(entry) {
+                changes.push(CoverageChange {
+                    name: entry.name.clone(),
+                    baseline: before.cloned(),
+                    current: Some(entry.clone()),
+                });
+            }
+        }
+        for before in &baseline.entries {
+            if !self.entries.iter().any(|entry| entry.name == before.name) {
+                changes.push(CoverageChange {
+                    name: before.name.clone(),
+                    baseline: Some(before.clone()),
+                    current: None,
+                });
+            }
+        }
+        changes
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "name\ttotal\tfailures\tcoverage")?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{:.2}",
+                entry.name,
+                entry.total,
+                entry.failures,
+                entry.percentage()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A line of a saved [CoverageReport] could not be read
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CoverageReportError {
+    /// Starting at 1
+    pub line: usize,
+}
+
+impl fmt::Display for CoverageReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid coverage on line {}", self.line)
+    }
+}
+
+impl std::error::Error for CoverageReportError {}
+
+impl FromStr for CoverageReport {
+    type Err = CoverageReportError;
+
+    /// The header and coverage column are optional
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = vec![];
+        for (index, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || (index == 0 && line.starts_with("name\t")) {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let mut next = || {
+                columns
+                    .next()
+                    .ok_or(CoverageReportError { line: index + 1 })
+            };
+            let name = next()?.to_string();
+            let total = next()?.parse().ok();
+            let failures = next()?.parse().ok();
+            match (total, failures) {
+                (Some(total), Some(failures)) if failures <= total => entries.push(Coverage {
+                    name,
+                    total,
+                    failures,
+                }),
+                _ => return Err(CoverageReportError { line: index + 1 }),
+            }
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Difference in coverage of a country or station between two runs
+#[derive(Clone, PartialEq, Debug)]
+pub struct CoverageChange {
+    pub name: String,
+    /// Absent if the entry is new
+    pub baseline: Option<Coverage>,
+    /// Absent if the entry was removed
+    pub current: Option<Coverage>,
+}
+
+impl CoverageChange {
+    /// Coverage decreased or more reports failed
+    pub fn is_regression(&self) -> bool {
+        match (&self.baseline, &self.current) {
+            (Some(baseline), Some(current)) => {
+                current.percentage() < baseline.percentage() || current.failures > baseline.failures
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for CoverageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.baseline, &self.current) {
+            (Some(baseline), Some(current)) => write!(
+                f,
+                "{}: {:.2}% -> {:.2}% ({} -> {} failures)",
+                self.name,
+                baseline.percentage(),
+                current.percentage(),
+                baseline.failures,
+                current.failures
+            ),
+            (None, Some(current)) => {
+                write!(f, "{}: new, {:.2}%", self.name, current.percentage())
+            }
+            (Some(baseline), None) => {
+                write!(
+                    f,
+                    "{}: removed, was {:.2}%",
+                    self.name,
+                    baseline.percentage()
+                )
+            }
+            (None, None) => write!(f, "{}: unchanged", self.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<html><pre>
+# Latest METAR/SPECI from Canada
+###################################
+202108300100 TUX SA 0100 AUTO8 M M M 141/21/20/1905/M/0043 PCPN 4.3MM
+                        PAST HR 3003 28MM=
+202108300100 METAR CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990 RMK CI5
+                        SLP122 DENSITY ALT 3500FT=
+202108300100 METAR CWXX 3001Z garbage=
+</pre></html>";
+
+    #[test]
+    fn ogimet_page_coverage() {
+        let mut failures = vec![];
+        let report = CoverageReport::of_ogimet_pages(
+            [("Canada.txt", PAGE), ("Chad.txt", "No METAR/SPECI reports")],
+            |name, report, _| failures.push((name, report.trim())),
+        );
+        assert_eq!(
+            report.entries,
+            vec![Coverage {
+                name: "Canada.txt".to_string(),
+                total: 3,
+                failures: 1,
+            }]
+        );
+        assert_eq!(failures, vec![("Canada.txt", "METAR CWXX 3001Z garbage")]);
+    }
+
+    #[test]
+    fn save_and_diff() {
+        let current = CoverageReport {
+            entries: vec![
+                Coverage {
+                    name: "Canada.txt".to_string(),
+                    total: 10,
+                    failures: 1,
+                },
+                Coverage {
+                    name: "Chad.txt".to_string(),
+                    total: 4,
+                    failures: 0,
+                },
+            ],
+        };
+        assert_eq!(current.to_string().parse(), Ok(current.clone()));
+        assert_eq!(current.total().total, 14);
+
+        let baseline: CoverageReport = "Canada.txt\t10\t0\nFiji.txt\t3\t3".parse().unwrap();
+        let changes = current.diff(&baseline);
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.to_string(), change.is_regression()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "Canada.txt: 100.00% -> 90.00% (0 -> 1 failures)".to_string(),
+                    true
+                ),
+                ("Chad.txt: new, 100.00%".to_string(), false),
+                ("Fiji.txt: removed, was 0.00%".to_string(), false),
+            ]
+        );
+
+        assert_eq!(
+            "Canada.txt\t10".parse::<CoverageReport>(),
+            Err(CoverageReportError { line: 1 })
+        );
+    }
+}
@@ -0,0 +1,822 @@
+//! Plain language descriptions of parsed reports
+//!
+//! Phrases come from a [Catalog], which is English by default.
+//! French, German, Spanish, and Portuguese catalogs are available with the `locales` feature.
+//!
+//! ```
+//! use weather_reports::{describe::{describe, UnitSystem}, parse::metar};
+//!
+//! let report = metar("KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004").unwrap();
+//! assert_eq!(
+//!     describe(&report, &UnitSystem::AVIATION),
+//!     "Observed at KSEA on day 29 at 03:53 UTC. Wind from 010° at 8 knots. Visibility 10 statute miles. \
+//!     Scattered clouds at 20,000 feet. Temperature 19 °C, dew point 9 °C. Pressure 30.04 inches of mercury."
+//! );
+//! ```
+use uom::si::{
+    angle::degree,
+    f64::{Angle, Length, Pressure, ThermodynamicTemperature, Velocity},
+    length::{foot, inch, kilometer, meter, mile, millimeter},
+    pressure::{hectopascal, inch_of_mercury},
+    thermodynamic_temperature::{degree_celsius, degree_fahrenheit},
+    velocity::{kilometer_per_hour, knot, meter_per_second, mile_per_hour},
+};
+
+use crate::tokens::*;
+
+mod catalog;
+pub use catalog::{Catalog, CatalogError};
+
+/// Units used when describing a report
+///
+/// Use one of the provided systems or combine units as needed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UnitSystem {
+    pub speed: SpeedDisplayUnit,
+    /// Used for visibility
+    pub distance: DistanceDisplayUnit,
+    /// Used for cloud bases and depths
+    pub height: HeightDisplayUnit,
+    pub temperature: TemperatureDisplayUnit,
+    pub pressure: PressureDisplayUnit,
+}
+
+impl UnitSystem {
+    pub const METRIC: Self = Self {
+        speed: SpeedDisplayUnit::KilometersPerHour,
+        distance: DistanceDisplayUnit::Kilometers,
+        height: HeightDisplayUnit::Meters,
+        temperature: TemperatureDisplayUnit::Celsius,
+        pressure: PressureDisplayUnit::Hectopascals,
+    };
+    /// As used by pilots in the United States
+    pub const AVIATION: Self = Self {
+        speed: SpeedDisplayUnit::Knots,
+        distance: DistanceDisplayUnit::StatuteMiles,
+        height: HeightDisplayUnit::Feet,
+        temperature: TemperatureDisplayUnit::Celsius,
+        pressure: PressureDisplayUnit::InchesOfMercury,
+    };
+    pub const IMPERIAL: Self = Self {
+        speed: SpeedDisplayUnit::MilesPerHour,
+        distance: DistanceDisplayUnit::StatuteMiles,
+        height: HeightDisplayUnit::Feet,
+        temperature: TemperatureDisplayUnit::Fahrenheit,
+        pressure: PressureDisplayUnit::InchesOfMercury,
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpeedDisplayUnit {
+    Knots,
+    MetersPerSecond,
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DistanceDisplayUnit {
+    /// Switches to meters for distances under 5 kilometers
+    Kilometers,
+    Meters,
+    StatuteMiles,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeightDisplayUnit {
+    Meters,
+    Feet,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TemperatureDisplayUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PressureDisplayUnit {
+    Hectopascals,
+    InchesOfMercury,
+}
+
+/// Describes a METAR in English sentences
+pub fn describe(report: &MetarReport, units: &UnitSystem) -> String {
+    describe_with_catalog(report, units, Catalog::english())
+}
+
+/// Describes a METAR in sentences using the phrases of a [Catalog]
+pub fn describe_with_catalog(
+    report: &MetarReport,
+    units: &UnitSystem,
+    catalog: &Catalog,
+) -> String {
+    Describer { units, catalog }.describe(report)
+}
+
+struct Describer<'a> {
+    units: &'a UnitSystem,
+    catalog: &'a Catalog,
+}
+
+impl<'a> Describer<'a> {
+    fn describe(&self, report: &MetarReport) -> String {
+        let mut sentences = vec![];
+
+        sentences.push(match report.observation_time {
+            Some(time) => {
+                let military_time = self.military_time(time.time);
+                self.message(
+                    "observed-time",
+                    &[
+                        ("station", &report.identifier),
+                        ("day", &time.day_of_month.to_string()),
+                        (
+                            "time",
+                            &if time.is_zulu {
+                                self.message("utc", &[("time", &military_time)])
+                            } else {
+                                military_time
+                            },
+                        ),
+                    ],
+                )
+            }
+            None => self.message("observed", &[("station", &report.identifier)]),
+        });
+        if report.observation_flags.contains(&ObservationFlag::Auto) {
+            sentences.push(self.message("automated", &[]));
+        }
+        if let Some(wind) = report.wind {
+            sentences.push(self.wind(&wind));
+        }
+        if report.cavok {
+            sentences.push(self.message("cavok", &[]));
+        }
+        if let Some(visibility) = report.visibility {
+            sentences.extend(self.visibility(&visibility));
+        }
+        for runway_visibility in &report.runway_visibilities {
+            sentences.push(self.runway_visibility(runway_visibility));
+        }
+        if !report.weather.is_empty() {
+            sentences.push(self.weather_sequence(&report.weather));
+        }
+        for cloud_cover in &report.cloud_cover {
+            sentences.push(self.cloud_cover(cloud_cover));
+        }
+        if let Some(temperatures) = report.temperatures {
+            let air = self.temperature(temperatures.air);
+            sentences.push(match temperatures.dewpoint {
+                Some(dewpoint) => self.message(
+                    "temperature-dewpoint",
+                    &[("air", &air), ("dewpoint", &self.temperature(dewpoint))],
+                ),
+                None => self.message("temperature", &[("air", &air)]),
+            });
+        }
+        if let Some(pressure) = report.pressure {
+            sentences.push(self.message("pressure", &[("pressure", &self.pressure(pressure))]));
+        }
+        if let Some(accumulated_rainfall) = report.accumulated_rainfall {
+            sentences.push(self.message(
+                "rainfall",
+                &[
+                    ("recent", &self.rainfall(accumulated_rainfall.recent)),
+                    ("past", &self.rainfall(accumulated_rainfall.past)),
+                ],
+            ));
+        }
+        if !report.recent_weather.is_empty() {
+            sentences.push(self.message(
+                "recent-weather",
+                &[("weather", &self.weather_sequence(&report.recent_weather))],
+            ));
+        }
+        for wind_shear in &report.wind_shear {
+            sentences.push(match wind_shear {
+                WindShear::AllRunways => self.message("wind-shear-all-runways", &[]),
+                WindShear::Runway(designator) => {
+                    self.message("wind-shear", &[("runway", designator)])
+                }
+            });
+        }
+        for runway_report in &report.runway_reports {
+            sentences.push(self.runway_report(runway_report));
+        }
+        if let Some(water_conditions) = report.water_conditions {
+            sentences.extend(self.water_conditions(&water_conditions));
+        }
+        if let Some(color) = report.color {
+            sentences.push(self.color(&color));
+        }
+        for trend in &report.trends {
+            sentences.push(self.trend(trend));
+        }
+        if let Some(field_pressure) = report.field_pressure {
+            sentences.push(self.message(
+                "field-pressure",
+                &[("pressure", &self.pressure(field_pressure.pressure))],
+            ));
+        }
+        for obscured in &report.obscured {
+            sentences.push(self.message(
+                "obscured",
+                &[(
+                    "feature",
+                    &self.message(&format!("obscured-{}", <&str>::from(*obscured)), &[]),
+                )],
+            ));
+        }
+        if let Some(remark) = &report.remark {
+            let remark = remark.strip_prefix("RMK").unwrap_or(remark).trim();
+            sentences.push(self.message("remark", &[("remark", remark)]));
+        }
+        if report.maintenance_needed {
+            sentences.push(self.message("maintenance", &[]));
+        }
+
+        sentences
+            .iter()
+            .map(|sentence| format!("{}.", capitalize(sentence)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn message(&self, key: &str, args: &[(&str, &str)]) -> String {
+        self.catalog.format(key, args)
+    }
+
+    fn wind(&self, wind: &Wind) -> String {
+        if wind.is_calm() == Some(true) {
+            return self.message("wind-calm", &[]);
+        }
+        let mut acc = match (wind.direction, wind.speed) {
+            (Some(direction), Some(speed)) => self.message(
+                "wind",
+                &[
+                    ("direction", &self.bearing(direction)),
+                    ("speed", &self.speed(speed)),
+                ],
+            ),
+            (Some(direction), None) => {
+                self.message("wind-direction", &[("direction", &self.bearing(direction))])
+            }
+            (None, Some(speed)) => self.message("wind-variable", &[("speed", &self.speed(speed))]),
+            (None, None) => self.message("wind-variable-direction", &[]),
+        };
+        if let Some(peak_gust) = wind.peak_gust {
+            acc = self.message(
+                "wind-gust",
+                &[("wind", &acc), ("speed", &self.speed(peak_gust))],
+            );
+        }
+        if let Some((from, to)) = wind.variance {
+            acc = self.message(
+                "wind-variance",
+                &[
+                    ("wind", &acc),
+                    ("from", &self.bearing(from)),
+                    ("to", &self.bearing(to)),
+                ],
+            );
+        }
+        acc
+    }
+
+    fn visibility(&self, visibility: &Visibility) -> Vec<String> {
+        let mut sentences = vec![];
+        if let Some(prevailing) = visibility.prevailing {
+            sentences.push(self.message(
+                "visibility",
+                &[("distance", &self.raw_visibility(&prevailing))],
+            ));
+        }
+        let minimum = match visibility.minimum {
+            Some(DirectionalOrRawVisiblity::Raw(raw)) => Some(self.raw_visibility(&raw)),
+            Some(DirectionalOrRawVisiblity::Directional(directional)) => {
+                Some(self.directional_visibility(&directional))
+            }
+            None => None,
+        };
+        if let Some(minimum) = minimum {
+            sentences.push(self.message("visibility-minimum", &[("distance", &minimum)]));
+        }
+        if let Some(maximum) = visibility.maximum_directional {
+            sentences.push(self.message(
+                "visibility-maximum",
+                &[("distance", &self.directional_visibility(&maximum))],
+            ));
+        }
+        sentences
+    }
+
+    fn directional_visibility(&self, directional: &DirectionalVisibility) -> String {
+        self.message(
+            "visibility-directional",
+            &[
+                ("distance", &self.raw_visibility(&directional.distance)),
+                (
+                    "direction",
+                    &self.message(
+                        &format!("compass-{}", <&str>::from(directional.direction)),
+                        &[],
+                    ),
+                ),
+            ],
+        )
+    }
+
+    fn raw_visibility(&self, raw: &RawVisibility) -> String {
+        match raw.out_of_range {
+            Some(out_of_range) => self.message(
+                &format!("out-of-range-{}", <&str>::from(out_of_range)),
+                &[("distance", &self.distance(raw.distance))],
+            ),
+            // 9999 is the largest visibility that can be reported in meters and means 10 km or more
+            None if matches!(raw.unit, None | Some(DistanceUnit::Meters))
+                && matches!(
+                    raw.reported_distance,
+                    ReportedValue::Decimal {
+                        digits: 9999,
+                        scale: 0,
+                        ..
+                    }
+                ) =>
+            {
+                self.message(
+                    "out-of-range-or-more",
+                    &[("distance", &self.distance(Length::new::<kilometer>(10.)))],
+                )
+            }
+            None => self.distance(raw.distance),
+        }
+    }
+
+    fn runway_visibility(&self, runway_visibility: &RunwayVisibility) -> String {
+        let mut visibility = match runway_visibility.visibility {
+            VisibilityType::Fixed(raw) => self.raw_visibility(&raw),
+            VisibilityType::Varying { lower, upper } => self.message(
+                "runway-visibility-varying",
+                &[
+                    ("lower", &self.raw_visibility(&lower)),
+                    ("upper", &self.raw_visibility(&upper)),
+                ],
+            ),
+        };
+        if let Some(trend) = runway_visibility.trend {
+            visibility = self.message(
+                &format!("visibility-trend-{}", <&str>::from(trend)),
+                &[("visibility", &visibility)],
+            );
+        }
+        self.message(
+            "runway-visibility",
+            &[
+                ("runway", &runway_visibility.designator),
+                ("visibility", &visibility),
+            ],
+        )
+    }
+
+    fn weather_sequence(&self, weather: &[Weather]) -> String {
+        weather
+            .iter()
+            .map(|weather| self.weather(weather))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn weather(&self, weather: &Weather) -> String {
+        let condition = match &weather.condition {
+            Some(Condition::Precipitation(precipitation)) => precipitation
+                .iter()
+                .map(|precipitation| {
+                    self.message(
+                        &format!("precipitation-{}", <&str>::from(*precipitation)),
+                        &[],
+                    )
+                })
+                .reduce(|first, second| {
+                    self.message(
+                        "precipitation-and",
+                        &[("first", &first), ("second", &second)],
+                    )
+                }),
+            Some(Condition::Obscuration(obscuration)) => {
+                Some(self.message(&format!("obscuration-{}", <&str>::from(*obscuration)), &[]))
+            }
+            Some(Condition::Other(other)) => {
+                Some(self.message(&format!("other-{}", <&str>::from(*other)), &[]))
+            }
+            None => None,
+        };
+
+        let mut phrase = match (weather.descriptor, condition) {
+            (Some(descriptor), Some(condition)) => self.message(
+                &format!("descriptor-{}", <&str>::from(descriptor)),
+                &[("condition", &condition)],
+            ),
+            (Some(descriptor), None) => self.message(
+                &format!("descriptor-{}-alone", <&str>::from(descriptor)),
+                &[],
+            ),
+            (None, Some(condition)) => condition,
+            (None, None) => self.message("weather-unknown", &[]),
+        };
+        if weather.intensity != Intensity::Moderate {
+            phrase = self.message(
+                &format!("intensity-{}", <&str>::from(weather.intensity)),
+                &[("weather", &phrase)],
+            );
+        }
+        if weather.vicinity {
+            phrase = self.message("vicinity", &[("weather", &phrase)]);
+        }
+        phrase
+    }
+
+    fn cloud_cover(&self, cloud_cover: &CloudCover) -> String {
+        let coverage = self.message(
+            &format!("cloud-{}", <&str>::from(cloud_cover.coverage)),
+            &[],
+        );
+        let mut acc = match cloud_cover.base {
+            Some(base) if cloud_cover.coverage == CloudCoverage::VerticalVisibility => {
+                self.message("cloud-VV-base", &[("height", &self.height(base))])
+            }
+            Some(base) => self.message(
+                "cloud-base",
+                &[("coverage", &coverage), ("height", &self.height(base))],
+            ),
+            None => coverage,
+        };
+        if let Some(cloud_type) = cloud_cover.cloud_type {
+            acc = self.message(
+                "cloud-type",
+                &[
+                    ("cloud", &acc),
+                    (
+                        "type",
+                        &self.message(&format!("cloud-type-{}", <&str>::from(cloud_type)), &[]),
+                    ),
+                ],
+            );
+        }
+        acc
+    }
+
+    fn runway_report(&self, runway_report: &RunwayReport) -> String {
+        let state = match runway_report.report_info {
+            RunwayReportInfo::Cleared { .. } => self.message("runway-cleared", &[]),
+            RunwayReportInfo::ClosedSnowOrIce => self.message("runway-closed", &[]),
+            RunwayReportInfo::Condition {
+                deposit,
+                coverage,
+                depth,
+                friction_coefficient,
+                braking_action,
+            } => {
+                let mut acc = self.message(&format!("deposit-{}", <&str>::from(deposit)), &[]);
+                if let Some(coverage) = coverage {
+                    acc = self.message(
+                        &format!("runway-coverage-{}", <&str>::from(coverage)),
+                        &[("deposit", &acc)],
+                    );
+                }
+                if let Some(depth) = depth {
+                    acc = self.message(
+                        "runway-depth",
+                        &[("state", &acc), ("depth", &self.height(depth))],
+                    );
+                }
+                if let Some(friction_coefficient) = friction_coefficient {
+                    acc = self.message(
+                        "runway-friction",
+                        &[
+                            ("state", &acc),
+                            ("friction", &self.number(friction_coefficient, 2)),
+                        ],
+                    );
+                }
+                if let Some(braking_action) = braking_action {
+                    acc = self.message(
+                        &format!("braking-action-{}", <&str>::from(braking_action)),
+                        &[("state", &acc)],
+                    );
+                }
+                acc
+            }
+        };
+        self.message(
+            "runway-state",
+            &[("runway", &runway_report.designator), ("state", &state)],
+        )
+    }
+
+    fn water_conditions(&self, water_conditions: &WaterConditions) -> Vec<String> {
+        let mut sentences = vec![];
+        if let Some(water_temperature) = water_conditions.temperature {
+            sentences.push(self.message(
+                "sea-temperature",
+                &[("temperature", &self.temperature(water_temperature))],
+            ));
+        }
+        if let Some(surface_state) = water_conditions.surface_state {
+            sentences.push(self.message(
+                "sea-state",
+                &[(
+                    "state",
+                    &self.message(&format!("sea-state-{}", <&str>::from(surface_state)), &[]),
+                )],
+            ));
+        }
+        if let Some(wave_height) = water_conditions.significant_wave_height {
+            sentences.push(self.message("wave-height", &[("height", &self.height(wave_height))]));
+        }
+        sentences
+    }
+
+    fn color(&self, color: &Color) -> String {
+        let mut acc = self.message(
+            "color",
+            &[("color", &self.color_state(color.current_color))],
+        );
+        if let Some(next_color) = color.next_color {
+            acc = self.message(
+                "color-next",
+                &[("color", &acc), ("next", &self.color_state(next_color))],
+            );
+        }
+        if color.is_black {
+            acc = self.message("color-black", &[("color", &acc)]);
+        }
+        acc
+    }
+
+    fn color_state(&self, color_state: ColorState) -> String {
+        self.message(&format!("color-{}", <&str>::from(color_state)), &[])
+    }
+
+    fn trend(&self, trend: &Trend) -> String {
+        let (key, report) = match trend {
+            Trend::NoSignificantChange => return self.message("trend-NOSIG", &[]),
+            Trend::Becoming(report) => ("trend-BECMG", report),
+            Trend::Temporarily(report) => ("trend-TEMPO", report),
+        };
+        let mut changes = vec![];
+        if let Some(time) = report.time {
+            changes.push(match time {
+                TrendTime::At(time) => {
+                    self.message("trend-at", &[("time", &self.military_time(time))])
+                }
+                TrendTime::From(time) => {
+                    self.message("trend-from", &[("time", &self.military_time(time))])
+                }
+                TrendTime::Until(time) => {
+                    self.message("trend-until", &[("time", &self.military_time(time))])
+                }
+                TrendTime::Range { from, until } => self.message(
+                    "trend-range",
+                    &[
+                        ("from", &self.military_time(from)),
+                        ("until", &self.military_time(until)),
+                    ],
+                ),
+            });
+        }
+        if let Some(wind) = report.wind {
+            changes.push(self.wind(&wind));
+        }
+        if let Some(visibility) = report.visibility {
+            changes.extend(self.visibility(&visibility));
+        }
+        if !report.weather.is_empty() {
+            changes.push(self.weather_sequence(&report.weather));
+        }
+        for cloud_cover in &report.cloud_cover {
+            changes.push(self.cloud_cover(cloud_cover));
+        }
+        if let Some(state) = report.color_state {
+            changes.push(self.message("trend-color", &[("color", &self.color_state(state))]));
+        }
+        let trend = self.message(key, &[]);
+        if changes.is_empty() {
+            trend
+        } else {
+            self.message(
+                "trend-changes",
+                &[("trend", &trend), ("changes", &changes.join(", "))],
+            )
+        }
+    }
+
+    fn military_time(&self, time: MilitaryTime) -> String {
+        format!("{:02}:{:02}", time.hour, time.minute)
+    }
+
+    fn bearing(&self, angle: Angle) -> String {
+        self.message(
+            "bearing",
+            &[("value", &format!("{:03.0}", angle.get::<degree>()))],
+        )
+    }
+
+    fn speed(&self, speed: Velocity) -> String {
+        let (value, key) = match self.units.speed {
+            SpeedDisplayUnit::Knots => (speed.get::<knot>(), "unit-knots"),
+            SpeedDisplayUnit::MetersPerSecond => {
+                (speed.get::<meter_per_second>(), "unit-meters-per-second")
+            }
+            SpeedDisplayUnit::KilometersPerHour => (
+                speed.get::<kilometer_per_hour>(),
+                "unit-kilometers-per-hour",
+            ),
+            SpeedDisplayUnit::MilesPerHour => (speed.get::<mile_per_hour>(), "unit-miles-per-hour"),
+        };
+        self.message(key, &[("value", &self.number(value, 0))])
+    }
+
+    fn distance(&self, distance: Length) -> String {
+        let (value, key) = match self.units.distance {
+            DistanceDisplayUnit::Kilometers if distance.get::<kilometer>() >= 5. => (
+                self.number(distance.get::<kilometer>(), 1),
+                "unit-kilometers",
+            ),
+            DistanceDisplayUnit::Kilometers | DistanceDisplayUnit::Meters => {
+                (self.number(distance.get::<meter>(), 0), "unit-meters")
+            }
+            DistanceDisplayUnit::StatuteMiles => {
+                (self.number(distance.get::<mile>(), 2), "unit-statute-miles")
+            }
+        };
+        self.message(key, &[("value", &value)])
+    }
+
+    fn height(&self, height: Length) -> String {
+        let (value, key) = match self.units.height {
+            HeightDisplayUnit::Meters if height.get::<meter>() < 1. => (
+                self.number(height.get::<meter>() * 1000., 0),
+                "unit-millimeters",
+            ),
+            HeightDisplayUnit::Meters => (self.number(height.get::<meter>(), 0), "unit-meters"),
+            HeightDisplayUnit::Feet if height.get::<foot>() < 1. => {
+                (self.number(height.get::<foot>() * 12., 1), "unit-inches")
+            }
+            HeightDisplayUnit::Feet => (self.number(height.get::<foot>(), 0), "unit-feet"),
+        };
+        self.message(key, &[("value", &value)])
+    }
+
+    fn rainfall(&self, depth: Length) -> String {
+        match self.units.height {
+            HeightDisplayUnit::Meters => self.message(
+                "unit-millimeters",
+                &[("value", &self.number(depth.get::<millimeter>(), 1))],
+            ),
+            HeightDisplayUnit::Feet => self.message(
+                "unit-inches",
+                &[("value", &self.number(depth.get::<inch>(), 2))],
+            ),
+        }
+    }
+
+    fn temperature(&self, temperature: ThermodynamicTemperature) -> String {
+        let (value, key) = match self.units.temperature {
+            TemperatureDisplayUnit::Celsius => {
+                (temperature.get::<degree_celsius>(), "unit-celsius")
+            }
+            TemperatureDisplayUnit::Fahrenheit => {
+                (temperature.get::<degree_fahrenheit>(), "unit-fahrenheit")
+            }
+        };
+        self.message(key, &[("value", &self.number(value, 0))])
+    }
+
+    fn pressure(&self, pressure: Pressure) -> String {
+        let (value, key) = match self.units.pressure {
+            PressureDisplayUnit::Hectopascals => (
+                self.number(pressure.get::<hectopascal>(), 0),
+                "unit-hectopascals",
+            ),
+            PressureDisplayUnit::InchesOfMercury => (
+                self.number(pressure.get::<inch_of_mercury>(), 2),
+                "unit-inches-of-mercury",
+            ),
+        };
+        self.message(key, &[("value", &value)])
+    }
+
+    /// Rounds to at most `decimals` places, dropping trailing zeros and grouping thousands
+    fn number(&self, value: f64, decimals: usize) -> String {
+        number(
+            value,
+            decimals,
+            &self.message("decimal-separator", &[]),
+            &self.message("thousands-separator", &[]),
+        )
+    }
+}
+
+fn number(
+    value: f64,
+    decimals: usize,
+    decimal_separator: &str,
+    thousands_separator: &str,
+) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction.trim_end_matches('0')),
+        None => (formatted.as_str(), ""),
+    };
+    let (sign, digits) = match integer.strip_prefix('-') {
+        Some(digits) if digits.chars().any(|c| c != '0') || !fraction.is_empty() => ("-", digits),
+        Some(digits) => ("", digits),
+        None => ("", integer),
+    };
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 && digits.len() > 4 {
+            grouped += thousands_separator;
+        }
+        grouped.push(c);
+    }
+    if fraction.is_empty() {
+        format!("{}{}", sign, grouped)
+    } else {
+        format!("{}{}{}{}", sign, grouped, decimal_separator, fraction)
+    }
+}
+
+fn capitalize(phrase: &str) -> String {
+    let mut chars = phrase.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::metar;
+
+    #[test]
+    fn describe_weather_phenomena() {
+        let report = metar(
+            "EGLL 291350Z 24012G25KT 3000 +TSRA VCSH -FZDZ BR BKN008CB 17/15 Q1014 TEMPO 1500 SHRA",
+        )
+        .unwrap();
+        assert_eq!(
+            describe(&report, &UnitSystem::METRIC),
+            "Observed at EGLL on day 29 at 13:50 UTC. Wind from 240° at 22 kilometers per hour, gusting to 46 kilometers per hour. \
+            Visibility 3000 meters. Heavy thunderstorm with rain, showers in the vicinity, light freezing drizzle, mist. \
+            Broken clouds at 244 meters (cumulonimbus). Temperature 17 °C, dew point 15 °C. Pressure 1014 hectopascals. \
+            Temporarily visibility 1500 meters, rain showers."
+        );
+    }
+
+    #[cfg(feature = "locales")]
+    #[test]
+    fn describe_in_french() {
+        let report = metar("LFPG 291350Z 24012KT 9999 -RA SCT020 17/15 Q1014 NOSIG").unwrap();
+        assert_eq!(
+            describe_with_catalog(&report, &UnitSystem::METRIC, Catalog::french()),
+            "Observé à LFPG le 29 à 13:50 UTC. Vent du 240° à 22 kilomètres par heure. Visibilité 10 kilomètres ou plus. \
+            Pluie faible. Nuages épars à 610 mètres. Température 17 °C, point de rosée 15 °C. \
+            Pression 1014 hectopascals. Pas de changement significatif prévu."
+        );
+    }
+
+    #[test]
+    fn describe_shear_rainfall_and_remarks() {
+        let report = metar(
+            "YSSY 291350Z 18010KT 9999 SCT020 17/15 Q1014 RF00.2/001.4 WS ALL RWY RMK MT OBSC QFE748",
+        )
+        .unwrap();
+        assert_eq!(
+            describe(&report, &UnitSystem::METRIC),
+            "Observed at YSSY on day 29 at 13:50 UTC. Wind from 180° at 19 kilometers per hour. \
+            Visibility 10 kilometers or more. Scattered clouds at 610 meters. Temperature 17 °C, dew point 15 °C. \
+            Pressure 1014 hectopascals. Rainfall 0.2 millimeters in the last 10 minutes, 1.4 millimeters since 09:00 local time. \
+            Wind shear on all runways. Field elevation pressure 997 hectopascals. Mountains obscured. \
+            Remarks: MT OBSC QFE748."
+        );
+
+        let report = metar("KSEA 290353Z 01008KT 9999 FEW035 17/09 A2992 WS R16L").unwrap();
+        let description = describe(&report, &UnitSystem::AVIATION);
+        assert!(description.contains("Visibility 6.21 statute miles or more."));
+        assert!(description.ends_with("Wind shear on runway 16L."));
+        let report = metar("EGLL 290350Z 24004MPS 8000 FEW035 17/09 Q1014").unwrap();
+        assert!(describe(&report, &UnitSystem::METRIC).contains("Visibility 8 kilometers."));
+    }
+
+    #[test]
+    fn number_formatting() {
+        assert_eq!(number(20000., 0, ".", ","), "20,000");
+        assert_eq!(number(2500., 0, ".", ","), "2500");
+        assert_eq!(number(0.25, 2, ",", " "), "0,25");
+        assert_eq!(number(10.0, 2, ".", ","), "10");
+        assert_eq!(number(-0.2, 0, ".", ","), "0");
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Translatable messages used to describe reports
+///
+/// A catalog is written as `key = message` lines, with `#` starting a comment line.
+/// Messages may contain `{name}` placeholders that are filled in when rendering,
+/// and can be surrounded by double quotes to keep leading or trailing whitespace.
+///
+/// Any message missing from a catalog falls back to [Catalog::english].
+/// See `src/describe/locales/en.txt` for the full list of keys.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+/// A line in a catalog that isn't a comment or a `key = message` pair
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CatalogError {
+    /// 1-based
+    pub line: usize,
+}
+
+impl std::fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {} is not a `key = message` pair", self.line)
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Result<Self, CatalogError> {
+        let mut messages = HashMap::new();
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, message) = line
+                .split_once('=')
+                .filter(|(key, _)| !key.trim().is_empty())
+                .ok_or(CatalogError { line: i + 1 })?;
+            let message = message.trim();
+            let message =
+                if message.len() >= 2 && message.starts_with('"') && message.ends_with('"') {
+                    &message[1..message.len() - 1]
+                } else {
+                    message
+                };
+            messages.insert(key.trim().to_string(), message.to_string());
+        }
+        Ok(Self { messages })
+    }
+
+    pub fn english() -> &'static Self {
+        static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+        ENGLISH.get_or_init(|| Self::parse(include_str!("locales/en.txt")).unwrap())
+    }
+
+    #[cfg(feature = "locales")]
+    pub fn french() -> &'static Self {
+        static FRENCH: OnceLock<Catalog> = OnceLock::new();
+        FRENCH.get_or_init(|| Self::parse(include_str!("locales/fr.txt")).unwrap())
+    }
+
+    #[cfg(feature = "locales")]
+    pub fn german() -> &'static Self {
+        static GERMAN: OnceLock<Catalog> = OnceLock::new();
+        GERMAN.get_or_init(|| Self::parse(include_str!("locales/de.txt")).unwrap())
+    }
+
+    #[cfg(feature = "locales")]
+    pub fn spanish() -> &'static Self {
+        static SPANISH: OnceLock<Catalog> = OnceLock::new();
+        SPANISH.get_or_init(|| Self::parse(include_str!("locales/es.txt")).unwrap())
+    }
+
+    #[cfg(feature = "locales")]
+    pub fn portuguese() -> &'static Self {
+        static PORTUGUESE: OnceLock<Catalog> = OnceLock::new();
+        PORTUGUESE.get_or_init(|| Self::parse(include_str!("locales/pt.txt")).unwrap())
+    }
+
+    /// Looks up a shipped catalog by its two letter [ISO 639-1](https://en.wikipedia.org/wiki/ISO_639-1) code
+    pub fn for_language(code: &str) -> Option<&'static Self> {
+        match code {
+            "en" => Some(Self::english()),
+            #[cfg(feature = "locales")]
+            "fr" => Some(Self::french()),
+            #[cfg(feature = "locales")]
+            "de" => Some(Self::german()),
+            #[cfg(feature = "locales")]
+            "es" => Some(Self::spanish()),
+            #[cfg(feature = "locales")]
+            "pt" => Some(Self::portuguese()),
+            _ => None,
+        }
+    }
+
+    /// The message for a key as written in this catalog
+    pub fn message(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    /// Overrides or adds a message
+    pub fn insert(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.messages.insert(key.into(), message.into());
+    }
+
+    /// Renders a message, replacing each `{name}` with its value
+    ///
+    /// Falls back to English, and then to the key itself, if the message is missing.
+    pub(super) fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let message = self
+            .message(key)
+            .or_else(|| Self::english().message(key))
+            .unwrap_or(key);
+
+        let mut acc = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find('{') {
+            acc += &rest[..start];
+            let placeholder = &rest[start + 1..];
+            match placeholder.find('}').and_then(|end| {
+                let name = &placeholder[..end];
+                args.iter()
+                    .find(|(arg, _)| *arg == name)
+                    .map(|(_, value)| (end, value))
+            }) {
+                Some((end, value)) => {
+                    acc += value;
+                    rest = &placeholder[end + 1..];
+                }
+                None => {
+                    acc.push('{');
+                    rest = placeholder;
+                }
+            }
+        }
+        acc += rest;
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let catalog = Catalog::parse(
+            "# comment\n\nwind = vent du {direction} à {speed}\nthousands-separator = \" \"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.format("wind", &[("direction", "010°"), ("speed", "8 kt")]),
+            "vent du 010° à 8 kt"
+        );
+        assert_eq!(catalog.message("thousands-separator"), Some(" "));
+        assert_eq!(catalog.format("wind-calm", &[]), "wind calm");
+        assert_eq!(Catalog::parse("wind\n"), Err(CatalogError { line: 1 }));
+    }
+
+    #[cfg(feature = "locales")]
+    #[test]
+    fn shipped_catalogs_are_complete() {
+        let english = Catalog::english();
+        for code in ["fr", "de", "es", "pt"] {
+            let catalog = Catalog::for_language(code).unwrap();
+            for key in english.messages.keys() {
+                assert!(
+                    catalog.message(key).is_some(),
+                    "{} is missing {}",
+                    code,
+                    key
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,551 @@
+//! Country and operator specific conventions for METARs
+//!
+//! The [metar](crate::parse::metar) parser accepts the quirks of stations all over the world. A [Dialect] narrows
+//! that down to what the stations following it actually report, and settles how groups that are ambiguous between
+//! dialects are read.
+//!
+//! ```
+//! use weather_reports::dialect::{parse, Dialect, DialectError, Quirk};
+//!
+//! // Chosen from the K prefix
+//! let err = parse("KSEA 290353Z 01008KT CAVOK 19/09 A3004", None).unwrap_err();
+//! assert!(matches!(err, DialectError::Disallowed { dialect: Dialect::Faa, quirk: Quirk::Cavok, .. }));
+//!
+//! let report = parse("YSSY 290400Z 16012KT 9999 FEW030 18/09 Q1021 RF00.0/000.0", None).unwrap();
+//! assert!(report.accumulated_rainfall.is_some());
+//! ```
+use peg::{error::ParseError, str::LineCol};
+use std::{fmt, ops::Range};
+use uom::si::{
+    f64::{Length, Pressure},
+    length::{foot, kilometer, meter, mile},
+    pressure::{hectopascal, millimeter_of_mercury},
+};
+
+use crate::{
+    parse::metar_with_spans,
+    tokens::{
+        DirectionalOrRawVisiblity, DistanceUnit, FieldPressureUnit, MetarReport, PressureUnit,
+        RawVisibility, ReportedValue, SpeedUnit, Trend, Visibility,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Dialect {
+    /// Every quirk the parser knows of, used for stations without a known dialect
+    Lenient,
+    /// WMO Manual on Codes (FM 15) without any national practices
+    WmoStrict,
+    /// United States, following the FAA and NWS
+    Faa,
+    Canada,
+    Australia,
+    /// Russia and the other members of the Commonwealth of Independent States
+    RussiaCis,
+    China,
+    /// NATO military airfields, which add color states
+    NatoMilitary,
+}
+
+impl Dialect {
+    /// Dialect of the country a station is in, based on the prefix of its ICAO identifier
+    ///
+    /// Military airfields share prefixes with civil ones, so [Dialect::NatoMilitary] must be chosen by the caller.
+    pub fn for_identifier(identifier: &str) -> Self {
+        match identifier.as_bytes() {
+            [b'K', ..] | [b'P', b'A' | b'F' | b'H' | b'O', ..] => Self::Faa,
+            [b'C', ..] => Self::Canada,
+            [b'Y', ..] => Self::Australia,
+            [b'U', ..] => Self::RussiaCis,
+            // North Korea and Mongolia also use the Z prefix
+            [b'Z', b'K' | b'M', ..] => Self::Lenient,
+            [b'Z', ..] => Self::China,
+            _ => Self::Lenient,
+        }
+    }
+
+    pub fn profile(self) -> Profile {
+        use DistanceUnit::*;
+        use PressureUnit::*;
+        use SpeedUnit::*;
+
+        const STRICT: Profile = Profile {
+            cavok: true,
+            accumulated_rainfall: false,
+            color: false,
+            temperatures_before_visibility: false,
+            misplaced_report_name: false,
+            nosig_misspellings: false,
+            legacy_runway_state: false,
+            speed_units: &[Knots, MetersPerSecond],
+            visibility_units: &[None],
+            pressure_units: &[Hectopascals],
+            unitless_visibility: Meters,
+            unitless_field_pressure: Some(FieldPressureUnit::Hectopascals),
+        };
+        const NORTH_AMERICA: Profile = Profile {
+            cavok: false,
+            speed_units: &[Knots],
+            // A bare number is a visibility with its SM dropped rather than meters
+            visibility_units: &[None, Some(StatuteMiles)],
+            pressure_units: &[InchesOfMercury],
+            unitless_visibility: StatuteMiles,
+            ..STRICT
+        };
+
+        match self {
+            Self::Lenient => Profile {
+                accumulated_rainfall: true,
+                color: true,
+                temperatures_before_visibility: true,
+                misplaced_report_name: true,
+                nosig_misspellings: true,
+                legacy_runway_state: true,
+                speed_units: &[Knots, MetersPerSecond, KilometersPerHour],
+                visibility_units: &[None, Some(Meters), Some(Kilometers), Some(StatuteMiles)],
+                pressure_units: &[Hectopascals, InchesOfMercury],
+                unitless_field_pressure: None,
+                ..STRICT
+            },
+            Self::WmoStrict => STRICT,
+            Self::Faa | Self::Canada => NORTH_AMERICA,
+            Self::Australia => Profile {
+                accumulated_rainfall: true,
+                speed_units: &[Knots],
+                visibility_units: &[None, Some(Kilometers)],
+                ..STRICT
+            },
+            Self::RussiaCis => Profile {
+                legacy_runway_state: true,
+                speed_units: &[MetersPerSecond, Knots],
+                unitless_field_pressure: Some(FieldPressureUnit::MillimetersOfMercury),
+                ..STRICT
+            },
+            Self::China => Profile {
+                speed_units: &[MetersPerSecond],
+                ..STRICT
+            },
+            Self::NatoMilitary => Profile {
+                color: true,
+                speed_units: &[Knots],
+                visibility_units: &[None, Some(StatuteMiles)],
+                pressure_units: &[Hectopascals, InchesOfMercury],
+                ..STRICT
+            },
+        }
+    }
+}
+
+/// Which of the lenient alternatives in the grammar a dialect allows, and how it reads ambiguous groups
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Profile {
+    pub cavok: bool,
+    /// Australian `RF` group
+    pub accumulated_rainfall: bool,
+    /// Military color states
+    pub color: bool,
+    /// Some stations put temperatures before visibility
+    pub temperatures_before_visibility: bool,
+    /// Some stations put `METAR` after the identifier
+    pub misplaced_report_name: bool,
+    /// i.e. `N0SIG` or `NOSG`
+    pub nosig_misspellings: bool,
+    /// 8 digit runway state group without the `R` prefix, i.e. `88290395`
+    pub legacy_runway_state: bool,
+    pub speed_units: &'static [SpeedUnit],
+    /// Units of prevailing and directional visibility, where [None] is a visibility reported without a unit
+    pub visibility_units: &'static [Option<DistanceUnit>],
+    pub pressure_units: &'static [PressureUnit],
+    /// Unit of a visibility reported without one
+    ///
+    /// Statute miles only apply up to [MAX_UNITLESS_STATUTE_MILES], so larger values such as a metric `9999` from a
+    /// military station stay in meters.
+    pub unitless_visibility: DistanceUnit,
+    /// Unit of a QFE without a decimal point or hectopascals, guessed from its value if absent
+    pub unitless_field_pressure: Option<FieldPressureUnit>,
+}
+
+/// Largest visibility without a unit that is read as statute miles, since Canada reports up to 15SM
+pub const MAX_UNITLESS_STATUTE_MILES: f64 = 15.;
+
+/// A lenient alternative that a dialect may not allow
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Quirk {
+    Cavok,
+    AccumulatedRainfall,
+    Color,
+    TemperaturesBeforeVisibility,
+    MisplacedReportName,
+    NosigMisspelling,
+    LegacyRunwayState,
+    SpeedUnit(SpeedUnit),
+    VisibilityUnit(Option<DistanceUnit>),
+    PressureUnit(PressureUnit),
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cavok => write!(f, "CAVOK"),
+            Self::AccumulatedRainfall => write!(f, "accumulated rainfall"),
+            Self::Color => write!(f, "color state"),
+            Self::TemperaturesBeforeVisibility => write!(f, "temperatures before visibility"),
+            Self::MisplacedReportName => write!(f, "report name after the identifier"),
+            Self::NosigMisspelling => write!(f, "misspelled NOSIG"),
+            Self::LegacyRunwayState => write!(f, "runway state without a runway prefix"),
+            Self::SpeedUnit(unit) => write!(f, "wind speed in {}", <&str>::from(*unit)),
+            Self::VisibilityUnit(None) => write!(f, "visibility without a unit"),
+            Self::VisibilityUnit(Some(unit)) => {
+                write!(f, "visibility in {}", <&str>::from(*unit))
+            }
+            Self::PressureUnit(unit) => write!(f, "pressure in {}", <&str>::from(*unit)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DialectError {
+    Parse(ParseError<LineCol>),
+    /// The report is valid but the dialect does not allow one of its groups
+    Disallowed {
+        dialect: Dialect,
+        quirk: Quirk,
+        /// Byte range of the group in the report
+        span: Range<usize>,
+    },
+}
+
+impl fmt::Display for DialectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "could not parse report: {}", err),
+            Self::Disallowed { dialect, quirk, .. } => {
+                write!(f, "{} is not allowed in the {:?} dialect", quirk, dialect)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DialectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            Self::Disallowed { .. } => None,
+        }
+    }
+}
+
+/// Parses a METAR and checks it against a dialect, chosen from its identifier if not given
+///
+/// Visibilities, including those of trends, and QFE without a unit are read in the units of the dialect.
+pub fn parse(input: &str, dialect: Option<Dialect>) -> Result<MetarReport<'_>, DialectError> {
+    let (mut report, spans) = metar_with_spans(input).map_err(DialectError::Parse)?;
+    let dialect = dialect.unwrap_or_else(|| Dialect::for_identifier(&report.identifier));
+    let profile = dialect.profile();
+    let disallowed = |quirk, span: &Range<usize>| DialectError::Disallowed {
+        dialect,
+        quirk,
+        span: span.clone(),
+    };
+
+    let checks = [
+        (profile.cavok, Quirk::Cavok, &spans.cavok),
+        (
+            profile.accumulated_rainfall,
+            Quirk::AccumulatedRainfall,
+            &spans.accumulated_rainfall,
+        ),
+        (profile.color, Quirk::Color, &spans.color),
+    ];
+    for (allowed, quirk, span) in checks.iter() {
+        if let (false, Some(span)) = (allowed, span) {
+            return Err(disallowed(*quirk, span));
+        }
+    }
+
+    if let (false, Some(span)) = (profile.misplaced_report_name, &spans.kind) {
+        if span.start > spans.identifier.start {
+            return Err(disallowed(Quirk::MisplacedReportName, span));
+        }
+    }
+    if let (false, Some(span)) = (profile.temperatures_before_visibility, &spans.temperatures) {
+        let visibility_start = spans
+            .visibility
+            .iter()
+            .chain(&spans.runway_visibilities)
+            .chain(&spans.weather)
+            .chain(&spans.cloud_cover)
+            .chain(&spans.cavok)
+            .map(|span| span.start)
+            .min();
+        if visibility_start.is_some_and(|start| span.start < start) {
+            return Err(disallowed(Quirk::TemperaturesBeforeVisibility, span));
+        }
+    }
+    if !profile.nosig_misspellings {
+        for (trend, span) in report.trends.iter().zip(&spans.trends) {
+            if *trend == Trend::NoSignificantChange && &input[span.clone()] != "NOSIG" {
+                return Err(disallowed(Quirk::NosigMisspelling, span));
+            }
+        }
+    }
+
+    if !profile.legacy_runway_state {
+        if let Some(span) = spans
+            .runway_reports
+            .iter()
+            .find(|span| !input[span.start..].starts_with('R'))
+        {
+            return Err(disallowed(Quirk::LegacyRunwayState, span));
+        }
+    }
+
+    if let (Some(wind), Some(span)) = (&report.wind, &spans.wind) {
+        if !profile.speed_units.contains(&wind.unit) {
+            return Err(disallowed(Quirk::SpeedUnit(wind.unit), span));
+        }
+    }
+    if let (Some(pressure), Some(span)) = (&report.reported_pressure, &spans.pressure) {
+        if !profile.pressure_units.contains(&pressure.unit) {
+            return Err(disallowed(Quirk::PressureUnit(pressure.unit), span));
+        }
+    }
+    let trend_visibilities =
+        report
+            .trends
+            .iter_mut()
+            .zip(&spans.trends)
+            .filter_map(|(trend, span)| match trend {
+                Trend::Becoming(trend) | Trend::Temporarily(trend) => {
+                    Some((trend.visibility.as_mut()?, span))
+                }
+                Trend::NoSignificantChange => None,
+            });
+    for (visibility, span) in report
+        .visibility
+        .as_mut()
+        .zip(spans.visibility.as_ref())
+        .into_iter()
+        .chain(trend_visibilities)
+    {
+        for raw in raw_visibilities(visibility) {
+            if !profile.visibility_units.contains(&raw.unit) {
+                return Err(disallowed(Quirk::VisibilityUnit(raw.unit), span));
+            }
+            if raw.unit.is_none() && reads_as(raw.reported_distance, profile.unitless_visibility) {
+                raw.distance = length(
+                    f64::from(raw.reported_distance),
+                    profile.unitless_visibility,
+                );
+            }
+        }
+    }
+
+    if let Some(field_pressure) = &mut report.field_pressure {
+        if let (None, Some(unit)) = (field_pressure.unit, profile.unitless_field_pressure) {
+            let value = f64::from(field_pressure.value);
+            field_pressure.pressure = match unit {
+                FieldPressureUnit::MillimetersOfMercury => {
+                    Pressure::new::<millimeter_of_mercury>(value)
+                }
+                FieldPressureUnit::Hectopascals => Pressure::new::<hectopascal>(value),
+            };
+        }
+    }
+
+    Ok(report)
+}
+
+fn raw_visibilities(visibility: &mut Visibility) -> impl Iterator<Item = &mut RawVisibility> {
+    let minimum = visibility.minimum.as_mut().map(|minimum| match minimum {
+        DirectionalOrRawVisiblity::Directional(directional) => &mut directional.distance,
+        DirectionalOrRawVisiblity::Raw(raw) => raw,
+    });
+    visibility
+        .prevailing
+        .as_mut()
+        .into_iter()
+        .chain(minimum)
+        .chain(
+            visibility
+                .maximum_directional
+                .as_mut()
+                .map(|directional| &mut directional.distance),
+        )
+}
+
+/// Whether a visibility without a unit is plausible in the unit, otherwise it is left in meters
+fn reads_as(value: ReportedValue, unit: DistanceUnit) -> bool {
+    match (unit, value) {
+        (DistanceUnit::StatuteMiles, ReportedValue::Fraction { .. }) => true,
+        (DistanceUnit::StatuteMiles, _) => f64::from(value) <= MAX_UNITLESS_STATUTE_MILES,
+        _ => true,
+    }
+}
+
+fn length(value: f64, unit: DistanceUnit) -> Length {
+    match unit {
+        DistanceUnit::Meters => Length::new::<meter>(value),
+        DistanceUnit::Kilometers => Length::new::<kilometer>(value),
+        DistanceUnit::StatuteMiles => Length::new::<mile>(value),
+        DistanceUnit::Feet => Length::new::<foot>(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choose_by_identifier() {
+        for (identifier, dialect) in [
+            ("KSEA", Dialect::Faa),
+            ("PANC", Dialect::Faa),
+            ("CYYZ", Dialect::Canada),
+            ("YSSY", Dialect::Australia),
+            ("UUEE", Dialect::RussiaCis),
+            ("UAAA", Dialect::RussiaCis),
+            ("ZBAA", Dialect::China),
+            ("ZKPY", Dialect::Lenient),
+            ("EGLL", Dialect::Lenient),
+        ] {
+            assert_eq!(
+                Dialect::for_identifier(identifier),
+                dialect,
+                "{}",
+                identifier
+            );
+        }
+    }
+
+    #[test]
+    fn allow_quirks_by_dialect() {
+        let color = "ETAR 291350Z 24012KT 9999 FEW035 17/09 Q1014 BLU";
+        assert!(matches!(
+            parse(color, Some(Dialect::WmoStrict)),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::Color,
+                span,
+                ..
+            }) if &color[span.clone()] == "BLU"
+        ));
+        assert!(parse(color, Some(Dialect::NatoMilitary)).is_ok());
+        assert!(parse(color, None).is_ok());
+
+        let nosig = "EGLL 291020Z 24015KT 9999 FEW035 15/14 Q1008 N0SIG";
+        assert!(parse(nosig, None).is_ok());
+        assert!(matches!(
+            parse(nosig, Some(Dialect::WmoStrict)),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::NosigMisspelling,
+                ..
+            })
+        ));
+
+        let temperatures_first = "EGLL 291020Z 24015KT 15/14 9999 FEW035 Q1008";
+        assert!(matches!(
+            parse(temperatures_first, Some(Dialect::WmoStrict)),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::TemperaturesBeforeVisibility,
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            parse("ZBAA 291000Z 18008KT 9999 FEW040 27/18 Q1008", None),
+            Err(DialectError::Disallowed {
+                dialect: Dialect::China,
+                quirk: Quirk::SpeedUnit(SpeedUnit::Knots),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("CYYZ 291000Z 18008KT 15SM FEW040 27/18 Q1008", None),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::PressureUnit(PressureUnit::Hectopascals),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn read_unitless_visibility() {
+        let prevailing = |report: MetarReport| report.visibility.unwrap().prevailing.unwrap();
+
+        let report = parse("KSEA 290353Z 01008KT 3 SCT200 19/09 A3004", None).unwrap();
+        let visibility = prevailing(report);
+        assert_eq!(visibility.unit, None);
+        assert!((visibility.distance.get::<mile>() - 3.).abs() < 1e-9);
+
+        let report = parse(
+            "KSEA 290353Z 01008KT 3000 SCT200 19/09 A3004",
+            Some(Dialect::WmoStrict),
+        );
+        assert!(matches!(
+            report,
+            Err(DialectError::Disallowed {
+                quirk: Quirk::PressureUnit(PressureUnit::InchesOfMercury),
+                ..
+            })
+        ));
+
+        let report = parse("EGLL 291020Z 24015KT 3000 FEW035 15/14 Q1008", None).unwrap();
+        assert!((prevailing(report).distance.get::<meter>() - 3000.).abs() < 1e-9);
+
+        // Metric visibilities from military or Canadian stations are too large to be miles
+        for input in [
+            "KNKX 290353Z 01008KT 9999 SCT200 19/09 A3004",
+            "CYYZ 290353Z 01008KT 9999 SCT200 19/09 A3004",
+        ] {
+            let report = parse(input, None).unwrap();
+            assert!((prevailing(report).distance.get::<meter>() - 9999.).abs() < 1e-9);
+        }
+        let report = parse("CYYZ 290353Z 01008KT 15 SCT200 19/09 A3004", None).unwrap();
+        assert!((prevailing(report).distance.get::<mile>() - 15.).abs() < 1e-9);
+
+        let report = parse(
+            "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004 TEMPO 2 BR",
+            None,
+        )
+        .unwrap();
+        let trend_visibility = match &report.trends[0] {
+            Trend::Temporarily(trend) => trend.visibility.unwrap().prevailing.unwrap(),
+            other => panic!("{:?}", other),
+        };
+        assert!((trend_visibility.distance.get::<mile>() - 2.).abs() < 1e-9);
+
+        assert!(matches!(
+            parse("KSEA 290353Z 01008KT 5KM SCT200 19/09 A3004", None),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::VisibilityUnit(Some(DistanceUnit::Kilometers)),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn read_cis_groups() {
+        let input = "UAAA 181000Z 00000MPS CAVOK 08/M04 Q1028 88290395 NOSIG RMK MT OBSC QFE710";
+        let field_pressure = |report: MetarReport| report.field_pressure.unwrap().pressure;
+        let report = parse(input, None).unwrap();
+        assert!((field_pressure(report).get::<millimeter_of_mercury>() - 710.).abs() < 1e-9);
+
+        assert!(matches!(
+            parse(input, Some(Dialect::WmoStrict)),
+            Err(DialectError::Disallowed {
+                quirk: Quirk::LegacyRunwayState,
+                span,
+                ..
+            }) if &input[span.clone()] == "88290395"
+        ));
+        let input = input.replace("88290395", "R88/290395");
+        let report = parse(&input, Some(Dialect::WmoStrict)).unwrap();
+        assert!((field_pressure(report).get::<hectopascal>() - 710.).abs() < 1e-9);
+
+        let input = "EGLL 181020Z 24010KT 9999 FEW030 15/09 Q1008 QFE1003";
+        let report = parse(input, Some(Dialect::Lenient)).unwrap();
+        assert!((field_pressure(report).get::<hectopascal>() - 1003.).abs() < 1e-9);
+    }
+}
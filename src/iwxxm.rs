@@ -0,0 +1,64 @@
+//! [IWXXM](https://community.wmo.int/en/activity-areas/wis/iwxxm) 3.0, the XML exchange format for
+//! aeronautical meteorological information defined by ICAO Annex 3 and WMO-No. 306
+//!
+//! Reports can be written from and read into the same types as the TAC (text) parsers.
+//!
+//! ```
+//! use weather_reports::{iwxxm::write_metar, parse::metar};
+//!
+//! let report = metar("METAR KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004").unwrap();
+//! let xml = write_metar(&report, 2021, 8).unwrap();
+//! assert!(xml.contains("<gml:timePosition>2021-08-29T03:53:00Z</gml:timePosition>"));
+//! ```
+use chrono::Timelike;
+use chrono_tz::{Greenwich, Tz};
+
+use crate::tokens::MilitaryTime;
+
+mod read;
+mod write;
+
+pub use read::{read_metar, read_taf, ReadError};
+pub use write::{write_metar, WriteError};
+
+const IWXXM_NAMESPACE: &str = "http://icao.int/iwxxm/3.0";
+const IWXXM_SCHEMA_LOCATION: &str = "http://schemas.wmo.int/iwxxm/3.0/iwxxm.xsd";
+const AIXM_NAMESPACE: &str = "http://www.aixm.aero/schema/5.1.1";
+const GML_NAMESPACE: &str = "http://www.opengis.net/gml/3.2";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// WMO code registers referenced by `xlink:href`
+mod codes {
+    pub const NIL_REASON: &str = "http://codes.wmo.int/common/nil/";
+    pub const PRESENT_WEATHER: &str = "http://codes.wmo.int/306/4678/";
+    pub const RECENT_WEATHER: &str = "http://codes.wmo.int/49-2/AerodromeRecentWeather/";
+    pub const CLOUD_AMOUNT: &str = "http://codes.wmo.int/49-2/CloudAmountReportedAtAerodrome/";
+    pub const CLOUD_TYPE: &str = "http://codes.wmo.int/49-2/SigConvectiveCloudType/";
+    pub const DEPOSIT_TYPE: &str = "http://codes.wmo.int/bufr4/codeflag/0-20-086/";
+    pub const CONTAMINATION: &str = "http://codes.wmo.int/bufr4/codeflag/0-20-087/";
+    pub const FRICTION_OR_BRAKING_ACTION: &str = "http://codes.wmo.int/bufr4/codeflag/0-20-089/";
+    pub const SEA_STATE: &str = "http://codes.wmo.int/bufr4/codeflag/0-22-061/";
+}
+
+/// A UTC date and time, resolved with the `chrono_helpers` methods of the report times
+type Instant = chrono::DateTime<Tz>;
+
+/// i.e. `2021-08-29T03:53:00Z`
+fn time_position(instant: &Instant) -> String {
+    instant.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Parses a `gml:timePosition`, ignoring seconds
+fn parse_time_position(position: &str) -> Option<Instant> {
+    chrono::DateTime::parse_from_rfc3339(position.trim())
+        .ok()
+        .map(|instant| instant.with_timezone(&Greenwich))
+}
+
+fn military_time(instant: &Instant) -> MilitaryTime {
+    MilitaryTime {
+        hour: instant.hour() as u8,
+        minute: instant.minute() as u8,
+    }
+}
@@ -0,0 +1,1235 @@
+use std::{borrow::Cow, convert::TryFrom, fmt};
+
+use chrono::Datelike;
+use roxmltree::{Document, Node};
+use uom::si::{
+    angle::degree,
+    f64::{Angle, Length, Pressure, ThermodynamicTemperature},
+    length::{foot, meter, millimeter},
+    pressure::hectopascal,
+    thermodynamic_temperature::{degree_celsius, kelvin},
+};
+
+use super::*;
+use crate::{
+    parse::parser::{velocity, weather_reports},
+    tokens::{taf::*, *},
+};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ReadError {
+    /// The document is not well-formed XML
+    Xml(roxmltree::Error),
+    /// The root element is not an IWXXM 3.0 report of the expected kind
+    UnexpectedReport(String),
+    /// A required element is absent
+    MissingElement(&'static str),
+    /// An element or attribute has a value that can't be interpreted
+    InvalidValue(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Xml(err) => write!(f, "{}", err),
+            ReadError::UnexpectedReport(name) => write!(f, "{} is not a supported report", name),
+            ReadError::MissingElement(name) => write!(f, "{} is missing", name),
+            ReadError::InvalidValue(name) => write!(f, "{} has an invalid value", name),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Xml(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<roxmltree::Error> for ReadError {
+    fn from(err: roxmltree::Error) -> Self {
+        ReadError::Xml(err)
+    }
+}
+
+/// Reads an IWXXM 3.0 METAR or SPECI into the same types as [crate::parse::metar]
+///
+/// The report owns its strings, so it outlives the document.
+/// Values are converted to the units used in TAC reports, so `10000` meters or more of visibility is `9999`.
+pub fn read_metar(xml: &str) -> Result<MetarReport<'static>, ReadError> {
+    let document = Document::parse(xml)?;
+    let root = document.root_element();
+    let kind = match root.tag_name().name() {
+        "METAR" if is_iwxxm(root) => ReportKind::Metar,
+        "SPECI" if is_iwxxm(root) => ReportKind::Speci,
+        name => return Err(ReadError::UnexpectedReport(name.to_string())),
+    };
+
+    let observation_time =
+        child(root, "observationTime").ok_or(ReadError::MissingElement("iwxxm:observationTime"))?;
+    let observed_at = time_instant(&document, observation_time)?;
+    let mut report = empty_report(root, observed_at)?;
+    report.kind = Some(kind);
+
+    let observation = match child(root, "observation").and_then(first_element) {
+        Some(observation) => observation,
+        None => {
+            report.observation_flags.push(ObservationFlag::Nil);
+            return Ok(report);
+        }
+    };
+    report.cavok = observation.attribute("cloudAndVisibilityOK") == Some("true");
+
+    let air = child(observation, "airTemperature")
+        .map(temperature)
+        .transpose()?
+        .flatten();
+    let dewpoint = child(observation, "dewpointTemperature")
+        .map(temperature)
+        .transpose()?
+        .flatten();
+    report.temperatures = air.map(|air| Temperatures { air, dewpoint });
+
+    if let Some((value, uom, text)) = child(observation, "qnh")
+        .map(measure)
+        .transpose()?
+        .flatten()
+    {
+        if uom != "hPa" {
+            return Err(invalid(observation));
+        }
+        report.pressure = Some(Pressure::new::<hectopascal>(value));
+        report.reported_pressure = Some(ReportedPressure {
+            unit: PressureUnit::Hectopascals,
+            value: zero_padded(
+                ReportedValue::try_from(text).map_err(|_| invalid(observation))?,
+                4,
+            ),
+        });
+    }
+
+    let conditions = weather_conditions(observation, "presentWeather")?;
+    report.wind = conditions.wind;
+    report.visibility = conditions.visibility;
+    report.weather = conditions.weather;
+    report.cloud_cover = conditions.cloud_cover;
+
+    for rvr in children(observation, "rvr").filter_map(first_element) {
+        report.runway_visibilities.push(runway_visual_range(rvr)?);
+    }
+    for recent_weather in children(observation, "recentWeather") {
+        report.recent_weather.extend(weather(recent_weather)?);
+    }
+    if let Some(wind_shear) = child(observation, "windShear").and_then(first_element) {
+        if wind_shear.attribute("allRunways") == Some("true") {
+            report.wind_shear.push(WindShear::AllRunways);
+        } else {
+            for runway in children(wind_shear, "runway") {
+                report
+                    .wind_shear
+                    .push(WindShear::Runway(runway_designator(runway)?.into()));
+            }
+        }
+    }
+    if let Some(sea_condition) = child(observation, "seaCondition").and_then(first_element) {
+        report.water_conditions = Some(sea_condition_of(sea_condition)?);
+    }
+    for runway_state in children(observation, "runwayState").filter_map(first_element) {
+        report.runway_reports.extend(runway_report(runway_state)?);
+    }
+
+    for trend in children(root, "trendForecast") {
+        report
+            .trends
+            .push(trend_forecast(&document, trend, observed_at)?);
+    }
+
+    Ok(report)
+}
+
+/// Reads an IWXXM 3.0 TAF
+pub fn read_taf(xml: &str) -> Result<TafReport<'static>, ReadError> {
+    let document = Document::parse(xml)?;
+    let root = document.root_element();
+    if !(root.tag_name().name() == "TAF" && is_iwxxm(root)) {
+        return Err(ReadError::UnexpectedReport(
+            root.tag_name().name().to_string(),
+        ));
+    }
+
+    let issue_time =
+        child(root, "issueTime").ok_or(ReadError::MissingElement("iwxxm:issueTime"))?;
+    let mut report = TafReport {
+        identifier: Cow::Owned(aerodrome_identifier(root)?),
+        issue_time: date_time(&time_instant(&document, issue_time)?),
+        valid_period: None,
+        flags: report_flags(root),
+        base_forecast: None,
+        change_forecasts: vec![],
+    };
+
+    if let Some(valid_period) = child(root, "validPeriod") {
+        let (begin, end) = time_period(&document, valid_period)?;
+        report.valid_period = Some(TimeRange {
+            begin: date_time(&begin),
+            end: date_time(&end),
+        });
+    }
+
+    let base_forecast = match child(root, "baseForecast").and_then(first_element) {
+        Some(base_forecast) => base_forecast,
+        None => {
+            report.flags.push(ObservationFlag::Nil);
+            return Ok(report);
+        }
+    };
+    report.base_forecast = Some(forecast_conditions(base_forecast)?);
+
+    for change_forecast in children(root, "changeForecast").filter_map(first_element) {
+        let indicator = match change_forecast.attribute("changeIndicator") {
+            Some("BECOMING") => ChangeIndicator::Becoming,
+            Some("TEMPORARY_FLUCTUATIONS") => ChangeIndicator::TemporaryFluctuations,
+            Some("FROM") => ChangeIndicator::From,
+            Some("PROBABILITY_30") => ChangeIndicator::Probability30,
+            Some("PROBABILITY_40") => ChangeIndicator::Probability40,
+            Some("PROBABILITY_30_TEMPORARY_FLUCTUATIONS") => {
+                ChangeIndicator::Probability30TemporaryFluctuations
+            }
+            Some("PROBABILITY_40_TEMPORARY_FLUCTUATIONS") => {
+                ChangeIndicator::Probability40TemporaryFluctuations
+            }
+            _ => return Err(invalid(change_forecast)),
+        };
+        let phenomenon_time = child(change_forecast, "phenomenonTime")
+            .ok_or(ReadError::MissingElement("iwxxm:phenomenonTime"))?;
+        let (begin, end) = time_period(&document, phenomenon_time)?;
+        report.change_forecasts.push(ChangeForecast {
+            indicator,
+            period: TimeRange {
+                begin: date_time(&begin),
+                end: date_time(&end),
+            },
+            conditions: forecast_conditions(change_forecast)?,
+        });
+    }
+
+    Ok(report)
+}
+
+fn forecast_conditions(forecast: Node) -> Result<ForecastConditions, ReadError> {
+    let conditions = weather_conditions(forecast, "weather")?;
+    Ok(ForecastConditions {
+        wind: conditions.wind,
+        visibility: conditions.visibility,
+        weather: conditions.weather,
+        cloud_cover: conditions.cloud_cover,
+        cavok: forecast.attribute("cloudAndVisibilityOK") == Some("true"),
+    })
+}
+
+/// A report with only its station, time, and flags
+fn empty_report(root: Node, time: Instant) -> Result<MetarReport<'static>, ReadError> {
+    Ok(MetarReport {
+        kind: None,
+        identifier: Cow::Owned(aerodrome_identifier(root)?),
+        observation_time: Some(date_time(&time)),
+        observation_validity_range: None,
+        observation_flags: report_flags(root),
+        wind: None,
+        visibility: None,
+        runway_visibilities: vec![],
+        runway_reports: vec![],
+        weather: vec![],
+        cloud_cover: vec![],
+        cavok: false,
+        temperatures: None,
+        pressure: None,
+        reported_pressure: None,
+        field_pressure: None,
+        accumulated_rainfall: None,
+        color: None,
+        recent_weather: vec![],
+        wind_shear: vec![],
+        water_conditions: None,
+        trends: vec![],
+        remark: None,
+        obscured: vec![],
+        maintenance_needed: false,
+    })
+}
+
+fn report_flags(root: Node) -> Vec<ObservationFlag> {
+    let mut flags = vec![];
+    if root.attribute("reportStatus") == Some("CORRECTION") {
+        flags.push(ObservationFlag::Correction { letter: None });
+    }
+    if root.attribute("automatedStation") == Some("true") {
+        flags.push(ObservationFlag::Auto);
+    }
+    flags
+}
+
+fn date_time(instant: &Instant) -> DateTime {
+    DateTime {
+        day_of_month: instant.day() as u8,
+        time: military_time(instant),
+        is_zulu: true,
+    }
+}
+
+fn aerodrome_identifier(root: Node) -> Result<String, ReadError> {
+    let aerodrome = child(root, "aerodrome").ok_or(ReadError::MissingElement("iwxxm:aerodrome"))?;
+    let text = |name: &str| {
+        aerodrome
+            .descendants()
+            .find(|node| node.has_tag_name((AIXM_NAMESPACE, name)))
+            .and_then(|node| node.text())
+            .map(|text| text.trim().to_string())
+    };
+    text("locationIndicatorICAO")
+        .or_else(|| text("designator"))
+        .ok_or(ReadError::MissingElement("aixm:locationIndicatorICAO"))
+}
+
+fn runway_designator(runway: Node) -> Result<String, ReadError> {
+    runway
+        .descendants()
+        .find(|node| node.has_tag_name((AIXM_NAMESPACE, "designator")))
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .ok_or(ReadError::MissingElement("aixm:designator"))
+}
+
+/// Wind, visibility, weather and clouds, which observations and forecasts have in common
+struct Conditions {
+    wind: Option<Wind>,
+    visibility: Option<Visibility>,
+    weather: Vec<Weather>,
+    cloud_cover: Vec<CloudCover>,
+}
+
+fn weather_conditions(node: Node, weather_name: &str) -> Result<Conditions, ReadError> {
+    let wind = match child(node, "surfaceWind").and_then(first_element) {
+        Some(wind) => surface_wind(wind)?,
+        None => None,
+    };
+
+    let visibility = match child(node, "visibility") {
+        Some(visibility) => match first_element(visibility) {
+            Some(visibility) => horizontal_visibility(visibility)?,
+            None => None,
+        },
+        None => prevailing_visibility(node)?.map(|prevailing| Visibility {
+            prevailing: Some(prevailing),
+            minimum: None,
+            maximum_directional: None,
+        }),
+    };
+
+    let mut weather_groups = vec![];
+    for weather_node in children(node, weather_name) {
+        weather_groups.extend(weather(weather_node)?);
+    }
+
+    let cloud_cover = match child(node, "cloud") {
+        Some(cloud) => cloud_cover(cloud)?,
+        None => vec![],
+    };
+
+    Ok(Conditions {
+        wind,
+        visibility,
+        weather: weather_groups,
+        cloud_cover,
+    })
+}
+
+/// Absent if there is no mean speed
+fn surface_wind(wind: Node) -> Result<Option<Wind>, ReadError> {
+    let (speed, unit, reported_speed) = match child(wind, "meanWindSpeed")
+        .map(wind_speed)
+        .transpose()?
+        .flatten()
+    {
+        Some(speed) => speed,
+        None => return Ok(None),
+    };
+    let peak_gust = child(wind, "windGustSpeed")
+        .map(wind_speed)
+        .transpose()?
+        .flatten();
+    let direction = if wind.attribute("variableWindDirection") == Some("true") {
+        None
+    } else {
+        child(wind, "meanWindDirection")
+            .map(angle)
+            .transpose()?
+            .flatten()
+    };
+    let clockwise = child(wind, "extremeClockwiseWindDirection")
+        .map(angle)
+        .transpose()?
+        .flatten();
+    let counter_clockwise = child(wind, "extremeCounterClockwiseWindDirection")
+        .map(angle)
+        .transpose()?
+        .flatten();
+
+    Ok(Some(Wind {
+        direction,
+        speed: Some(speed),
+        peak_gust: peak_gust.map(|(peak_gust, _, _)| peak_gust),
+        variance: counter_clockwise.zip(clockwise),
+        unit,
+        reported_speed: Some(reported_speed),
+        reported_peak_gust: peak_gust.map(|(_, _, reported)| reported),
+    }))
+}
+
+fn wind_speed(
+    node: Node,
+) -> Result<Option<(uom::si::f64::Velocity, SpeedUnit, ReportedValue)>, ReadError> {
+    let (_, uom, text) = match measure(node)? {
+        Some(measure) => measure,
+        None => return Ok(None),
+    };
+    let unit = match uom {
+        "[kn_i]" => SpeedUnit::Knots,
+        "m/s" => SpeedUnit::MetersPerSecond,
+        "km/h" => SpeedUnit::KilometersPerHour,
+        _ => return Err(invalid(node)),
+    };
+    let reported = zero_padded(ReportedValue::try_from(text).map_err(|_| invalid(node))?, 2);
+    Ok(Some((velocity(reported, unit), unit, reported)))
+}
+
+/// Widens a number to the digits TAC reports always use, i.e. `998` hPa is written `Q0998`
+fn zero_padded(value: ReportedValue, min_width: u8) -> ReportedValue {
+    match value {
+        ReportedValue::Decimal {
+            digits,
+            width,
+            scale,
+        } => ReportedValue::Decimal {
+            digits,
+            width: width.max(min_width),
+            scale,
+        },
+        fraction => fraction,
+    }
+}
+
+fn angle(node: Node) -> Result<Option<Angle>, ReadError> {
+    match measure(node)? {
+        Some((value, "deg", _)) => Ok(Some(Angle::new::<degree>(value))),
+        Some(_) => Err(invalid(node)),
+        None => Ok(None),
+    }
+}
+
+fn temperature(node: Node) -> Result<Option<ThermodynamicTemperature>, ReadError> {
+    match measure(node)? {
+        Some((value, "Cel", _)) => Ok(Some(ThermodynamicTemperature::new::<degree_celsius>(value))),
+        Some((value, "K", _)) => Ok(Some(ThermodynamicTemperature::new::<kelvin>(value))),
+        Some(_) => Err(invalid(node)),
+        None => Ok(None),
+    }
+}
+
+fn length(node: Node) -> Result<Option<Length>, ReadError> {
+    match measure(node)? {
+        Some((value, "m", _)) => Ok(Some(Length::new::<meter>(value))),
+        Some((value, "mm", _)) => Ok(Some(Length::new::<millimeter>(value))),
+        Some((value, "[ft_i]", _)) => Ok(Some(Length::new::<foot>(value))),
+        Some(_) => Err(invalid(node)),
+        None => Ok(None),
+    }
+}
+
+fn horizontal_visibility(visibility: Node) -> Result<Option<Visibility>, ReadError> {
+    let prevailing = prevailing_visibility(visibility)?;
+    let minimum = match child(visibility, "minimumVisibility")
+        .map(|minimum| raw_visibility(minimum, None))
+        .transpose()?
+        .flatten()
+    {
+        Some(minimum) => Some(
+            match child(visibility, "minimumVisibilityDirection")
+                .map(angle)
+                .transpose()?
+                .flatten()
+            {
+                Some(direction) => DirectionalOrRawVisiblity::Directional(DirectionalVisibility {
+                    direction: compass_direction(direction),
+                    distance: minimum,
+                }),
+                None => DirectionalOrRawVisiblity::Raw(minimum),
+            },
+        ),
+        None => None,
+    };
+
+    if prevailing.is_none() && minimum.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Visibility {
+        prevailing,
+        minimum,
+        maximum_directional: None,
+    }))
+}
+
+fn prevailing_visibility(node: Node) -> Result<Option<RawVisibility>, ReadError> {
+    let operator = child(node, "prevailingVisibilityOperator").and_then(|operator| operator.text());
+    child(node, "prevailingVisibility")
+        .map(|prevailing| raw_visibility(prevailing, operator))
+        .transpose()
+        .map(Option::flatten)
+}
+
+/// In four digit meters, where `10000` and above is `9999` like in TAC reports
+fn raw_visibility(node: Node, operator: Option<&str>) -> Result<Option<RawVisibility>, ReadError> {
+    let distance = match length(node)? {
+        Some(distance) => distance,
+        None => return Ok(None),
+    };
+    let out_of_range = match operator.map(str::trim) {
+        Some("ABOVE") => Some(OutOfRange::Above),
+        Some("BELOW") => Some(OutOfRange::Below),
+        Some(_) => return Err(invalid(node)),
+        None => None,
+    };
+    let meters = distance.get::<meter>().round();
+    if meters >= 9999. && out_of_range != Some(OutOfRange::Below) {
+        return Ok(Some(RawVisibility {
+            out_of_range: None,
+            distance: Length::new::<meter>(9999.),
+            unit: None,
+            reported_distance: ReportedValue::Decimal {
+                digits: 9999,
+                width: 4,
+                scale: 0,
+            },
+        }));
+    }
+    Ok(Some(RawVisibility {
+        out_of_range,
+        distance: Length::new::<meter>(meters),
+        unit: None,
+        reported_distance: ReportedValue::Decimal {
+            digits: meters as u32,
+            width: 4,
+            scale: 0,
+        },
+    }))
+}
+
+fn compass_direction(bearing: Angle) -> CompassDirection {
+    use CompassDirection::*;
+    let octant = (bearing.get::<degree>() / 45.).round().rem_euclid(8.) as usize;
+    [
+        North, NorthEast, East, SouthEast, South, SouthWest, West, NorthWest,
+    ][octant]
+}
+
+fn runway_visual_range(rvr: Node) -> Result<RunwayVisibility<'static>, ReadError> {
+    let designator = child(rvr, "runway")
+        .ok_or(ReadError::MissingElement("iwxxm:runway"))
+        .and_then(runway_designator)?;
+    let operator = child(rvr, "meanRVROperator").and_then(|operator| operator.text());
+    let mean = child(rvr, "meanRVR")
+        .ok_or(ReadError::MissingElement("iwxxm:meanRVR"))
+        .and_then(|mean| raw_visibility(mean, operator))?
+        .ok_or(ReadError::MissingElement("iwxxm:meanRVR"))?;
+    let trend = match rvr.attribute("pastTendency") {
+        Some("UPWARD") => Some(VisibilityTrend::Up),
+        Some("DOWNWARD") => Some(VisibilityTrend::Down),
+        Some("NO_CHANGE") => Some(VisibilityTrend::NoChange),
+        Some(_) => return Err(invalid(rvr)),
+        None => None,
+    };
+    Ok(RunwayVisibility {
+        designator: designator.into(),
+        visibility: VisibilityType::Fixed(mean),
+        trend,
+    })
+}
+
+/// Absent if the weather is nil, i.e. `NSW`
+fn weather(node: Node) -> Result<Option<Weather>, ReadError> {
+    match code(node) {
+        Some(code) => weather_reports::weather(&code)
+            .map(Some)
+            .map_err(|_| invalid(node)),
+        None => Ok(None),
+    }
+}
+
+/// `NSC` and `NCD` are read from the nil reason
+fn cloud_cover(cloud: Node) -> Result<Vec<CloudCover>, ReadError> {
+    let without_base = |coverage| CloudCover {
+        coverage,
+        base: None,
+        cloud_type: None,
+        reported_base: None,
+    };
+    let cloud = match first_element(cloud) {
+        Some(cloud) => cloud,
+        None => {
+            return Ok(match nil_reason(cloud) {
+                Some("nothingOfOperationalSignificance") => {
+                    vec![without_base(CloudCoverage::NoSignificantCloud)]
+                }
+                Some("notDetectedByAutoSystem") => vec![without_base(CloudCoverage::NilCloud)],
+                _ => vec![],
+            })
+        }
+    };
+
+    let mut cloud_cover = vec![];
+    if let Some(vertical_visibility) = child(cloud, "verticalVisibility") {
+        cloud_cover.push(cloud_layer(
+            CloudCoverage::VerticalVisibility,
+            length(vertical_visibility)?,
+            None,
+        ));
+    }
+    for layer in children(cloud, "layer").filter_map(first_element) {
+        let amount = child(layer, "amount").ok_or(ReadError::MissingElement("iwxxm:amount"))?;
+        let coverage = match code(amount).as_deref() {
+            Some("1") => CloudCoverage::Few,
+            Some("2") => CloudCoverage::Scattered,
+            Some("3") => CloudCoverage::Broken,
+            Some("4") => CloudCoverage::Overcast,
+            Some(code) => CloudCoverage::try_from(code).map_err(|_| invalid(amount))?,
+            None => return Err(invalid(amount)),
+        };
+        let base = match child(layer, "base") {
+            Some(base) => length(base)?,
+            None => None,
+        };
+        let cloud_type = child(layer, "cloudType")
+            .and_then(code)
+            .map(|code| CloudType::try_from(code.as_str()))
+            .transpose()
+            .map_err(|_| invalid(layer))?;
+        cloud_cover.push(cloud_layer(coverage, base, cloud_type));
+    }
+    Ok(cloud_cover)
+}
+
+/// The base is rounded to three digit hundreds of feet like in TAC reports
+fn cloud_layer(
+    coverage: CloudCoverage,
+    base: Option<Length>,
+    cloud_type: Option<CloudType>,
+) -> CloudCover {
+    let reported_base = base.map(|base| ReportedValue::Decimal {
+        digits: (base.get::<foot>() / 100.).round() as u32,
+        width: 3,
+        scale: 0,
+    });
+    CloudCover {
+        coverage,
+        base: reported_base.map(|base| Length::new::<foot>(f64::from(base)) * 100.),
+        cloud_type,
+        reported_base,
+    }
+}
+
+fn sea_condition_of(sea_condition: Node) -> Result<WaterConditions, ReadError> {
+    let temperature = child(sea_condition, "seaSurfaceTemperature")
+        .map(temperature)
+        .transpose()?
+        .flatten();
+    let significant_wave_height = child(sea_condition, "significantWaveHeight")
+        .map(length)
+        .transpose()?
+        .flatten();
+    let surface_state = child(sea_condition, "seaState")
+        .and_then(|sea_state| Some((sea_state, code(sea_state)?)))
+        .map(|(sea_state, code)| {
+            WaterSurfaceState::try_from(code.as_str()).map_err(|_| invalid(sea_state))
+        })
+        .transpose()?;
+    Ok(WaterConditions {
+        temperature,
+        surface_state,
+        significant_wave_height,
+    })
+}
+
+/// Runway `88` is all runways and `99` repeats the previous report
+///
+/// Absent if the deposit was not reported.
+fn runway_report(runway_state: Node) -> Result<Option<RunwayReport<'static>>, ReadError> {
+    let designator = if runway_state.attribute("allRunways") == Some("true") {
+        "88".to_string()
+    } else if runway_state.attribute("fromPreviousReport") == Some("true") {
+        "99".to_string()
+    } else {
+        child(runway_state, "runway")
+            .ok_or(ReadError::MissingElement("iwxxm:runway"))
+            .and_then(runway_designator)?
+    };
+    let friction_or_braking_action = child(runway_state, "estimatedSurfaceFrictionOrBrakingAction")
+        .and_then(|node| Some((node, code(node)?)))
+        .map(|(node, code)| code.parse::<u8>().map_err(|_| invalid(node)))
+        .transpose()?;
+    let friction = friction_or_braking_action.filter(|code| *code <= 90);
+
+    let report_info = if runway_state.attribute("cleared") == Some("true") {
+        RunwayReportInfo::Cleared {
+            friction: friction.map(f64::from),
+        }
+    } else if runway_state.attribute("snowClosure") == Some("true") {
+        RunwayReportInfo::ClosedSnowOrIce
+    } else {
+        let deposit = match child(runway_state, "depositType").and_then(code) {
+            Some(deposit) => {
+                DepositType::try_from(deposit.as_str()).map_err(|_| invalid(runway_state))?
+            }
+            None => return Ok(None),
+        };
+        let coverage = child(runway_state, "contamination")
+            .and_then(code)
+            .map(|coverage| Coverage::try_from(coverage.as_str()))
+            .transpose()
+            .map_err(|_| invalid(runway_state))?;
+        let depth = child(runway_state, "depthOfDeposit")
+            .map(length)
+            .transpose()?
+            .flatten();
+        let braking_action = friction_or_braking_action
+            .filter(|code| *code > 90)
+            .map(|code| BrakingAction::try_from(code.to_string().as_str()))
+            .transpose()
+            .map_err(|_| invalid(runway_state))?;
+        RunwayReportInfo::Condition {
+            deposit,
+            coverage,
+            depth,
+            friction_coefficient: friction.map(|friction| f64::from(friction) / 100.),
+            braking_action,
+        }
+    };
+
+    Ok(Some(RunwayReport {
+        designator: designator.into(),
+        report_info,
+    }))
+}
+
+/// A trend without a time indicator that covers the two hours after the observation was untimed
+fn trend_forecast(
+    document: &Document,
+    trend: Node,
+    observed_at: Instant,
+) -> Result<Trend, ReadError> {
+    let forecast = match first_element(trend) {
+        Some(forecast) => forecast,
+        None if nil_reason(trend) == Some("noSignificantChange") => {
+            return Ok(Trend::NoSignificantChange)
+        }
+        None => return Err(invalid(trend)),
+    };
+
+    let phenomenon_time = child(forecast, "phenomenonTime")
+        .ok_or(ReadError::MissingElement("iwxxm:phenomenonTime"))?;
+    let time = match forecast.attribute("timeIndicator") {
+        Some("AT") => TrendTime::At(military_time(&time_instant(document, phenomenon_time)?)),
+        Some("FROM") => TrendTime::From(military_time(&time_period(document, phenomenon_time)?.0)),
+        Some("UNTIL") => {
+            TrendTime::Until(military_time(&time_period(document, phenomenon_time)?.1))
+        }
+        Some(_) => return Err(invalid(forecast)),
+        None => {
+            let (from, until) = time_period(document, phenomenon_time)?;
+            TrendTime::Range {
+                from: military_time(&from),
+                until: military_time(&until),
+            }
+        }
+    };
+    let untimed = TrendTime::Range {
+        from: military_time(&observed_at),
+        until: military_time(&(observed_at + chrono::Duration::minutes(120))),
+    };
+
+    let conditions = weather_conditions(forecast, "forecastWeather")?;
+    let trend = TrendReport {
+        time: Some(time).filter(|time| *time != untimed),
+        wind: conditions.wind,
+        visibility: conditions.visibility,
+        weather: conditions.weather,
+        cloud_cover: conditions.cloud_cover,
+        color_state: None,
+    };
+    match forecast.attribute("changeIndicator") {
+        Some("BECOMING") => Ok(Trend::Becoming(trend)),
+        Some("TEMPORARY_FLUCTUATIONS") => Ok(Trend::Temporarily(trend)),
+        _ => Err(invalid(forecast)),
+    }
+}
+
+/// Follows an `xlink:href` to a `gml:TimeInstant` elsewhere in the document
+fn time_instant(document: &Document, node: Node) -> Result<Instant, ReadError> {
+    time_position(document, node, "timePosition")
+}
+
+fn time_period(document: &Document, node: Node) -> Result<(Instant, Instant), ReadError> {
+    Ok((
+        time_position(document, node, "beginPosition")?,
+        time_position(document, node, "endPosition")?,
+    ))
+}
+
+fn time_position(
+    document: &Document,
+    node: Node,
+    name: &'static str,
+) -> Result<Instant, ReadError> {
+    let node = match node
+        .attribute((XLINK_NAMESPACE, "href"))
+        .and_then(|href| href.strip_prefix('#'))
+    {
+        Some(id) => document
+            .descendants()
+            .find(|node| node.attribute((GML_NAMESPACE, "id")) == Some(id))
+            .ok_or(ReadError::MissingElement("gml:TimeInstant"))?,
+        None => node,
+    };
+    let position = node
+        .descendants()
+        .find(|node| node.has_tag_name((GML_NAMESPACE, name)))
+        .ok_or(ReadError::MissingElement(match name {
+            "beginPosition" => "gml:beginPosition",
+            "endPosition" => "gml:endPosition",
+            _ => "gml:timePosition",
+        }))?;
+    position
+        .text()
+        .and_then(parse_time_position)
+        .ok_or_else(|| invalid(position))
+}
+
+fn is_iwxxm(node: Node) -> bool {
+    node.tag_name().namespace() == Some(IWXXM_NAMESPACE)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| is_iwxxm(*child) && child.tag_name().name() == name)
+}
+
+/// The value of a property element, absent if it is nil
+fn first_element<'a, 'input>(node: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    node.first_element_child()
+}
+
+/// Relative to the WMO nil reason register
+fn nil_reason<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute("nilReason")
+        .map(|reason| reason.strip_prefix(codes::NIL_REASON).unwrap_or(reason))
+}
+
+/// Value, unit of measure, and text, absent if it is nil
+fn measure<'a>(node: Node<'a, '_>) -> Result<Option<(f64, &'a str, &'a str)>, ReadError> {
+    if node.attribute((XSI_NAMESPACE, "nil")) == Some("true") {
+        return Ok(None);
+    }
+    let text = node.text().map(str::trim).ok_or_else(|| invalid(node))?;
+    let value = text.parse().map_err(|_| invalid(node))?;
+    let uom = node.attribute("uom").ok_or_else(|| invalid(node))?;
+    Ok(Some((value, uom, text)))
+}
+
+/// Last segment of an `xlink:href` into a code register, i.e. `+TSRA`
+fn code(node: Node) -> Option<String> {
+    let href = node.attribute((XLINK_NAMESPACE, "href"))?;
+    let segment = href.rsplit('/').next()?;
+    let mut code = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            let escaped = chars.by_ref().take(2).collect::<String>();
+            code.push(u8::from_str_radix(&escaped, 16).ok()? as char);
+        } else {
+            code.push(c);
+        }
+    }
+    Some(code).filter(|code| !code.is_empty())
+}
+
+fn invalid(node: Node) -> ReadError {
+    ReadError::InvalidValue(node.tag_name().name().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iwxxm::write_metar, parse::metar};
+    use uom::si::velocity::meter_per_second;
+
+    const METAR: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<iwxxm:METAR xmlns:iwxxm="http://icao.int/iwxxm/3.0" xmlns:aixm="http://www.aixm.aero/schema/5.1.1"
+    xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:xlink="http://www.w3.org/1999/xlink"
+    xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+    gml:id="uuid.3d4e2c1a" reportStatus="CORRECTION" permissibleUsage="OPERATIONAL" automatedStation="true">
+    <iwxxm:issueTime>
+        <gml:TimeInstant gml:id="uuid.ti">
+            <gml:timePosition>2021-09-14T06:50:00Z</gml:timePosition>
+        </gml:TimeInstant>
+    </iwxxm:issueTime>
+    <iwxxm:aerodrome>
+        <aixm:AirportHeliport gml:id="uuid.ad">
+            <aixm:timeSlice>
+                <aixm:AirportHeliportTimeSlice gml:id="uuid.ad-ts">
+                    <gml:validTime/>
+                    <aixm:interpretation>SNAPSHOT</aixm:interpretation>
+                    <aixm:designator>EDDF</aixm:designator>
+                    <aixm:name>FRANKFURT/MAIN</aixm:name>
+                    <aixm:locationIndicatorICAO>EDDF</aixm:locationIndicatorICAO>
+                </aixm:AirportHeliportTimeSlice>
+            </aixm:timeSlice>
+        </aixm:AirportHeliport>
+    </iwxxm:aerodrome>
+    <iwxxm:observationTime xlink:href="#uuid.ti"/>
+    <iwxxm:observation>
+        <iwxxm:MeteorologicalAerodromeObservation gml:id="uuid.obs" cloudAndVisibilityOK="false">
+            <iwxxm:airTemperature uom="Cel">-1.5</iwxxm:airTemperature>
+            <iwxxm:dewpointTemperature uom="Cel">-3</iwxxm:dewpointTemperature>
+            <iwxxm:qnh uom="hPa">1022</iwxxm:qnh>
+            <iwxxm:surfaceWind>
+                <iwxxm:AerodromeSurfaceWind variableWindDirection="true">
+                    <iwxxm:meanWindSpeed uom="m/s">2</iwxxm:meanWindSpeed>
+                </iwxxm:AerodromeSurfaceWind>
+            </iwxxm:surfaceWind>
+            <iwxxm:visibility>
+                <iwxxm:AerodromeHorizontalVisibility>
+                    <iwxxm:prevailingVisibility uom="m">350</iwxxm:prevailingVisibility>
+                </iwxxm:AerodromeHorizontalVisibility>
+            </iwxxm:visibility>
+            <iwxxm:rvr>
+                <iwxxm:AerodromeRunwayVisualRange pastTendency="DOWNWARD">
+                    <iwxxm:runway>
+                        <aixm:RunwayDirection gml:id="uuid.rwy">
+                            <aixm:timeSlice>
+                                <aixm:RunwayDirectionTimeSlice gml:id="uuid.rwy-ts">
+                                    <gml:validTime/>
+                                    <aixm:interpretation>SNAPSHOT</aixm:interpretation>
+                                    <aixm:designator>25C</aixm:designator>
+                                </aixm:RunwayDirectionTimeSlice>
+                            </aixm:timeSlice>
+                        </aixm:RunwayDirection>
+                    </iwxxm:runway>
+                    <iwxxm:meanRVR uom="m">50</iwxxm:meanRVR>
+                    <iwxxm:meanRVROperator>BELOW</iwxxm:meanRVROperator>
+                </iwxxm:AerodromeRunwayVisualRange>
+            </iwxxm:rvr>
+            <iwxxm:presentWeather xlink:href="http://codes.wmo.int/306/4678/FZFG"/>
+            <iwxxm:cloud>
+                <iwxxm:AerodromeCloud>
+                    <iwxxm:verticalVisibility uom="[ft_i]">100</iwxxm:verticalVisibility>
+                </iwxxm:AerodromeCloud>
+            </iwxxm:cloud>
+            <iwxxm:runwayState>
+                <iwxxm:AerodromeRunwayState allRunways="true">
+                    <iwxxm:depositType xlink:href="http://codes.wmo.int/bufr4/codeflag/0-20-086/7"/>
+                    <iwxxm:contamination xlink:href="http://codes.wmo.int/bufr4/codeflag/0-20-087/9"/>
+                    <iwxxm:depthOfDeposit uom="N/A" xsi:nil="true" nilReason="http://codes.wmo.int/common/nil/notObservable"/>
+                    <iwxxm:estimatedSurfaceFrictionOrBrakingAction xlink:href="http://codes.wmo.int/bufr4/codeflag/0-20-089/35"/>
+                </iwxxm:AerodromeRunwayState>
+            </iwxxm:runwayState>
+        </iwxxm:MeteorologicalAerodromeObservation>
+    </iwxxm:observation>
+    <iwxxm:trendForecast>
+        <iwxxm:MeteorologicalAerodromeTrendForecast gml:id="uuid.trend" changeIndicator="BECOMING" cloudAndVisibilityOK="false" timeIndicator="AT">
+            <iwxxm:phenomenonTime>
+                <gml:TimeInstant gml:id="uuid.trend-ti">
+                    <gml:timePosition>2021-09-14T07:30:00Z</gml:timePosition>
+                </gml:TimeInstant>
+            </iwxxm:phenomenonTime>
+            <iwxxm:prevailingVisibility uom="m">1500</iwxxm:prevailingVisibility>
+            <iwxxm:forecastWeather xlink:href="http://codes.wmo.int/306/4678/BR"/>
+        </iwxxm:MeteorologicalAerodromeTrendForecast>
+    </iwxxm:trendForecast>
+</iwxxm:METAR>
+"##;
+
+    const TAF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<iwxxm:TAF xmlns:iwxxm="http://icao.int/iwxxm/3.0" xmlns:aixm="http://www.aixm.aero/schema/5.1.1"
+    xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:xlink="http://www.w3.org/1999/xlink"
+    gml:id="uuid.taf" reportStatus="NORMAL" permissibleUsage="OPERATIONAL">
+    <iwxxm:issueTime>
+        <gml:TimeInstant gml:id="uuid.ti">
+            <gml:timePosition>2021-09-14T05:00:00Z</gml:timePosition>
+        </gml:TimeInstant>
+    </iwxxm:issueTime>
+    <iwxxm:aerodrome>
+        <aixm:AirportHeliport gml:id="uuid.ad">
+            <aixm:timeSlice>
+                <aixm:AirportHeliportTimeSlice gml:id="uuid.ad-ts">
+                    <gml:validTime/>
+                    <aixm:interpretation>SNAPSHOT</aixm:interpretation>
+                    <aixm:designator>LFPG</aixm:designator>
+                </aixm:AirportHeliportTimeSlice>
+            </aixm:timeSlice>
+        </aixm:AirportHeliport>
+    </iwxxm:aerodrome>
+    <iwxxm:validPeriod>
+        <gml:TimePeriod gml:id="uuid.tp">
+            <gml:beginPosition>2021-09-14T06:00:00Z</gml:beginPosition>
+            <gml:endPosition>2021-09-15T12:00:00Z</gml:endPosition>
+        </gml:TimePeriod>
+    </iwxxm:validPeriod>
+    <iwxxm:baseForecast>
+        <iwxxm:MeteorologicalAerodromeForecast gml:id="uuid.base" cloudAndVisibilityOK="false">
+            <iwxxm:phenomenonTime xlink:href="#uuid.tp"/>
+            <iwxxm:prevailingVisibility uom="m">10000</iwxxm:prevailingVisibility>
+            <iwxxm:prevailingVisibilityOperator>ABOVE</iwxxm:prevailingVisibilityOperator>
+            <iwxxm:surfaceWind>
+                <iwxxm:AerodromeSurfaceWindForecast variableWindDirection="false">
+                    <iwxxm:meanWindDirection uom="deg">230</iwxxm:meanWindDirection>
+                    <iwxxm:meanWindSpeed uom="[kn_i]">12</iwxxm:meanWindSpeed>
+                    <iwxxm:windGustSpeed uom="[kn_i]">25</iwxxm:windGustSpeed>
+                </iwxxm:AerodromeSurfaceWindForecast>
+            </iwxxm:surfaceWind>
+            <iwxxm:cloud>
+                <iwxxm:AerodromeCloudForecast>
+                    <iwxxm:layer>
+                        <iwxxm:CloudLayer>
+                            <iwxxm:amount xlink:href="http://codes.wmo.int/49-2/CloudAmountReportedAtAerodrome/BKN"/>
+                            <iwxxm:base uom="[ft_i]">2500</iwxxm:base>
+                        </iwxxm:CloudLayer>
+                    </iwxxm:layer>
+                </iwxxm:AerodromeCloudForecast>
+            </iwxxm:cloud>
+        </iwxxm:MeteorologicalAerodromeForecast>
+    </iwxxm:baseForecast>
+    <iwxxm:changeForecast>
+        <iwxxm:MeteorologicalAerodromeForecast gml:id="uuid.change" changeIndicator="PROBABILITY_30_TEMPORARY_FLUCTUATIONS" cloudAndVisibilityOK="false">
+            <iwxxm:phenomenonTime>
+                <gml:TimePeriod gml:id="uuid.change-tp">
+                    <gml:beginPosition>2021-09-14T14:00:00Z</gml:beginPosition>
+                    <gml:endPosition>2021-09-14T18:00:00Z</gml:endPosition>
+                </gml:TimePeriod>
+            </iwxxm:phenomenonTime>
+            <iwxxm:prevailingVisibility uom="m">4000</iwxxm:prevailingVisibility>
+            <iwxxm:weather xlink:href="http://codes.wmo.int/306/4678/%2BTSRA"/>
+            <iwxxm:cloud>
+                <iwxxm:AerodromeCloudForecast>
+                    <iwxxm:layer>
+                        <iwxxm:CloudLayer>
+                            <iwxxm:amount xlink:href="http://codes.wmo.int/49-2/CloudAmountReportedAtAerodrome/SCT"/>
+                            <iwxxm:base uom="[ft_i]">3000</iwxxm:base>
+                            <iwxxm:cloudType xlink:href="http://codes.wmo.int/49-2/SigConvectiveCloudType/CB"/>
+                        </iwxxm:CloudLayer>
+                    </iwxxm:layer>
+                </iwxxm:AerodromeCloudForecast>
+            </iwxxm:cloud>
+        </iwxxm:MeteorologicalAerodromeForecast>
+    </iwxxm:changeForecast>
+</iwxxm:TAF>
+"##;
+
+    #[test]
+    fn read_written_metar() {
+        for tac in [
+            "SPECI EGLL 291020Z 24015G28KT 200V280 4000 1200NE R27L/1100U +TSRA BKN008CB 18/16 Q1004 RERA WS R27L R27L/290195 BECMG FM1100 TL1200 27010KT 9999 SCT020 TEMPO 3000 SHRA",
+            "METAR ENQA 290350Z 33015KT 9999 FEW030 12/08 Q1011 R88/CLRD70 W12/S4",
+            "METAR KSEA 290353Z AUTO 00000KT 9999 NCD 19/ Q1013 WS ALL RWY NOSIG",
+            "METAR UUEE 181030Z 24004MPS 0800 FG VV001 06/M01 Q0998",
+            "METAR KSEA 290353Z NIL",
+        ] {
+            let expected = metar(tac).unwrap();
+            let xml = write_metar(&expected, 2021, 8).unwrap();
+            assert_eq!(read_metar(&xml).unwrap(), expected, "{}", tac);
+        }
+    }
+
+    #[test]
+    fn read_metar_document() {
+        let report = read_metar(METAR).unwrap();
+        assert_eq!(report.identifier, "EDDF");
+        assert_eq!(report.kind, Some(ReportKind::Metar));
+        assert_eq!(
+            report.observation_flags,
+            vec![
+                ObservationFlag::Correction { letter: None },
+                ObservationFlag::Auto
+            ]
+        );
+        assert_eq!(report.observation_time.unwrap().day_of_month, 14);
+
+        let temperatures = report.temperatures.unwrap();
+        assert_eq!(temperatures.air.get::<degree_celsius>(), -1.5);
+        let wind = report.wind.unwrap();
+        assert_eq!(wind.direction, None);
+        assert_eq!(wind.unit, SpeedUnit::MetersPerSecond);
+        assert_eq!(wind.speed.unwrap().get::<meter_per_second>(), 2.);
+
+        let rvr = &report.runway_visibilities[0];
+        assert_eq!(rvr.designator, "25C");
+        assert_eq!(rvr.trend, Some(VisibilityTrend::Down));
+        assert!(matches!(
+            rvr.visibility,
+            VisibilityType::Fixed(RawVisibility {
+                out_of_range: Some(OutOfRange::Below),
+                ..
+            })
+        ));
+
+        assert_eq!(
+            report.weather,
+            vec![weather_reports::weather("FZFG").unwrap()]
+        );
+        assert_eq!(
+            report.cloud_cover[0].coverage,
+            CloudCoverage::VerticalVisibility
+        );
+        assert_eq!(
+            report.cloud_cover[0].reported_base,
+            Some(ReportedValue::Decimal {
+                digits: 1,
+                width: 3,
+                scale: 0
+            })
+        );
+
+        assert_eq!(
+            report.runway_reports,
+            vec![RunwayReport {
+                designator: "88".into(),
+                report_info: RunwayReportInfo::Condition {
+                    deposit: DepositType::Ice,
+                    coverage: Some(Coverage::High),
+                    depth: None,
+                    friction_coefficient: Some(0.35),
+                    braking_action: None,
+                },
+            }]
+        );
+
+        match &report.trends[0] {
+            Trend::Becoming(trend) => {
+                assert_eq!(
+                    trend.time,
+                    Some(TrendTime::At(MilitaryTime {
+                        hour: 7,
+                        minute: 30
+                    }))
+                );
+                assert_eq!(trend.weather, vec![weather_reports::weather("BR").unwrap()]);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn read_taf_document() {
+        let report = read_taf(TAF).unwrap();
+        assert_eq!(report.identifier, "LFPG");
+        assert_eq!(
+            report.valid_period,
+            Some(TimeRange {
+                begin: DateTime {
+                    day_of_month: 14,
+                    time: MilitaryTime { hour: 6, minute: 0 },
+                    is_zulu: true,
+                },
+                end: DateTime {
+                    day_of_month: 15,
+                    time: MilitaryTime {
+                        hour: 12,
+                        minute: 0
+                    },
+                    is_zulu: true,
+                },
+            })
+        );
+        let base_forecast = report.base_forecast.unwrap();
+        let wind = base_forecast.wind.unwrap();
+        assert_eq!(
+            wind.reported_peak_gust,
+            Some(ReportedValue::Decimal {
+                digits: 25,
+                width: 2,
+                scale: 0
+            })
+        );
+        assert_eq!(
+            base_forecast
+                .visibility
+                .unwrap()
+                .prevailing
+                .unwrap()
+                .reported_distance,
+            ReportedValue::Decimal {
+                digits: 9999,
+                width: 4,
+                scale: 0
+            }
+        );
+        assert_eq!(base_forecast.cloud_cover[0].coverage, CloudCoverage::Broken);
+
+        let change_forecast = &report.change_forecasts[0];
+        assert_eq!(
+            change_forecast.indicator,
+            ChangeIndicator::Probability30TemporaryFluctuations
+        );
+        assert_eq!(change_forecast.indicator.probability(), Some(30));
+        assert_eq!(change_forecast.period.begin.day_of_month, 14);
+        assert_eq!(
+            change_forecast.period.begin.time,
+            MilitaryTime {
+                hour: 14,
+                minute: 0
+            }
+        );
+        assert_eq!(
+            change_forecast.period.end.time,
+            MilitaryTime {
+                hour: 18,
+                minute: 0
+            }
+        );
+        assert_eq!(
+            change_forecast.conditions.weather,
+            vec![weather_reports::weather("+TSRA").unwrap()]
+        );
+        assert_eq!(
+            change_forecast.conditions.cloud_cover[0].cloud_type,
+            Some(CloudType::Cumulonimbus)
+        );
+    }
+
+    #[test]
+    fn reject_other_documents() {
+        assert_eq!(
+            read_metar(TAF),
+            Err(ReadError::UnexpectedReport("TAF".to_string()))
+        );
+        assert_eq!(
+            read_taf(METAR),
+            Err(ReadError::UnexpectedReport("METAR".to_string()))
+        );
+        assert!(matches!(
+            read_metar("<iwxxm:METAR>"),
+            Err(ReadError::Xml(_))
+        ));
+        assert_eq!(
+            read_metar(&METAR.replace("FZFG", "XX")),
+            Err(ReadError::InvalidValue("presentWeather".to_string()))
+        );
+    }
+}
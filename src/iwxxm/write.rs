@@ -0,0 +1,986 @@
+use std::fmt;
+
+use uom::si::{
+    angle::degree,
+    f64::Velocity,
+    length::{foot, meter, millimeter},
+    pressure::hectopascal,
+    thermodynamic_temperature::degree_celsius,
+    velocity::{kilometer_per_hour, knot, meter_per_second},
+};
+
+use super::*;
+use crate::tokens::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WriteError {
+    /// IWXXM reports must have an observation time
+    MissingObservationTime,
+    /// A time in the report does not exist in the given month
+    InvalidTime,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::MissingObservationTime => write!(f, "report has no observation time"),
+            WriteError::InvalidTime => write!(f, "report has a time that does not exist"),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Serializes a METAR or SPECI as an IWXXM 3.0 document
+///
+/// Reports only carry the day of the month, so the year and 1-based month of the observation must be provided.
+/// Missing values are marked with a nil reason.
+///
+/// Remarks, color states, and accumulated rainfall have no IWXXM equivalent and are left out.
+/// IWXXM does not have RVR variations either, so the lower bound of a varying RVR is used.
+pub fn write_metar(report: &MetarReport, year: i32, month: u8) -> Result<String, WriteError> {
+    let observation_time = report
+        .observation_time
+        .ok_or(WriteError::MissingObservationTime)?;
+    let observed_at = observation_time
+        .as_datetime(year, month as u32)
+        .ok_or(WriteError::InvalidTime)?;
+
+    let (name, prefix) = match report.kind {
+        Some(ReportKind::Speci) => ("iwxxm:SPECI", "speci"),
+        _ => ("iwxxm:METAR", "metar"),
+    };
+    let report_status = if report
+        .observation_flags
+        .iter()
+        .any(|flag| matches!(flag, ObservationFlag::Correction { .. }))
+    {
+        "CORRECTION"
+    } else {
+        "NORMAL"
+    };
+    let automated = report.observation_flags.contains(&ObservationFlag::Auto);
+    let schema_location = format!("{} {}", IWXXM_NAMESPACE, IWXXM_SCHEMA_LOCATION);
+    let id = format!(
+        "{}-{}-{}",
+        prefix,
+        report.identifier,
+        observed_at.format("%Y%m%dT%H%MZ")
+    );
+
+    let mut writer = Writer::default();
+    writer
+        .xml
+        .push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writer.open(
+        name,
+        &[
+            ("xmlns:iwxxm", IWXXM_NAMESPACE),
+            ("xmlns:aixm", AIXM_NAMESPACE),
+            ("xmlns:gml", GML_NAMESPACE),
+            ("xmlns:xlink", XLINK_NAMESPACE),
+            ("xmlns:xsi", XSI_NAMESPACE),
+            ("xsi:schemaLocation", &schema_location),
+            ("gml:id", &id),
+            ("reportStatus", report_status),
+            ("permissibleUsage", "OPERATIONAL"),
+            ("automatedStation", boolean(automated)),
+        ],
+    );
+
+    writer.open("iwxxm:issueTime", &[]);
+    let observation_time_id = writer.time_instant(observed_at);
+    writer.close("iwxxm:issueTime");
+    aerodrome(&mut writer, &report.identifier);
+    writer.empty(
+        "iwxxm:observationTime",
+        &[("xlink:href", &format!("#{}", observation_time_id))],
+    );
+
+    if report.observation_flags.contains(&ObservationFlag::Nil) {
+        writer.nil("iwxxm:observation", "missing");
+    } else {
+        observation(&mut writer, report);
+        for trend in &report.trends {
+            trend_forecast(&mut writer, trend, observed_at)?;
+        }
+    }
+
+    writer.close(name);
+    Ok(writer.xml)
+}
+
+fn aerodrome(writer: &mut Writer, identifier: &str) {
+    writer.open("iwxxm:aerodrome", &[]);
+    let id = writer.id("aerodrome");
+    writer.open("aixm:AirportHeliport", &[("gml:id", &id)]);
+    writer.open("aixm:timeSlice", &[]);
+    let id = writer.id("aerodrome-ts");
+    writer.open("aixm:AirportHeliportTimeSlice", &[("gml:id", &id)]);
+    writer.empty("gml:validTime", &[]);
+    writer.text("aixm:interpretation", &[], "SNAPSHOT");
+    writer.text("aixm:designator", &[], identifier);
+    writer.text("aixm:locationIndicatorICAO", &[], identifier);
+    writer.close("aixm:AirportHeliportTimeSlice");
+    writer.close("aixm:timeSlice");
+    writer.close("aixm:AirportHeliport");
+    writer.close("iwxxm:aerodrome");
+}
+
+fn runway(writer: &mut Writer, designator: &str) {
+    writer.open("iwxxm:runway", &[]);
+    let id = writer.id("runway");
+    writer.open("aixm:RunwayDirection", &[("gml:id", &id)]);
+    writer.open("aixm:timeSlice", &[]);
+    let id = writer.id("runway-ts");
+    writer.open("aixm:RunwayDirectionTimeSlice", &[("gml:id", &id)]);
+    writer.empty("gml:validTime", &[]);
+    writer.text("aixm:interpretation", &[], "SNAPSHOT");
+    writer.text("aixm:designator", &[], designator);
+    writer.close("aixm:RunwayDirectionTimeSlice");
+    writer.close("aixm:timeSlice");
+    writer.close("aixm:RunwayDirection");
+    writer.close("iwxxm:runway");
+}
+
+fn observation(writer: &mut Writer, report: &MetarReport) {
+    writer.open("iwxxm:observation", &[]);
+    let id = writer.id("obs");
+    writer.open(
+        "iwxxm:MeteorologicalAerodromeObservation",
+        &[
+            ("gml:id", &id),
+            ("cloudAndVisibilityOK", boolean(report.cavok)),
+        ],
+    );
+
+    let temperatures = report.temperatures;
+    match temperatures {
+        Some(temperatures) => writer.measure(
+            "iwxxm:airTemperature",
+            "Cel",
+            temperatures.air.get::<degree_celsius>(),
+        ),
+        None => writer.nil_measure("iwxxm:airTemperature", "missing"),
+    }
+    match temperatures.and_then(|temperatures| temperatures.dewpoint) {
+        Some(dewpoint) => writer.measure(
+            "iwxxm:dewpointTemperature",
+            "Cel",
+            dewpoint.get::<degree_celsius>(),
+        ),
+        None => writer.nil_measure("iwxxm:dewpointTemperature", "missing"),
+    }
+    match report.pressure {
+        Some(pressure) => writer.measure("iwxxm:qnh", "hPa", pressure.get::<hectopascal>()),
+        None => writer.nil_measure("iwxxm:qnh", "missing"),
+    }
+
+    match report.wind.filter(|wind| wind.speed.is_some()) {
+        Some(wind) => surface_wind(writer, &wind, false),
+        None => writer.nil("iwxxm:surfaceWind", "missing"),
+    }
+
+    if !report.cavok {
+        match report.visibility {
+            Some(visibility) => horizontal_visibility(writer, &visibility),
+            None => writer.nil("iwxxm:visibility", "missing"),
+        }
+        for runway_visibility in report.runway_visibilities.iter().take(4) {
+            runway_visual_range(writer, runway_visibility);
+        }
+        for weather in report.weather.iter().take(3) {
+            writer.href(
+                "iwxxm:presentWeather",
+                codes::PRESENT_WEATHER,
+                &String::from(weather.clone()),
+            );
+        }
+        cloud(writer, &report.cloud_cover, "iwxxm:AerodromeCloud");
+    }
+
+    for weather in report.recent_weather.iter().take(3) {
+        writer.href(
+            "iwxxm:recentWeather",
+            codes::RECENT_WEATHER,
+            &String::from(weather.clone()),
+        );
+    }
+
+    if !report.wind_shear.is_empty() {
+        wind_shear(writer, &report.wind_shear);
+    }
+
+    if let Some(water_conditions) = report.water_conditions {
+        sea_condition(writer, &water_conditions);
+    }
+
+    for runway_report in &report.runway_reports {
+        runway_state(writer, runway_report);
+    }
+
+    writer.close("iwxxm:MeteorologicalAerodromeObservation");
+    writer.close("iwxxm:observation");
+}
+
+/// Trend forecasts only have a mean direction, speed, and gust
+fn surface_wind(writer: &mut Writer, wind: &Wind, trend: bool) {
+    writer.open("iwxxm:surfaceWind", &[]);
+    let element = if trend {
+        writer.open("iwxxm:AerodromeSurfaceWindTrendForecast", &[]);
+        "iwxxm:AerodromeSurfaceWindTrendForecast"
+    } else {
+        writer.open(
+            "iwxxm:AerodromeSurfaceWind",
+            &[("variableWindDirection", boolean(wind.direction.is_none()))],
+        );
+        "iwxxm:AerodromeSurfaceWind"
+    };
+
+    if let Some(direction) = wind.direction {
+        writer.measure("iwxxm:meanWindDirection", "deg", direction.get::<degree>());
+    }
+    if let Some(speed) = wind.speed {
+        let (unit, value) = wind_speed(wind.unit, speed, wind.reported_speed);
+        writer.measure("iwxxm:meanWindSpeed", unit, value);
+    }
+    if let Some(peak_gust) = wind.peak_gust {
+        let (unit, value) = wind_speed(wind.unit, peak_gust, wind.reported_peak_gust);
+        writer.measure("iwxxm:windGustSpeed", unit, value);
+    }
+    if let (Some((counter_clockwise, clockwise)), false) = (wind.variance, trend) {
+        writer.measure(
+            "iwxxm:extremeClockwiseWindDirection",
+            "deg",
+            clockwise.get::<degree>(),
+        );
+        writer.measure(
+            "iwxxm:extremeCounterClockwiseWindDirection",
+            "deg",
+            counter_clockwise.get::<degree>(),
+        );
+    }
+
+    writer.close(element);
+    writer.close("iwxxm:surfaceWind");
+}
+
+/// Unit of measure and value, preferring the value exactly as reported
+fn wind_speed(
+    unit: SpeedUnit,
+    speed: Velocity,
+    reported: Option<ReportedValue>,
+) -> (&'static str, f64) {
+    let (uom, converted) = match unit {
+        SpeedUnit::Knots => ("[kn_i]", speed.get::<knot>()),
+        SpeedUnit::MetersPerSecond => ("m/s", speed.get::<meter_per_second>()),
+        SpeedUnit::KilometersPerHour => ("km/h", speed.get::<kilometer_per_hour>()),
+    };
+    (uom, reported.map(f64::from).unwrap_or(converted))
+}
+
+fn horizontal_visibility(writer: &mut Writer, visibility: &Visibility) {
+    writer.open("iwxxm:visibility", &[]);
+    writer.open("iwxxm:AerodromeHorizontalVisibility", &[]);
+
+    match visibility.prevailing {
+        Some(prevailing) => {
+            let (distance, out_of_range) = visibility_distance(&prevailing);
+            writer.measure("iwxxm:prevailingVisibility", "m", distance);
+            if let Some(out_of_range) = out_of_range {
+                writer.text(
+                    "iwxxm:prevailingVisibilityOperator",
+                    &[],
+                    operator(out_of_range),
+                );
+            }
+        }
+        None => writer.nil_measure("iwxxm:prevailingVisibility", "missing"),
+    }
+    match visibility.minimum {
+        Some(DirectionalOrRawVisiblity::Raw(minimum)) => writer.measure(
+            "iwxxm:minimumVisibility",
+            "m",
+            minimum.distance.get::<meter>(),
+        ),
+        Some(DirectionalOrRawVisiblity::Directional(minimum)) => {
+            writer.measure(
+                "iwxxm:minimumVisibility",
+                "m",
+                minimum.distance.distance.get::<meter>(),
+            );
+            writer.measure(
+                "iwxxm:minimumVisibilityDirection",
+                "deg",
+                minimum.direction.bearing().get::<degree>(),
+            );
+        }
+        None => {}
+    }
+
+    writer.close("iwxxm:AerodromeHorizontalVisibility");
+    writer.close("iwxxm:visibility");
+}
+
+/// Distance in meters, where `9999` is 10 km or more
+fn visibility_distance(visibility: &RawVisibility) -> (f64, Option<OutOfRange>) {
+    let unit_is_meters = matches!(visibility.unit, None | Some(DistanceUnit::Meters));
+    if unit_is_meters && f64::from(visibility.reported_distance) >= 9999. {
+        (10000., Some(OutOfRange::Above))
+    } else {
+        (visibility.distance.get::<meter>(), visibility.out_of_range)
+    }
+}
+
+fn operator(out_of_range: OutOfRange) -> &'static str {
+    match out_of_range {
+        OutOfRange::Above => "ABOVE",
+        OutOfRange::Below => "BELOW",
+    }
+}
+
+fn runway_visual_range(writer: &mut Writer, runway_visibility: &RunwayVisibility) {
+    let past_tendency = runway_visibility.trend.map(|trend| match trend {
+        VisibilityTrend::Up => "UPWARD",
+        VisibilityTrend::Down => "DOWNWARD",
+        VisibilityTrend::NoChange => "NO_CHANGE",
+    });
+    let range = match runway_visibility.visibility {
+        VisibilityType::Fixed(range) => range,
+        VisibilityType::Varying { lower, .. } => lower,
+    };
+
+    writer.open("iwxxm:rvr", &[]);
+    match past_tendency {
+        Some(past_tendency) => writer.open(
+            "iwxxm:AerodromeRunwayVisualRange",
+            &[("pastTendency", past_tendency)],
+        ),
+        None => writer.open("iwxxm:AerodromeRunwayVisualRange", &[]),
+    }
+    runway(writer, &runway_visibility.designator);
+    writer.measure("iwxxm:meanRVR", "m", range.distance.get::<meter>());
+    if let Some(out_of_range) = range.out_of_range {
+        writer.text("iwxxm:meanRVROperator", &[], operator(out_of_range));
+    }
+    writer.close("iwxxm:AerodromeRunwayVisualRange");
+    writer.close("iwxxm:rvr");
+}
+
+/// Clouds are absent from the document if there were none in the report
+fn cloud(writer: &mut Writer, cloud_cover: &[CloudCover], element: &str) {
+    let vertical_visibility = cloud_cover
+        .iter()
+        .find(|cloud| cloud.coverage == CloudCoverage::VerticalVisibility);
+    let layers = cloud_cover
+        .iter()
+        .filter(|cloud| {
+            matches!(
+                cloud.coverage,
+                CloudCoverage::Few
+                    | CloudCoverage::Scattered
+                    | CloudCoverage::Broken
+                    | CloudCoverage::Overcast
+            )
+        })
+        .take(4)
+        .collect::<Vec<_>>();
+
+    if vertical_visibility.is_none() && layers.is_empty() {
+        let nil_reason = cloud_cover.iter().find_map(|cloud| match cloud.coverage {
+            CloudCoverage::NoCloud | CloudCoverage::NoSignificantCloud => {
+                Some("nothingOfOperationalSignificance")
+            }
+            CloudCoverage::NilCloud | CloudCoverage::Clear => Some("notDetectedByAutoSystem"),
+            _ => None,
+        });
+        if let Some(nil_reason) = nil_reason {
+            writer.nil("iwxxm:cloud", nil_reason);
+        }
+        return;
+    }
+
+    writer.open("iwxxm:cloud", &[]);
+    writer.open(element, &[]);
+    if let Some(vertical_visibility) = vertical_visibility {
+        match vertical_visibility.base {
+            Some(base) => writer.measure("iwxxm:verticalVisibility", "[ft_i]", base.get::<foot>()),
+            None => writer.nil_measure("iwxxm:verticalVisibility", "notObservable"),
+        }
+    }
+    for layer in layers {
+        writer.open("iwxxm:layer", &[]);
+        writer.open("iwxxm:CloudLayer", &[]);
+        writer.href(
+            "iwxxm:amount",
+            codes::CLOUD_AMOUNT,
+            <&str>::from(layer.coverage),
+        );
+        match layer.base {
+            Some(base) => writer.measure("iwxxm:base", "[ft_i]", base.get::<foot>()),
+            None => writer.nil_measure("iwxxm:base", "notObservable"),
+        }
+        if let Some(cloud_type @ (CloudType::Cumulonimbus | CloudType::ToweringCumulus)) =
+            layer.cloud_type
+        {
+            writer.href("iwxxm:cloudType", codes::CLOUD_TYPE, cloud_type.into());
+        }
+        writer.close("iwxxm:CloudLayer");
+        writer.close("iwxxm:layer");
+    }
+    writer.close(element);
+    writer.close("iwxxm:cloud");
+}
+
+fn wind_shear(writer: &mut Writer, wind_shear: &[WindShear]) {
+    writer.open("iwxxm:windShear", &[]);
+    if wind_shear.contains(&WindShear::AllRunways) {
+        writer.empty("iwxxm:AerodromeWindShear", &[("allRunways", "true")]);
+    } else {
+        writer.open("iwxxm:AerodromeWindShear", &[]);
+        for wind_shear in wind_shear {
+            if let WindShear::Runway(designator) = wind_shear {
+                runway(writer, designator);
+            }
+        }
+        writer.close("iwxxm:AerodromeWindShear");
+    }
+    writer.close("iwxxm:windShear");
+}
+
+fn sea_condition(writer: &mut Writer, water_conditions: &WaterConditions) {
+    writer.open("iwxxm:seaCondition", &[]);
+    writer.open("iwxxm:AerodromeSeaCondition", &[]);
+    match water_conditions.temperature {
+        Some(temperature) => writer.measure(
+            "iwxxm:seaSurfaceTemperature",
+            "Cel",
+            temperature.get::<degree_celsius>(),
+        ),
+        None => writer.nil_measure("iwxxm:seaSurfaceTemperature", "missing"),
+    }
+    match (
+        water_conditions.significant_wave_height,
+        water_conditions.surface_state,
+    ) {
+        (Some(height), _) => {
+            writer.measure("iwxxm:significantWaveHeight", "m", height.get::<meter>())
+        }
+        (None, Some(state)) => writer.href("iwxxm:seaState", codes::SEA_STATE, state.into()),
+        (None, None) => writer.nil_measure("iwxxm:significantWaveHeight", "missing"),
+    }
+    writer.close("iwxxm:AerodromeSeaCondition");
+    writer.close("iwxxm:seaCondition");
+}
+
+fn runway_state(writer: &mut Writer, runway_report: &RunwayReport) {
+    let mut attributes = vec![];
+    let reported_runway = runway_report.runway();
+    match reported_runway {
+        ReportedRunway::AllRunways => attributes.push(("allRunways", "true")),
+        ReportedRunway::RepeatedReport => attributes.push(("fromPreviousReport", "true")),
+        ReportedRunway::Runway(_) => {}
+    }
+    match runway_report.report_info {
+        RunwayReportInfo::Cleared { .. } => attributes.push(("cleared", "true")),
+        RunwayReportInfo::ClosedSnowOrIce => attributes.push(("snowClosure", "true")),
+        RunwayReportInfo::Condition { .. } => {}
+    }
+
+    writer.open("iwxxm:runwayState", &[]);
+    writer.open("iwxxm:AerodromeRunwayState", &attributes);
+    if let ReportedRunway::Runway(designator) = &reported_runway {
+        runway(writer, designator);
+    }
+    match runway_report.report_info {
+        RunwayReportInfo::Cleared { friction } => {
+            if let Some(friction) = friction {
+                writer.href(
+                    "iwxxm:estimatedSurfaceFrictionOrBrakingAction",
+                    codes::FRICTION_OR_BRAKING_ACTION,
+                    &(friction.round() as u8).to_string(),
+                );
+            }
+        }
+        RunwayReportInfo::ClosedSnowOrIce => {}
+        RunwayReportInfo::Condition {
+            deposit,
+            coverage,
+            depth,
+            friction_coefficient,
+            braking_action,
+        } => {
+            writer.href("iwxxm:depositType", codes::DEPOSIT_TYPE, deposit.into());
+            if let Some(coverage) = coverage {
+                writer.href("iwxxm:contamination", codes::CONTAMINATION, coverage.into());
+            }
+            match depth {
+                Some(depth) => {
+                    writer.measure("iwxxm:depthOfDeposit", "mm", depth.get::<millimeter>())
+                }
+                None => writer.nil_measure("iwxxm:depthOfDeposit", "notObservable"),
+            }
+            let friction_or_braking_action = match (braking_action, friction_coefficient) {
+                (Some(braking_action), _) => Some(<&str>::from(braking_action).to_string()),
+                (None, Some(friction_coefficient)) => {
+                    Some(((friction_coefficient * 100.).round() as u8).to_string())
+                }
+                (None, None) => None,
+            };
+            if let Some(code) = friction_or_braking_action {
+                writer.href(
+                    "iwxxm:estimatedSurfaceFrictionOrBrakingAction",
+                    codes::FRICTION_OR_BRAKING_ACTION,
+                    &code,
+                );
+            }
+        }
+    }
+    writer.close("iwxxm:AerodromeRunwayState");
+    writer.close("iwxxm:runwayState");
+}
+
+/// Trends are valid for two hours after the observation unless their time says otherwise
+fn trend_forecast(
+    writer: &mut Writer,
+    trend: &Trend,
+    observed_at: Instant,
+) -> Result<(), WriteError> {
+    let (change_indicator, trend) = match trend {
+        Trend::NoSignificantChange => {
+            writer.nil("iwxxm:trendForecast", "noSignificantChange");
+            return Ok(());
+        }
+        Trend::Becoming(trend) => ("BECOMING", trend),
+        Trend::Temporarily(trend) => ("TEMPORARY_FLUCTUATIONS", trend),
+    };
+    let next = |time: MilitaryTime| {
+        time.resolve_after(&observed_at)
+            .ok_or(WriteError::InvalidTime)
+    };
+    let end_of_validity = observed_at + chrono::Duration::minutes(120);
+
+    let id = writer.id("trend");
+    let mut attributes = vec![
+        ("gml:id", id.as_str()),
+        ("changeIndicator", change_indicator),
+        ("cloudAndVisibilityOK", "false"),
+    ];
+    match trend.time {
+        Some(TrendTime::At(_)) => attributes.push(("timeIndicator", "AT")),
+        Some(TrendTime::From(_)) => attributes.push(("timeIndicator", "FROM")),
+        Some(TrendTime::Until(_)) => attributes.push(("timeIndicator", "UNTIL")),
+        Some(TrendTime::Range { .. }) | None => {}
+    }
+
+    writer.open("iwxxm:trendForecast", &[]);
+    writer.open("iwxxm:MeteorologicalAerodromeTrendForecast", &attributes);
+
+    writer.open("iwxxm:phenomenonTime", &[]);
+    match trend.time {
+        Some(TrendTime::At(time)) => {
+            writer.time_instant(next(time)?);
+        }
+        Some(TrendTime::From(from)) => writer.time_period(next(from)?, end_of_validity),
+        Some(TrendTime::Until(until)) => writer.time_period(observed_at, next(until)?),
+        Some(TrendTime::Range { from, until }) => writer.time_period(next(from)?, next(until)?),
+        None => writer.time_period(observed_at, end_of_validity),
+    }
+    writer.close("iwxxm:phenomenonTime");
+
+    if let Some(wind) = trend.wind.filter(|wind| wind.speed.is_some()) {
+        surface_wind(writer, &wind, true);
+    }
+    if let Some(prevailing) = trend
+        .visibility
+        .and_then(|visibility| visibility.prevailing)
+    {
+        let (distance, out_of_range) = visibility_distance(&prevailing);
+        writer.measure("iwxxm:prevailingVisibility", "m", distance);
+        if let Some(out_of_range) = out_of_range {
+            writer.text(
+                "iwxxm:prevailingVisibilityOperator",
+                &[],
+                operator(out_of_range),
+            );
+        }
+    }
+    for weather in trend.weather.iter().take(3) {
+        writer.href(
+            "iwxxm:forecastWeather",
+            codes::PRESENT_WEATHER,
+            &String::from(weather.clone()),
+        );
+    }
+    cloud(writer, &trend.cloud_cover, "iwxxm:AerodromeCloudForecast");
+
+    writer.close("iwxxm:MeteorologicalAerodromeTrendForecast");
+    writer.close("iwxxm:trendForecast");
+    Ok(())
+}
+
+fn boolean(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// Rounded to at most two decimal places
+fn number(value: f64) -> String {
+    let rounded = (value * 100.).round() / 100.;
+    if rounded.abs() < f64::EPSILON {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Indents each element on its own line
+#[derive(Default)]
+struct Writer {
+    xml: String,
+    depth: usize,
+    ids: usize,
+}
+
+impl Writer {
+    /// Unique within the document
+    fn id(&mut self, prefix: &str) -> String {
+        self.ids += 1;
+        format!("{}-{}", prefix, self.ids)
+    }
+
+    fn start_tag(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        for _ in 0..self.depth {
+            self.xml.push_str("    ");
+        }
+        self.xml.push('<');
+        self.xml.push_str(name);
+        for (attribute, value) in attributes {
+            self.xml.push(' ');
+            self.xml.push_str(attribute);
+            self.xml.push_str("=\"");
+            self.xml.push_str(&escape(value));
+            self.xml.push('"');
+        }
+    }
+
+    fn open(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(name, attributes);
+        self.xml.push_str(">\n");
+        self.depth += 1;
+    }
+
+    fn close(&mut self, name: &str) {
+        self.depth -= 1;
+        for _ in 0..self.depth {
+            self.xml.push_str("    ");
+        }
+        self.xml.push_str("</");
+        self.xml.push_str(name);
+        self.xml.push_str(">\n");
+    }
+
+    fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.start_tag(name, attributes);
+        self.xml.push_str("/>\n");
+    }
+
+    fn text(&mut self, name: &str, attributes: &[(&str, &str)], text: &str) {
+        self.start_tag(name, attributes);
+        self.xml.push('>');
+        self.xml.push_str(&escape(text));
+        self.xml.push_str("</");
+        self.xml.push_str(name);
+        self.xml.push_str(">\n");
+    }
+
+    fn measure(&mut self, name: &str, uom: &str, value: f64) {
+        self.text(name, &[("uom", uom)], &number(value));
+    }
+
+    fn href(&mut self, name: &str, register: &str, code: &str) {
+        self.empty(name, &[("xlink:href", &format!("{}{}", register, code))]);
+    }
+
+    /// An absent property
+    fn nil(&mut self, name: &str, reason: &str) {
+        self.empty(
+            name,
+            &[("nilReason", &format!("{}{}", codes::NIL_REASON, reason))],
+        );
+    }
+
+    /// An absent measurement
+    fn nil_measure(&mut self, name: &str, reason: &str) {
+        self.empty(
+            name,
+            &[
+                ("uom", "N/A"),
+                ("xsi:nil", "true"),
+                ("nilReason", &format!("{}{}", codes::NIL_REASON, reason)),
+            ],
+        );
+    }
+
+    /// Returns the id so the instant can be referenced
+    fn time_instant(&mut self, instant: Instant) -> String {
+        let id = self.id("ti");
+        self.open("gml:TimeInstant", &[("gml:id", &id)]);
+        self.text("gml:timePosition", &[], &time_position(&instant));
+        self.close("gml:TimeInstant");
+        id
+    }
+
+    fn time_period(&mut self, begin: Instant, end: Instant) {
+        let id = self.id("tp");
+        self.open("gml:TimePeriod", &[("gml:id", &id)]);
+        self.text("gml:beginPosition", &[], &time_position(&begin));
+        self.text("gml:endPosition", &[], &time_position(&end));
+        self.close("gml:TimePeriod");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        path::Path,
+        process::{Command, Stdio},
+    };
+
+    use super::*;
+    use crate::parse::metar;
+
+    fn descendants<'a, 'input>(
+        document: &'a roxmltree::Document<'input>,
+        name: &'a str,
+    ) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> + 'a {
+        document
+            .descendants()
+            .filter(move |node| node.has_tag_name((IWXXM_NAMESPACE, name)))
+    }
+
+    fn href<'a>(node: roxmltree::Node<'a, '_>) -> &'a str {
+        node.attribute((XLINK_NAMESPACE, "href")).unwrap()
+    }
+
+    fn nil_reason<'a>(node: roxmltree::Node<'a, '_>) -> &'a str {
+        node.attribute("nilReason").unwrap()
+    }
+
+    #[test]
+    fn write_speci() {
+        let report = metar("SPECI EGLL 291020Z 24015G28KT 200V280 4000 1200NE R27L/1100U +TSRA BKN008CB 18/16 Q1004 RERA WS R27L R27L/290195 BECMG FM1100 TL1200 27010KT 9999 SCT020 TEMPO 3000 SHRA").unwrap();
+        let xml = write_metar(&report, 2021, 8).unwrap();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+
+        let root = document.root_element();
+        assert!(root.has_tag_name((IWXXM_NAMESPACE, "SPECI")));
+        assert_eq!(root.attribute("reportStatus"), Some("NORMAL"));
+        assert_eq!(root.attribute("automatedStation"), Some("false"));
+        assert_eq!(
+            descendants(&document, "observationTime").next().map(href),
+            Some("#ti-1")
+        );
+
+        let wind = descendants(&document, "AerodromeSurfaceWind")
+            .next()
+            .unwrap();
+        assert_eq!(wind.attribute("variableWindDirection"), Some("false"));
+        let gust = descendants(&document, "windGustSpeed").next().unwrap();
+        assert_eq!(gust.attribute("uom"), Some("[kn_i]"));
+        assert_eq!(gust.text(), Some("28"));
+        assert_eq!(
+            descendants(&document, "extremeClockwiseWindDirection")
+                .next()
+                .unwrap()
+                .text(),
+            Some("280")
+        );
+        assert_eq!(
+            descendants(&document, "minimumVisibilityDirection")
+                .next()
+                .unwrap()
+                .text(),
+            Some("45")
+        );
+
+        let rvr = descendants(&document, "AerodromeRunwayVisualRange")
+            .next()
+            .unwrap();
+        assert_eq!(rvr.attribute("pastTendency"), Some("UPWARD"));
+        assert_eq!(
+            descendants(&document, "meanRVR").next().unwrap().text(),
+            Some("1100")
+        );
+
+        assert_eq!(
+            descendants(&document, "presentWeather").next().map(href),
+            Some("http://codes.wmo.int/306/4678/+TSRA")
+        );
+        assert_eq!(
+            descendants(&document, "recentWeather").next().map(href),
+            Some("http://codes.wmo.int/49-2/AerodromeRecentWeather/RA")
+        );
+        assert_eq!(
+            descendants(&document, "base").next().unwrap().text(),
+            Some("800")
+        );
+        assert_eq!(
+            descendants(&document, "cloudType").next().map(href),
+            Some("http://codes.wmo.int/49-2/SigConvectiveCloudType/CB")
+        );
+        assert_eq!(descendants(&document, "windShear").count(), 1);
+
+        assert_eq!(
+            descendants(&document, "depositType").next().map(href),
+            Some("http://codes.wmo.int/bufr4/codeflag/0-20-086/2")
+        );
+        assert_eq!(
+            descendants(&document, "estimatedSurfaceFrictionOrBrakingAction")
+                .next()
+                .map(href),
+            Some("http://codes.wmo.int/bufr4/codeflag/0-20-089/95")
+        );
+
+        let trends =
+            descendants(&document, "MeteorologicalAerodromeTrendForecast").collect::<Vec<_>>();
+        assert_eq!(trends.len(), 2);
+        assert_eq!(trends[0].attribute("changeIndicator"), Some("BECOMING"));
+        let positions = |trend: roxmltree::Node| {
+            trend
+                .descendants()
+                .filter(|node| node.tag_name().namespace() == Some(GML_NAMESPACE))
+                .filter_map(|node| node.text().filter(|text| !text.trim().is_empty()))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            positions(trends[0]),
+            vec!["2021-08-29T11:00:00Z", "2021-08-29T12:00:00Z"]
+        );
+        assert_eq!(
+            trends[1].attribute("changeIndicator"),
+            Some("TEMPORARY_FLUCTUATIONS")
+        );
+        assert_eq!(
+            positions(trends[1]),
+            vec!["2021-08-29T10:20:00Z", "2021-08-29T12:20:00Z"]
+        );
+    }
+
+    #[test]
+    fn write_nil_reasons() {
+        let report = metar("METAR KSEA 290353Z AUTO 00000KT 9999 NCD 19/ A3004 NOSIG").unwrap();
+        let xml = write_metar(&report, 2021, 8).unwrap();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+
+        assert_eq!(
+            document.root_element().attribute("automatedStation"),
+            Some("true")
+        );
+        let dewpoint = descendants(&document, "dewpointTemperature")
+            .next()
+            .unwrap();
+        assert_eq!(dewpoint.attribute((XSI_NAMESPACE, "nil")), Some("true"));
+        assert_eq!(
+            nil_reason(dewpoint),
+            "http://codes.wmo.int/common/nil/missing"
+        );
+        let prevailing = descendants(&document, "prevailingVisibility")
+            .next()
+            .unwrap();
+        assert_eq!(prevailing.text(), Some("10000"));
+        assert_eq!(
+            descendants(&document, "prevailingVisibilityOperator")
+                .next()
+                .unwrap()
+                .text(),
+            Some("ABOVE")
+        );
+        assert_eq!(
+            nil_reason(descendants(&document, "cloud").next().unwrap()),
+            "http://codes.wmo.int/common/nil/notDetectedByAutoSystem"
+        );
+        assert_eq!(
+            nil_reason(descendants(&document, "trendForecast").next().unwrap()),
+            "http://codes.wmo.int/common/nil/noSignificantChange"
+        );
+
+        let report = metar("METAR KSEA 290353Z NIL").unwrap();
+        let xml = write_metar(&report, 2021, 8).unwrap();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        assert_eq!(
+            nil_reason(descendants(&document, "observation").next().unwrap()),
+            "http://codes.wmo.int/common/nil/missing"
+        );
+    }
+
+    #[test]
+    fn resolve_times() {
+        let report =
+            metar("METAR KSEA 312350Z 00000KT CAVOK 19/09 Q1013 BECMG AT0010 VRB02KT").unwrap();
+        let xml = write_metar(&report, 2021, 12).unwrap();
+        assert!(xml.contains("<gml:timePosition>2021-12-31T23:50:00Z</gml:timePosition>"));
+        assert!(xml.contains("<gml:timePosition>2022-01-01T00:10:00Z</gml:timePosition>"));
+        assert!(xml.contains("cloudAndVisibilityOK=\"true\""));
+
+        assert_eq!(write_metar(&report, 2021, 2), Err(WriteError::InvalidTime));
+        let report = metar("KSEA 00000KT").unwrap();
+        assert_eq!(
+            write_metar(&report, 2021, 12),
+            Err(WriteError::MissingObservationTime)
+        );
+    }
+
+    /// Validates with xmllint against the IWXXM 3.0 schemas and their GML, AIXM and ISO imports in `tests/iwxxm`,
+    /// which `tests/iwxxm/catalog.xml` resolves without network access.
+    ///
+    /// Fails if the schemas or xmllint are missing. `tests/iwxxm/fetch-schemas.sh` mirrors the schemas when
+    /// updating them.
+    #[test]
+    fn validate_against_iwxxm_schema() {
+        let schemas = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/iwxxm");
+        let schema = schemas.join("schemas.wmo.int/iwxxm/3.0/iwxxm.xsd");
+        assert!(
+            schema.exists(),
+            "{} is missing, run tests/iwxxm/fetch-schemas.sh and commit the downloaded schemas",
+            schema.display()
+        );
+        for report in [
+            "SPECI EGLL 291020Z 24015G28KT 200V280 4000 1200NE R27L/1100U +TSRA BKN008CB 18/16 Q1004 RERA WS R27L R27L/290195 BECMG FM1100 TL1200 27010KT 9999 SCT020 TEMPO 3000 SHRA",
+            "METAR EGLL 291020Z 24005KT 0800 R27L/0600V1000D R09R/M0050N FG VV002 08/08 Q1021 WS ALL RWY R88/CLRD70 TEMPO TL1100 2000 BR BECMG AT1130 NSW",
+            "METAR KSEA 290353Z AUTO 00000KT 9999 NCD 19/ A3004 NOSIG",
+            "METAR ENQA 290350Z 33015KT 9999 FEW030 12/08 Q1011 R88/CLRD70 W12/S4",
+            "METAR KSEA 290353Z NIL",
+        ] {
+            let xml = write_metar(&metar(report).unwrap(), 2021, 8).unwrap();
+            let mut xmllint = Command::new("xmllint")
+                .env("XML_CATALOG_FILES", schemas.join("catalog.xml"))
+                .args(["--nonet", "--noout", "--schema"])
+                .arg(&schema)
+                .arg("-")
+                .stdin(Stdio::piped())
+                .spawn()
+                .expect("xmllint is not installed");
+            xmllint
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(xml.as_bytes())
+                .unwrap();
+            assert!(xmllint.wait().unwrap().success(), "{}\n{}", report, xml);
+        }
+    }
+}
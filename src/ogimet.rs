@@ -0,0 +1,245 @@
+//! Raw reports from saved [Ogimet](https://www.ogimet.com) pages
+//!
+//! Both the HTML listings of the latest reports, where each report is prefixed by a `YYYYMMDDHHMM` timestamp,
+//! and the comma separated `getmetar` output are supported.
+//!
+//! ```
+//! use weather_reports::{ogimet::reports, parse::metar};
+//!
+//! let page = "<pre>
+//! # Latest METAR/SPECI from Sweden
+//! 202108300120 METAR ESGR 300120Z AUTO 00000KT 0150 R01/0650N R19/0700N
+//!                         FG NCD 07/07 Q1022=
+//! </pre>";
+//! let report = reports(page).next().unwrap();
+//! assert_eq!(report.station, "ESGR");
+//! assert_eq!(report.time.time.minute, 20);
+//! assert!(metar(report.text).is_ok());
+//! ```
+use crate::tokens::MilitaryTime;
+
+/// A report as listed by Ogimet
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OgimetReport<'a> {
+    /// First word of the report after its name, usually the ICAO identifier
+    pub station: &'a str,
+    /// Time of the report according to the listing
+    pub time: ListingTime,
+    /// Report without its terminating `=`, which may span several lines
+    pub text: &'a str,
+}
+
+/// A UTC date and time as listed by Ogimet
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ListingTime {
+    pub year: u16,
+    pub month: u8,
+    pub day_of_month: u8,
+    pub time: MilitaryTime,
+}
+
+#[cfg(feature = "chrono_helpers")]
+impl ListingTime {
+    /// Absent if this is not a valid date and time
+    pub fn as_datetime(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        self.time.as_datetime(chrono::NaiveDate::from_ymd_opt(
+            self.year as i32,
+            self.month as u32,
+            self.day_of_month as u32,
+        )?)
+    }
+}
+
+/// Reports in a saved page, in the order they are listed
+///
+/// Only the last `<pre>` block of an HTML page is read, if there is one.
+pub fn reports(page: &str) -> Reports<'_> {
+    let listing = match page.rfind("<pre>") {
+        Some(start) => {
+            let listing = &page[start + "<pre>".len()..];
+            &listing[..listing.find("</pre>").unwrap_or(listing.len())]
+        }
+        None => page,
+    };
+    Reports { rest: listing }
+}
+
+pub struct Reports<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Reports<'a> {
+    type Item = OgimetReport<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.rest.is_empty() {
+            let line = &self.rest[..self.rest.find('\n').map_or(self.rest.len(), |end| end + 1)];
+            if let Some((station, time, text)) = comma_separated(line) {
+                self.rest = &self.rest[line.len()..];
+                return Some(OgimetReport {
+                    station,
+                    time,
+                    text: text.trim().trim_end_matches('='),
+                });
+            }
+
+            let time = match line.get(..12).and_then(listing_time) {
+                Some(time) if line[12..].starts_with(' ') => time,
+                _ => {
+                    self.rest = &self.rest[line.len()..];
+                    continue;
+                }
+            };
+            let body = &self.rest[12..];
+            let end = report_end(body);
+            self.rest = &body[end..];
+            self.rest = self.rest.strip_prefix('=').unwrap_or(self.rest);
+            let text = body[..end].trim();
+            return Some(OgimetReport {
+                station: station(text),
+                time,
+                text,
+            });
+        }
+        None
+    }
+}
+
+/// A report ends at `=` or before the next line that does not start with whitespace
+fn report_end(body: &str) -> usize {
+    let mut offset = 0;
+    for (index, line) in body.split_inclusive('\n').enumerate() {
+        let is_continuation = index == 0 || line.starts_with([' ', '\t']);
+        if !is_continuation {
+            return offset;
+        }
+        if let Some(terminator) = line.find('=') {
+            return offset + terminator;
+        }
+        offset += line.len();
+    }
+    offset
+}
+
+/// `ICAO,YYYY,MM,DD,HH,mm,report`
+fn comma_separated(line: &str) -> Option<(&str, ListingTime, &str)> {
+    let mut fields = line.splitn(7, ',');
+    let station = fields.next()?;
+    let digits = (0..5)
+        .map(|_| fields.next())
+        .collect::<Option<Vec<_>>>()?
+        .concat();
+    let text = fields.next()?;
+    if station.is_empty() || !station.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((station, listing_time(&digits)?, text))
+}
+
+/// `YYYYMMDDHHMM`
+fn listing_time(digits: &str) -> Option<ListingTime> {
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits[range].parse::<u8>().unwrap();
+    Some(ListingTime {
+        year: digits[..4].parse().unwrap(),
+        month: field(4..6),
+        day_of_month: field(6..8),
+        time: MilitaryTime {
+            hour: field(8..10),
+            minute: field(10..12),
+        },
+    })
+}
+
+fn station(text: &str) -> &str {
+    text.split_whitespace()
+        .find(|word| !matches!(*word, "METAR" | "SPECI" | "COR" | "AMD"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<html><pre>
+##########################################################
+# Query made at 08/30/2021 01:29:25 UTC
+##########################################################
+</pre><pre>
+###################################
+# Latest METAR/SPECI from Canada before 08/30/2021 01:28:16 UTC
+###################################
+202108300100 TUX SA 0100 AUTO8 M M M 141/21/20/1905/M/0043 PCPN 4.3MM
+                        PAST HR 3003 28MM=
+202108300100 METAR COR CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990 RMK CI5
+                        SLP122=
+202108292300 SPECI CYYZ 292300Z 27008KT 15SM FEW040 24/12 A2995
+202108300000 METAR CWCI 300000Z AUTO 26019KT 19/17 RMK AO1=
+</pre></html>";
+
+    #[test]
+    fn read_listing() {
+        let reports = reports(PAGE).collect::<Vec<_>>();
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| (report.station, report.text))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "TUX",
+                    "TUX SA 0100 AUTO8 M M M 141/21/20/1905/M/0043 PCPN 4.3MM\n                        PAST HR 3003 28MM"
+                ),
+                (
+                    "CWAE",
+                    "METAR COR CWAE 300100Z 23002KT 15SM BKN240 21/08 A2990 RMK CI5\n                        SLP122"
+                ),
+                (
+                    "CYYZ",
+                    "SPECI CYYZ 292300Z 27008KT 15SM FEW040 24/12 A2995"
+                ),
+                ("CWCI", "METAR CWCI 300000Z AUTO 26019KT 19/17 RMK AO1"),
+            ]
+        );
+        assert_eq!(
+            reports[2].time,
+            ListingTime {
+                year: 2021,
+                month: 8,
+                day_of_month: 29,
+                time: MilitaryTime {
+                    hour: 23,
+                    minute: 0
+                },
+            }
+        );
+        assert_eq!(super::reports("No METAR/SPECI reports").count(), 0);
+    }
+
+    #[test]
+    fn read_comma_separated() {
+        let page = "LEMD,2021,08,29,03,30,METAR LEMD 290330Z 00000KT CAVOK 19/11 Q1019 NOSIG=
+LEMD,2021,08,29,04,00,METAR LEMD 290400Z 02003KT CAVOK 18/11 Q1019 NOSIG=
+";
+        let reports = reports(page).collect::<Vec<_>>();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].station, "LEMD");
+        assert_eq!(
+            reports[1].text,
+            "METAR LEMD 290400Z 02003KT CAVOK 18/11 Q1019 NOSIG"
+        );
+        assert_eq!(reports[1].time.time, MilitaryTime { hour: 4, minute: 0 });
+    }
+
+    #[cfg(feature = "chrono_helpers")]
+    #[test]
+    fn listing_datetime() {
+        let report = reports(PAGE).next().unwrap();
+        assert_eq!(
+            report.time.as_datetime().unwrap().to_rfc3339(),
+            "2021-08-30T01:00:00+00:00"
+        );
+    }
+}
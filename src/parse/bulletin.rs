@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+
+use crate::tokens::{bulletin::*, *};
+
+peg::parser! {
+    pub grammar bulletin_envelope() for str {
+        /// Parser for the envelope of a WMO bulletin, use [Bulletin::reports] to get the reports in it
+        ///
+        /// The start and end of message characters and the transmission sequence number are optional.
+        pub rule bulletin() -> Bulletin<'input> =
+                    "\u{1}"? whitespace()
+                    (digit()*<3, 5> line_end())?
+                    heading:heading() [' ' | '\t']* line_end()
+                    body_offset:position!() body:$((!("\u{3}" whitespace() ![_]) [_])*)
+                    ("\u{3}" whitespace())?
+                    {
+                Bulletin {
+                    heading,
+                    body,
+                    body_offset,
+                }
+            }
+
+        rule whitespace() = quiet!{[' ' | '\t' | '\r' | '\n']*};
+        rule line_end() = quiet!{[' ' | '\t']* ['\r']* "\n" / ![_]} / expected!("end of line");
+        rule digit() -> &'input str = quiet!{$(['0'..='9'])} / expected!("digit");
+        rule letter() -> &'input str = quiet!{$(['A'..='Z'])} / expected!("letter");
+
+        pub rule heading() -> AbbreviatedHeading<'input> =
+            data_type:$(letter()*<2>) area:$(letter()*<2>) number:$(digit()*<1, 2>) " "+
+            originating_centre:$(quiet!{letter()*<4>} / expected!("originating centre")) " "+
+            day_of_month:$(digit()*<2>) hour:$(digit()*<2>) minute:$(digit()*<2>)
+            indicator:(" "+ indicator:indicator() { indicator })? {
+                AbbreviatedHeading {
+                    data_type,
+                    area,
+                    number: number.parse().unwrap(),
+                    originating_centre,
+                    time: DateTime {
+                        day_of_month: day_of_month.parse().unwrap(),
+                        time: MilitaryTime {
+                            hour: hour.parse().unwrap(),
+                            minute: minute.parse().unwrap(),
+                        },
+                        is_zulu: true,
+                    },
+                    indicator,
+                }
+            }
+
+        rule indicator() -> BulletinIndicator<'input> =
+            kind:$(quiet!{"RR" / "CC" / "AA"} / expected!("bulletin indicator")) sequence:letter() {
+                BulletinIndicator {
+                    kind: IndicatorKind::try_from(kind).unwrap(),
+                    sequence,
+                }
+            }
+            / "P" sequence:$(letter()*<2>) {
+                BulletinIndicator {
+                    kind: IndicatorKind::Segment,
+                    sequence,
+                }
+            }
+    }
+}
+
+impl<'input> Bulletin<'input> {
+    /// Parses each report in the bulletin as a METAR
+    ///
+    /// Error locations are relative to [BulletinReport::text], add the start of its span to get the offset in the bulletin.
+    pub fn metars(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            BulletinReport<'input>,
+            Result<MetarReport<'input>, peg::error::ParseError<peg::str::LineCol>>,
+        ),
+    > + 'input {
+        self.reports().map(|report| {
+            let metar = super::metar(report.text);
+            (report, metar)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bulletin_envelope::*;
+    use crate::tokens::bulletin::*;
+
+    #[test]
+    fn parse_bulletin() {
+        let input = "\u{1}\r\r\n123 \r\r\nSAUS70 KWBC 121200 RRA\r\r\nMETAR KJFK 121151Z 31008KT 10SM FEW250 M01/M12 A3036=\r\r\nKLGA 121151Z 32010KT 10SM SKC M01/M13 A3035=\r\r\nKEWR 121151Z =\r\r\n\u{3}";
+        let bulletin = bulletin(input).unwrap();
+        assert_eq!(bulletin.heading.data_type, "SA");
+        assert_eq!(bulletin.heading.area, "US");
+        assert_eq!(bulletin.heading.number, 70);
+        assert_eq!(bulletin.heading.originating_centre, "KWBC");
+        assert_eq!(bulletin.heading.time.time.hour, 12);
+        assert_eq!(
+            bulletin.heading.indicator,
+            Some(BulletinIndicator {
+                kind: IndicatorKind::Delayed,
+                sequence: "A"
+            })
+        );
+
+        let reports = bulletin.reports().collect::<Vec<_>>();
+        assert_eq!(reports.len(), 3);
+        for report in &reports {
+            assert_eq!(&input[report.span.clone()], report.text);
+        }
+        assert!(reports[1].text.starts_with("KLGA"));
+
+        let metars = bulletin.metars().collect::<Vec<_>>();
+        assert_eq!(metars[0].1.as_ref().unwrap().identifier, "KJFK");
+        assert_eq!(metars[1].1.as_ref().unwrap().identifier, "KLGA");
+    }
+
+    #[test]
+    fn parse_segmented_heading() {
+        let heading = heading("SMRA10 RUMS 120000 PAB").unwrap();
+        assert_eq!(
+            heading.indicator,
+            Some(BulletinIndicator {
+                kind: IndicatorKind::Segment,
+                sequence: "AB"
+            })
+        );
+        assert_eq!(heading.number, 10);
+    }
+}
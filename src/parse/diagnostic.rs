@@ -0,0 +1,178 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use peg::{error::ParseError, str::LineCol};
+
+use super::group_at;
+
+/// Expected tokens listed in the label before the rest are left to a note
+const MAX_LABELED_EXPECTED: usize = 8;
+
+const KEYWORDS: &[&str] = &[
+    "METAR", "SPECI", "AUTO", "NOSIG", "BECMG", "TEMPO", "CAVOK", "NSC", "NCD", "SKC", "CLR",
+    "NSW", "RMK",
+];
+const UNITS: &[&str] = &["KT", "MPS", "KMH", "SM", "KM", "FT"];
+
+/// Convenience function for converting a parsing error
+/// into a [codespan_reporting::diagnostic::Diagnostic] for displaying to a user.
+#[deprecated(
+    note = "use into_rich_diagnostic, which labels the offending group and hints at near misses"
+)]
+pub fn into_diagnostic(err: &ParseError<LineCol>) -> Diagnostic<()> {
+    let (label_msg, notes) = expected(err);
+    Diagnostic::error()
+        .with_message("could not parse report")
+        .with_labels(vec![Label::primary(
+            (),
+            err.location.offset..err.location.offset,
+        )
+        .with_message(label_msg)])
+        .with_notes(notes)
+}
+
+/// Converts a parsing error into a [codespan_reporting::diagnostic::Diagnostic] with more context than [into_diagnostic]
+///
+/// `input` is the report that failed to parse. The primary label covers the group the error is in, a secondary label
+/// covers the groups before it, and near misses of keywords or units get a hint.
+pub fn into_rich_diagnostic(input: &str, err: &ParseError<LineCol>) -> Diagnostic<()> {
+    let (label_msg, mut notes) = expected(err);
+
+    let offset = err.location.offset.min(input.len());
+    let group = group_at(input, offset);
+    let mut labels = vec![if group.is_empty() {
+        Label::primary((), offset..offset)
+    } else {
+        Label::primary((), group.clone())
+    }
+    .with_message(label_msg)];
+
+    let parsed = input[..group.start].trim_end();
+    let parsed_start = parsed.len() - parsed.trim_start().len();
+    if parsed_start < parsed.len() {
+        labels.push(
+            Label::secondary((), parsed_start..parsed.len()).with_message("parsed up to here"),
+        );
+    }
+
+    notes.extend(hint(&input[group]).map(|hint| format!("help: did you mean `{}`?", hint)));
+
+    Diagnostic::error()
+        .with_message("could not parse report")
+        .with_labels(labels)
+        .with_notes(notes)
+}
+
+/// Label message listing the expected tokens, and a note with all of them if there are too many for the label
+fn expected(err: &ParseError<LineCol>) -> (String, Vec<String>) {
+    let mut tokens = err.expected.tokens().collect::<Vec<_>>();
+    tokens.sort_unstable();
+    let mut notes = vec![];
+    let label_msg = match tokens.as_slice() {
+        [] => "unclear cause".to_string(),
+        [token] => format!("expected {}", token),
+        tokens if tokens.len() <= MAX_LABELED_EXPECTED => {
+            format!("expected one of {}", list(tokens))
+        }
+        tokens => {
+            notes.push(format!("expected one of {}", list(tokens)));
+            format!(
+                "expected one of {}, or {} others",
+                tokens[..MAX_LABELED_EXPECTED - 1].join(", "),
+                tokens.len() - (MAX_LABELED_EXPECTED - 1)
+            )
+        }
+    };
+    (label_msg, notes)
+}
+
+fn list(tokens: &[&str]) -> String {
+    match tokens.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{}, or {}", rest.join(", "), last),
+        _ => tokens.concat(),
+    }
+}
+
+/// Closest keyword to the group, or the group with the closest unit if it ends with a misspelled one
+fn hint(group: &str) -> Option<String> {
+    let closest = |candidates: &[&'static str], text: &str, max_distance: usize| {
+        candidates
+            .iter()
+            .map(|candidate| (distance(text, candidate), *candidate))
+            .filter(|(distance, _)| (1..=max_distance).contains(distance))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    };
+
+    if group.chars().all(|c| c.is_ascii_alphabetic()) {
+        if KEYWORDS.contains(&group) {
+            return None;
+        }
+        let max_distance = if group.len() >= 5 { 2 } else { 1 };
+        return closest(KEYWORDS, group, max_distance).map(str::to_string);
+    }
+
+    let value = group.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &group[value.len()..];
+    if value.ends_with(|c: char| c.is_ascii_digit()) && !unit.is_empty() && !UNITS.contains(&unit) {
+        return closest(UNITS, unit, 1).map(|unit| format!("{}{}", value, unit));
+    }
+    None
+}
+
+/// Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::metar;
+
+    #[test]
+    fn label_offending_group() {
+        let input = "KSEA 290353Z 01008KTT 10SM SCT200 19/09 A3004";
+        let diagnostic = into_rich_diagnostic(input, &metar(input).unwrap_err());
+        assert_eq!(&input[diagnostic.labels[0].range.clone()], "01008KTT");
+        assert_eq!(&input[diagnostic.labels[1].range.clone()], "KSEA 290353Z");
+        assert!(diagnostic
+            .notes
+            .contains(&"help: did you mean `01008KT`?".to_string()));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn label_error_location() {
+        let input = "KSEA 290353Z 01008KTT 10SM SCT200 19/09 A3004";
+        let err = metar(input).unwrap_err();
+        let diagnostic = into_diagnostic(&err);
+        assert_eq!(diagnostic.labels.len(), 1);
+        assert_eq!(
+            diagnostic.labels[0].range,
+            err.location.offset..err.location.offset
+        );
+        assert_eq!(
+            diagnostic.labels[0].message,
+            into_rich_diagnostic(input, &err).labels[0].message
+        );
+    }
+
+    #[test]
+    fn hint_near_misses() {
+        assert_eq!(hint("NOSGI").as_deref(), Some("NOSIG"));
+        assert_eq!(hint("TEMOP").as_deref(), Some("TEMPO"));
+        assert_eq!(hint("10SMM").as_deref(), Some("10SM"));
+        assert_eq!(hint("NOSIG"), None);
+        assert_eq!(hint("GARBAGE"), None);
+        assert_eq!(hint("01008"), None);
+    }
+}
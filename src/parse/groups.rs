@@ -0,0 +1,157 @@
+use std::convert::TryFrom;
+
+use super::parser::weather_reports::*;
+use crate::tokens::{
+    groups::{Group, GroupKind},
+    ObservationFlag, ReportKind,
+};
+
+/// Splits a METAR into whitespace separated groups and classifies each one
+///
+/// Groups are classified on their own, so this never fails and unknown groups are kept.
+/// The `=` terminating a report is not part of any group.
+///
+/// ```
+/// use weather_reports::{parse::classify_groups, tokens::groups::GroupKind};
+///
+/// let groups = classify_groups("KSEA 290353Z 01008KT 10SM XYZZY SCT200 19/09 A3004 RMK AO2=");
+/// assert_eq!(
+///     groups.iter().map(|group| group.kind).collect::<Vec<_>>(),
+///     vec![
+///         GroupKind::Identifier,
+///         GroupKind::Time,
+///         GroupKind::Wind,
+///         GroupKind::Visibility,
+///         GroupKind::Unknown,
+///         GroupKind::Cloud,
+///         GroupKind::Temperature,
+///         GroupKind::Pressure,
+///         GroupKind::Remark,
+///     ]
+/// );
+/// assert_eq!(groups[8].text, "RMK AO2");
+/// ```
+pub fn classify_groups(report: &str) -> Vec<Group<'_>> {
+    let mut groups = vec![];
+    let mut seen_identifier = false;
+    for word in report.split_whitespace() {
+        let start = word.as_ptr() as usize - report.as_ptr() as usize;
+        let text = word.trim_end_matches('=');
+        if text.is_empty() {
+            continue;
+        }
+
+        // A report name or flag such as `AUTO` may also look like an identifier
+        let kind = if matches!(text, "RMK" | ":RMK" | "REMARK") {
+            GroupKind::Remark
+        } else if ReportKind::try_from(text).is_ok() {
+            GroupKind::ReportName
+        } else if ObservationFlag::try_from(text).is_ok() {
+            GroupKind::ObservationFlag
+        } else if !seen_identifier && icao_identifier(text).is_ok() {
+            seen_identifier = true;
+            GroupKind::Identifier
+        } else {
+            classify(text)
+        };
+        let text = match kind {
+            GroupKind::Remark => {
+                report[start..].trim_end_matches(|c: char| c.is_whitespace() || c == '=')
+            }
+            _ => text,
+        };
+        groups.push(Group {
+            kind,
+            text,
+            span: start..start + text.len(),
+        });
+        if kind == GroupKind::Remark {
+            break;
+        }
+    }
+    groups
+}
+
+fn classify(text: &str) -> GroupKind {
+    let is_weather = |text| weather(text).is_ok() || text == "NSW";
+
+    if trend(text).is_ok() {
+        GroupKind::Trend
+    } else if observation_time(text).is_ok() || trend_time(text).is_ok() {
+        GroupKind::Time
+    } else if wind(text).is_ok() || wind_variance(text).is_ok() {
+        GroupKind::Wind
+    } else if runway_report(text).is_ok() {
+        GroupKind::RunwayState
+    } else if runway_visibility(text).is_ok() {
+        GroupKind::RunwayVisibility
+    } else if is_weather(text) || text.strip_prefix("RE").is_some_and(is_weather) {
+        GroupKind::Weather
+    } else if cloud_cover(text).is_ok() {
+        GroupKind::Cloud
+    } else if temperatures(text).is_ok() {
+        GroupKind::Temperature
+    } else if text == "CAVOK" || visibility(text).is_ok() {
+        GroupKind::Visibility
+    } else if pressure(text).is_ok() {
+        GroupKind::Pressure
+    } else if color(text).is_ok() {
+        GroupKind::Color
+    } else {
+        GroupKind::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use GroupKind::*;
+
+    #[test]
+    fn classify_all_groups() {
+        let report = "METAR COR EGLL 291020Z 24015G25KT 210V270 CAVOK R27L/P1500 R24/290550 -RA RERA FEW005 15/14 Q1008 BLU TEMPO FM1100 9999 NOSIG=";
+        let groups = classify_groups(report);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.kind, group.text))
+                .collect::<Vec<_>>(),
+            vec![
+                (ReportName, "METAR"),
+                (ObservationFlag, "COR"),
+                (Identifier, "EGLL"),
+                (Time, "291020Z"),
+                (Wind, "24015G25KT"),
+                (Wind, "210V270"),
+                (Visibility, "CAVOK"),
+                (RunwayVisibility, "R27L/P1500"),
+                (RunwayState, "R24/290550"),
+                (Weather, "-RA"),
+                (Weather, "RERA"),
+                (Cloud, "FEW005"),
+                (Temperature, "15/14"),
+                (Pressure, "Q1008"),
+                (Color, "BLU"),
+                (Trend, "TEMPO"),
+                (Time, "FM1100"),
+                (Visibility, "9999"),
+                (Trend, "NOSIG"),
+            ]
+        );
+        for group in groups {
+            assert_eq!(&report[group.span], group.text);
+        }
+
+        // Only the first identifier-like group is the identifier
+        let groups = classify_groups("KSEA AUTO KBFI");
+        assert_eq!(groups[1].kind, ObservationFlag);
+        assert_eq!(groups[2].kind, Unknown);
+
+        // Report names and flags are checked before the identifier
+        let groups = classify_groups("METAR AUTO KSEA 290353Z");
+        assert_eq!(
+            groups.iter().map(|group| group.kind).collect::<Vec<_>>(),
+            vec![ReportName, ObservationFlag, Identifier, Time]
+        );
+    }
+}
@@ -1,45 +1,102 @@
-mod parser;
+mod bulletin;
+#[cfg(feature = "codespan_helpers")]
+mod diagnostic;
+mod groups;
+pub(crate) mod parser;
+mod pirep;
+mod sao;
+mod sigmet;
+mod synop;
 
+pub use bulletin::bulletin_envelope::bulletin;
+#[cfg(feature = "codespan_helpers")]
+#[allow(deprecated)]
+pub use diagnostic::into_diagnostic;
+#[cfg(feature = "codespan_helpers")]
+pub use diagnostic::into_rich_diagnostic;
+pub use groups::classify_groups;
 pub use parser::weather_reports::metar;
+pub use pirep::pirep_reports::pirep;
+pub use sao::sao_reports::sao;
+pub use sigmet::sigmet_reports::{airmet, sigmet};
+pub use synop::synop_reports::{buoy, synop};
 
-/// Convenience function for converting a parsing error
-/// into a [codespan_reporting::diagnostic::Diagnostic] for displaying to a user.
-#[cfg(feature = "codespan_helpers")]
-pub fn into_diagnostic(
-    err: &peg::error::ParseError<peg::str::LineCol>,
-) -> codespan_reporting::diagnostic::Diagnostic<()> {
-    let expected_count = err.expected.tokens().count();
-    let label_msg = if expected_count == 0 {
-        "unclear cause".to_string()
-    } else if expected_count == 1 {
-        format!("expected {}", err.expected.tokens().next().unwrap())
-    } else {
-        let tokens = {
-            let mut tokens = err.expected.tokens().collect::<Vec<_>>();
-            tokens.sort_unstable();
-            tokens
+use crate::tokens::{MetarReport, MetarSpans};
+
+/// Parses a METAR like [metar], along with where each group is in the input
+///
+/// ```
+/// use weather_reports::parse::metar_with_spans;
+///
+/// let input = "KSEA 290353Z 01008KT 10SM SCT200 19/09 A3004";
+/// let (report, spans) = metar_with_spans(input).unwrap();
+/// assert_eq!(&input[spans.wind.unwrap()], "01008KT");
+/// assert_eq!(&input[spans.cloud_cover[0].clone()], "SCT200");
+/// ```
+pub fn metar_with_spans(
+    input: &str,
+) -> Result<(MetarReport<'_>, MetarSpans), peg::error::ParseError<peg::str::LineCol>> {
+    let (report, mut spans) = parser::weather_reports::spanned_metar(input)?;
+    spans.trim_end(input);
+    Ok((report, spans))
+}
+
+/// Parses a METAR, skipping groups that cannot be parsed until the rest of it can be
+///
+/// Returns the report without the skipped groups if there is one, and the error for each skipped group.
+/// Error locations are in `input`, so each can be passed to [into_rich_diagnostic] with it.
+///
+/// ```
+/// use weather_reports::parse::metar_with_recovery;
+///
+/// let (report, errors) = metar_with_recovery("KSEA 290353Z 01008KTT 10SM SCT200 19/09 A3004");
+/// assert_eq!(errors.len(), 1);
+/// assert_eq!(report.unwrap().cloud_cover.len(), 1);
+/// ```
+pub fn metar_with_recovery(
+    input: &str,
+) -> (
+    Option<MetarReport<'static>>,
+    Vec<peg::error::ParseError<peg::str::LineCol>>,
+) {
+    let mut input = input.to_string();
+    let mut errors = vec![];
+    loop {
+        let err = match metar(&input) {
+            Ok(report) => return (Some(report.into_owned()), errors),
+            Err(err) => err,
         };
-        let mut acc = "expected one of ".to_string();
-        for token in tokens.iter().take(expected_count - 1) {
-            acc += token;
-            acc += ", ";
-        }
-        acc += "or ";
-        acc += tokens.last().unwrap();
-        acc
-    };
-    codespan_reporting::diagnostic::Diagnostic::error()
-        .with_message("could not parse report")
-        .with_labels(vec![codespan_reporting::diagnostic::Label::primary(
-            (),
-            err.location.offset..err.location.offset,
-        )
-        .with_message(label_msg)])
+        let group = group_at(&input, err.location.offset.min(input.len()));
+        errors.push(err);
+        if group.is_empty() {
+            return (None, errors);
+        }
+        // Blanked out rather than removed so later errors are at the same location in the original input
+        let blank = " ".repeat(group.len());
+        input.replace_range(group, &blank);
+    }
+}
+
+/// Byte range of the whitespace separated group at `offset`, or the next one if `offset` is at whitespace
+///
+/// A terminating `=` is not part of the group.
+fn group_at(input: &str, offset: usize) -> std::ops::Range<usize> {
+    let rest = &input[offset..];
+    let offset = offset + rest.len() - rest.trim_start().len();
+    let start = input[..offset]
+        .trim_end_matches(|c: char| !c.is_whitespace())
+        .len();
+    let group = &input[start..];
+    let group = &group[..group.find(char::is_whitespace).unwrap_or(group.len())];
+    start..(start + group.trim_end_matches('=').len()).max(offset)
 }
 
 #[cfg(test)]
 mod tests {
     use super::parser::weather_reports::*;
+    use crate::tokens::*;
+    use std::convert::TryFrom;
+    use uom::si::pressure::{hectopascal, inch_of_mercury, millimeter_of_mercury};
 
     #[test]
     fn parse_icao_identifier() {
@@ -76,6 +133,133 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_runway_report() {
+        for (val, runway) in [
+            ("R24/290550", ReportedRunway::Runway("24".into())),
+            ("R88/CLRD70", ReportedRunway::AllRunways),
+            ("88290395", ReportedRunway::AllRunways),
+            ("99CLRD//", ReportedRunway::RepeatedReport),
+            ("74290395", ReportedRunway::Runway("24R".into())),
+            ("R74/290395", ReportedRunway::Runway("24R".into())),
+            ("R06L/290050", ReportedRunway::Runway("06L".into())),
+        ] {
+            assert_eq!(runway_report(val).expect(val).unwrap().runway(), runway);
+        }
+    }
+
+    #[test]
+    fn parse_cis_remarks() {
+        let report = metar("UUEE 181030Z 24004MPS 9999 SCT033 06/M01 Q1020 88290395 NOSIG RMK MT OBSC QFE748/0997=").unwrap();
+        assert_eq!(
+            report.runway_reports[0].runway(),
+            ReportedRunway::AllRunways
+        );
+        assert_eq!(report.trends, vec![Trend::NoSignificantChange]);
+        assert_eq!(report.obscured, vec![Obscured::Mountains]);
+        let field_pressure = report.field_pressure.unwrap();
+        assert_eq!(
+            field_pressure.unit,
+            Some(FieldPressureUnit::MillimetersOfMercury)
+        );
+        assert!((field_pressure.pressure.get::<millimeter_of_mercury>() - 748.).abs() < 1e-9);
+        assert_eq!(
+            field_pressure.reported_hectopascals,
+            Some(ReportedValue::Decimal {
+                digits: 997,
+                width: 4,
+                scale: 0
+            })
+        );
+
+        let report =
+            metar("UAAA 181000Z 00000MPS CAVOK 08/M04 Q1028 NOSIG RMK OBST OBSC QFE710").unwrap();
+        assert_eq!(report.obscured, vec![Obscured::Obstacles]);
+        let field_pressure = report.field_pressure.unwrap();
+        assert_eq!(field_pressure.unit, None);
+        assert!((field_pressure.pressure.get::<millimeter_of_mercury>() - 710.).abs() < 1e-9);
+
+        let report = metar("EGLL 181020Z 24010KT 9999 FEW030 15/09 Q1008 QFE1003").unwrap();
+        let field_pressure = report.field_pressure.unwrap();
+        assert_eq!(field_pressure.unit, None);
+        assert!((field_pressure.pressure.get::<hectopascal>() - 1003.).abs() < 1e-9);
+
+        let report = metar("MGCB 300000Z 00000KT 9999 SCT018 OVC060 19/18 QFE869.8=").unwrap();
+        let field_pressure = report.field_pressure.unwrap();
+        assert_eq!(field_pressure.unit, Some(FieldPressureUnit::Hectopascals));
+        assert!((field_pressure.pressure.get::<hectopascal>() - 869.8).abs() < 1e-9);
+        assert_eq!(report.reported_pressure, None);
+
+        let report =
+            metar("UUWW 181030Z 24004MPS 9999 SCT033 06/M01 Q1020 QFE748/0997 NOSIG").unwrap();
+        assert!(report.field_pressure.is_some() && report.remark.is_none());
+    }
+
+    #[test]
+    fn guess_unitless_qfe_unit() {
+        for (qfe, millimeters_of_mercury) in [
+            ("QFE820", Some(820.)),
+            ("QFE819", Some(819.)),
+            ("QFE821", None),
+            ("QFE870", None),
+            ("QFE999", None),
+        ] {
+            let field_pressure = field_pressure(qfe).unwrap();
+            assert_eq!(field_pressure.unit, None, "{}", qfe);
+            let value = f64::from(field_pressure.value);
+            match millimeters_of_mercury {
+                Some(expected) => assert!(
+                    (field_pressure.pressure.get::<millimeter_of_mercury>() - expected).abs()
+                        < 1e-9,
+                    "{}",
+                    qfe
+                ),
+                None => assert!(
+                    (field_pressure.pressure.get::<hectopascal>() - value).abs() < 1e-9,
+                    "{}",
+                    qfe
+                ),
+            }
+        }
+
+        // A high aerodrome's QFE in hectopascals is within the range of mmHg, so it can only be read correctly by a dialect
+        let input = "SLLP 181000Z 27010KT 9999 FEW030 12/M02 Q1024 RMK QFE650";
+        let report = metar(input).unwrap();
+        assert!(
+            (report
+                .field_pressure
+                .unwrap()
+                .pressure
+                .get::<millimeter_of_mercury>()
+                - 650.)
+                .abs()
+                < 1e-9
+        );
+        let report =
+            crate::dialect::parse(input, Some(crate::dialect::Dialect::WmoStrict)).unwrap();
+        assert!((report.field_pressure.unwrap().pressure.get::<hectopascal>() - 650.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn other_eight_digit_groups_are_not_runway_states() {
+        // Runway designators above 36, other than right parallel runways, 88 and 99, are not used
+        for val in ["40290395", "00290395", "37290395", "87290395", "95290395"] {
+            assert!(runway_report(val).is_err(), "{}", val);
+        }
+        // Coverage can only be 1, 2, 5 or 9
+        assert!(runway_report("12345678").is_err());
+        for input in [
+            "UUEE 181030Z 24004MPS 9999 SCT033 06/M01 Q1020 40290395 NOSIG",
+            "UUEE 181030Z 24004MPS 9999 SCT033 06/M01 Q1020 12345678 NOSIG",
+        ] {
+            assert!(
+                metar(input).map_or(true, |report| report.runway_reports.is_empty()),
+                "{}",
+                input
+            );
+        }
+    }
+
     #[test]
     fn parse_weather() {
         for val in ["-RA", "BR", "MIFG"] {
@@ -118,6 +302,186 @@ mod tests {
         }
     }
 
+    #[test]
+    fn preserve_reported_values() {
+        let report = metar("KSEA 290353Z 01008KT 1 1/2SM SCT200 19/09 A3004").unwrap();
+        let wind = report.wind.unwrap();
+        assert_eq!(wind.unit, SpeedUnit::Knots);
+        assert_eq!(String::from(wind.reported_speed.unwrap()), "08");
+        let prevailing = report.visibility.unwrap().prevailing.unwrap();
+        assert_eq!(prevailing.unit, Some(DistanceUnit::StatuteMiles));
+        assert_eq!(String::from(prevailing.reported_distance), "1 1/2");
+        assert_eq!(
+            report.cloud_cover[0].reported_base,
+            Some(ReportedValue::Decimal {
+                digits: 200,
+                width: 3,
+                scale: 0
+            })
+        );
+        let reported_pressure = report.reported_pressure.unwrap();
+        assert_eq!(reported_pressure.unit, PressureUnit::InchesOfMercury);
+        assert!((report.pressure.unwrap().get::<inch_of_mercury>() - 30.04).abs() < 1e-9);
+
+        let report = metar("EGLL 290350Z 24004MPS 9999 FEW035 17/09 Q1014").unwrap();
+        assert_eq!(report.wind.unwrap().unit, SpeedUnit::MetersPerSecond);
+        let prevailing = report.visibility.unwrap().prevailing.unwrap();
+        assert_eq!(prevailing.unit, None);
+        assert_eq!(String::from(prevailing.reported_distance), "9999");
+        assert!((report.pressure.unwrap().get::<hectopascal>() - 1014.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn round_trip_zero_padded_values() {
+        let report = metar("EGLL 291020Z 24005KT 0800 R27L/0600 FG VV002 08/08 Q0998").unwrap();
+        assert_eq!(
+            String::from(report.wind.unwrap().reported_speed.unwrap()),
+            "05"
+        );
+        let prevailing = report.visibility.unwrap().prevailing.unwrap();
+        assert_eq!(String::from(prevailing.reported_distance), "0800");
+        match report.runway_visibilities[0].visibility {
+            VisibilityType::Fixed(raw) => assert_eq!(String::from(raw.reported_distance), "0600"),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            String::from(report.cloud_cover[0].reported_base.unwrap()),
+            "002"
+        );
+        assert_eq!(
+            String::from(report.reported_pressure.unwrap().value),
+            "0998"
+        );
+
+        for val in ["0800", "29.92", "0.05", "1 1/2", "1/4"] {
+            assert_eq!(String::from(ReportedValue::try_from(val).unwrap()), val);
+        }
+        for val in ["1.0000000001", "0.0000000001", "12345678901"] {
+            assert!(ReportedValue::try_from(val).is_err(), "{}", val);
+        }
+        assert!(metar("KSEA 290353Z 010001.0000000001KT 10SM A3004").is_err());
+    }
+
+    #[test]
+    fn color_state_consistency() {
+        for (val, consistent) in [
+            (
+                "EGVN 291350Z 24012KT 9999 FEW035 SCT250 17/09 Q1014 BLU",
+                Some(true),
+            ),
+            (
+                "EGVN 291350Z 24012KT 9999 BKN008 17/09 Q1014 BLU",
+                Some(false),
+            ),
+            (
+                "EGVN 291350Z 24012KT 3000 BR OVC006 17/09 Q1014 YLO1",
+                Some(true),
+            ),
+            ("EGVN 291350Z 24012KT CAVOK 17/09 Q1014 BLU", Some(true)),
+            ("EGVN 291350Z 24012KT 9999 BKN008 17/09 Q1014", None),
+        ] {
+            assert_eq!(
+                metar(val).expect(val).is_color_consistent(),
+                consistent,
+                "{}",
+                val
+            );
+        }
+    }
+
+    #[test]
+    fn parse_kind_and_wind_shear() {
+        let report =
+            metar("SPECI LTBA 251420Z 22015KT 9999 SCT030 18/12 Q1006 RETSRA WS R05 WS RWY35L")
+                .unwrap();
+        assert_eq!(report.kind, Some(ReportKind::Speci));
+        assert_eq!(
+            report.wind_shear,
+            vec![
+                WindShear::Runway("05".into()),
+                WindShear::Runway("35L".into())
+            ]
+        );
+
+        let report =
+            metar("METAR OMDB 251400Z 33012KT 6000 NSC 38/18 Q1001 WS ALL RWY NOSIG").unwrap();
+        assert_eq!(report.kind, Some(ReportKind::Metar));
+        assert_eq!(report.wind_shear, vec![WindShear::AllRunways]);
+        assert_eq!(report.trends, vec![Trend::NoSignificantChange]);
+    }
+
+    #[test]
+    fn owned_report_outlives_input() {
+        const REPORT: &str = "METAR UUEE 251430Z 18004MPS 9999 R06L/1000U BKN020 14/09 Q1014 WS R24C R88/290550 RMK QFE745";
+        let report = {
+            let input = REPORT.to_string();
+            let report = metar(&input).unwrap().into_owned();
+            report
+        };
+
+        let report = std::thread::spawn(move || report).join().unwrap();
+        assert_eq!(report, metar(REPORT).unwrap());
+        assert_eq!(report.remark.as_deref(), Some("RMK QFE745"));
+    }
+
+    #[test]
+    fn spans_cover_groups() {
+        let input = "METAR EGLL 291020Z 24015G25KT 210V270 1500 R27L/P1500 -RA BR FEW005 BKN010 15/14 Q1008 RERA TEMPO 4000 BKN014 NOSIG RMK HAZY=";
+        let (report, spans) = super::metar_with_spans(input).unwrap();
+        assert_eq!(report, metar(input).unwrap());
+        let text = |span: &std::ops::Range<usize>| &input[span.clone()];
+        assert_eq!(text(&spans.identifier), "EGLL");
+        assert_eq!(text(&spans.wind.clone().unwrap()), "24015G25KT 210V270");
+        assert_eq!(text(&spans.visibility.clone().unwrap()), "1500");
+        assert_eq!(
+            spans
+                .groups()
+                .iter()
+                .map(|(name, span)| (*name, text(span)))
+                .collect::<Vec<_>>(),
+            vec![
+                ("report name", "METAR"),
+                ("identifier", "EGLL"),
+                ("observation time", "291020Z"),
+                ("wind", "24015G25KT 210V270"),
+                ("visibility", "1500"),
+                ("runway visibility", "R27L/P1500"),
+                ("weather", "-RA"),
+                ("weather", "BR"),
+                ("cloud cover", "FEW005"),
+                ("cloud cover", "BKN010"),
+                ("temperatures", "15/14"),
+                ("pressure", "Q1008"),
+                ("trend", "TEMPO 4000 BKN014"),
+                ("trend", "NOSIG"),
+                ("remark", "RMK HAZY"),
+            ]
+        );
+
+        // Groups that parse to nothing have no span
+        let (_, spans) = super::metar_with_spans("KSEA 290353Z /////KT //// 19/09").unwrap();
+        assert_eq!((spans.wind, spans.visibility), (None, None));
+    }
+
+    #[test]
+    fn recover_multiple_errors() {
+        let input = "KSEA 290353Z 01008KTT 10SM SCT200 19/09 A3004 NOSGI";
+        let (report, errors) = super::metar_with_recovery(input);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| &input[super::group_at(input, err.location.offset)])
+                .collect::<Vec<_>>(),
+            vec!["01008KTT", "NOSGI"]
+        );
+        let report = report.unwrap();
+        assert_eq!(report.wind, None);
+        assert_eq!(report.cloud_cover.len(), 1);
+
+        let (report, errors) = super::metar_with_recovery("KSEA 290353Z 01008KT");
+        assert!(report.is_some() && errors.is_empty());
+    }
+
     #[test]
     fn parse_whitespace() {
         for val in [" ///// ", " > ", "\t", "\r\n\r\n", " > /// \n> "] {
@@ -1,85 +1,134 @@
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::ops::Range;
 use uom::{
     si::angle::degree,
     si::f64::{Angle, Length, Pressure, ThermodynamicTemperature, Velocity},
     si::length::{decimeter, foot, kilometer, meter, mile, millimeter},
-    si::pressure::{hectopascal, inch_of_mercury},
+    si::pressure::{hectopascal, inch_of_mercury, millimeter_of_mercury},
     si::thermodynamic_temperature::degree_celsius,
     si::velocity::{kilometer_per_hour, knot, meter_per_second},
 };
 
 use crate::tokens::*;
 
+enum RemarkGroup {
+    FieldPressure(FieldPressure),
+    Obscured(Obscured),
+    Other,
+}
+
 peg::parser! {
     pub grammar weather_reports() for str {
         /// [METAR](https://en.wikipedia.org/wiki/METAR) parser
-        pub rule metar() -> MetarReport<'input> =
+        pub rule metar() -> MetarReport<'input> = report:spanned_metar() { report.0 }
+
+        /// Spans may include trailing whitespace
+        pub rule spanned_metar() -> (MetarReport<'input>, MetarSpans) =
                     whitespace()
-                    report_name()? whitespace()
+                    kind:spanned(<report_name()>)? whitespace()
                     pre_observation_flags:observation_flag() ** whitespace() whitespace()
-                    identifier:icao_identifier() whitespace()
-                    observation_time:observation_time()? whitespace()
+                    identifier:spanned(<icao_identifier()>) whitespace()
+                    observation_time:spanned(<observation_time()>)? whitespace()
                     observation_validity_range:observation_validity_range()? whitespace()
                     // Some stations incorrectly place METAR here
-                    report_name()? whitespace()
+                    misplaced_kind:spanned(<report_name()>)? whitespace()
                     observation_flags:observation_flag() ** whitespace() whitespace()
-                    wind:wind()? whitespace()
-                    pre_temperatures:temperatures()? whitespace()
-                    visibility:visibility()? whitespace()
-                    runway_visibilities:runway_visibility() ** whitespace() whitespace()
+                    wind:spanned(<wind()>)? whitespace()
+                    pre_temperatures:spanned(<temperatures()>)? whitespace()
+                    visibility:spanned(<visibility()>)? whitespace()
+                    runway_visibilities:spanned(<runway_visibility()>) ** whitespace() whitespace()
                     pre_recent_weather:recent_weather_sequence()? whitespace()
-                    weather:weather_sequence()? whitespace()
-                    cloud_cover:cloud_cover() ** whitespace() whitespace()
-                    cavok:("CAVOK" whitespace())?
-                    temperatures:temperatures()? whitespace()
-                    pressure:pressure()? whitespace()
-                    // Some stations also report the altimeter setting in a different unit and/or Q Field Elevation, discard it
-                    pressure() ** whitespace() whitespace()
-                    weather_post_pressure:weather_sequence()? whitespace()
-                    cloud_cover_post_pressure:cloud_cover() ** whitespace() whitespace()
-                    temperatures_post_pressure:temperatures()? whitespace()
-                    accumulated_rainfall:accumulated_rainfall()? whitespace()
+                    weather:spanned_weather_sequence()? whitespace()
+                    cloud_cover:spanned(<cloud_cover()>) ** whitespace() whitespace()
+                    cavok:(cavok:spanned(<"CAVOK">) whitespace() { cavok })?
+                    temperatures:spanned(<temperatures()>)? whitespace()
+                    // Reported instead of the altimeter setting by a few stations
+                    pre_field_pressure:field_pressure()? whitespace()
+                    pressure:spanned(<pressure()>)? whitespace()
+                    // Some stations also report QFE and/or the altimeter setting in a different unit, which is discarded
+                    field_pressures:(field_pressure:field_pressure() { Some(field_pressure) } / pressure() { None }) ** whitespace() whitespace()
+                    weather_post_pressure:spanned_weather_sequence()? whitespace()
+                    cloud_cover_post_pressure:spanned(<cloud_cover()>) ** whitespace() whitespace()
+                    temperatures_post_pressure:spanned(<temperatures()>)? whitespace()
+                    accumulated_rainfall:spanned(<accumulated_rainfall()>)? whitespace()
                     recent_weather:recent_weather_sequence()? whitespace()
-                    cloud_cover_post_recent_weather:cloud_cover() ** whitespace() whitespace()
-                    temperatures_post_recent_weather:temperatures()? whitespace()
+                    wind_shear:wind_shear() ** whitespace() whitespace()
+                    cloud_cover_post_recent_weather:spanned(<cloud_cover()>) ** whitespace() whitespace()
+                    temperatures_post_recent_weather:spanned(<temperatures()>)? whitespace()
                     // Military stations often report these
-                    color:color()? whitespace()
+                    color:spanned(<color()>)? whitespace()
                     // Some stations report runway visibility after pressure
-                    runway_visibilities_post_pressure:runway_visibility() ** whitespace() whitespace()
-                    runway_reports:runway_report() ** whitespace() whitespace()
+                    runway_visibilities_post_pressure:spanned(<runway_visibility()>) ** whitespace() whitespace()
+                    runway_reports:spanned(<runway_report()>) ** whitespace() whitespace()
                     water_conditions:water_conditions()? whitespace()
-                    trends:trend()** whitespace() whitespace()
-                    remark:$((":RMK" / "R MK"/ "RMK" / "REMARK") [^'$']*)?
+                    trends:spanned(<trend()>) ** whitespace() whitespace()
+                    remark:spanned(<$((":RMK" / "R MK"/ "RMK" / "REMARK") [^'$']*)>)?
                     maintenance_needed:quiet!{"$"}? whitespace()
                     // Consumes trailing garbage characters
                     quiet!{"/"*} whitespace()
                     // Some machines use = to indicate end of message
                     quiet!{"=" [_]*}? whitespace()
                     {
-                MetarReport {
-                    identifier,
-                    observation_time,
+                let runway_visibilities = runway_visibilities.into_iter().chain(runway_visibilities_post_pressure).filter_map(|(rvr, span)| Some((rvr?, span))).collect::<Vec<_>>();
+                let weather = weather.unwrap_or_default().into_iter().chain(weather_post_pressure.unwrap_or_default()).collect::<Vec<_>>();
+                let cloud_cover = cloud_cover.into_iter().chain(cloud_cover_post_pressure).chain(cloud_cover_post_recent_weather).filter_map(|(cloud_cover, span)| Some((cloud_cover?, span))).collect::<Vec<_>>();
+                let temperatures = vec![pre_temperatures, temperatures, temperatures_post_pressure, temperatures_post_recent_weather].into_iter().flatten().find_map(|(temperatures, span)| Some((temperatures?, span)));
+                let pressure = pressure.and_then(|(pressure, span)| Some((pressure?, span)));
+                let wind = wind.and_then(|(wind, span)| Some((wind?, span)));
+                let visibility = visibility.and_then(|(visibility, span)| Some((visibility?, span)));
+                let kind = kind.or(misplaced_kind);
+                let runway_reports = runway_reports.into_iter().filter_map(|(runway_report, span)| Some((runway_report?, span))).collect::<Vec<_>>();
+                let (remark_field_pressure, obscured) = remark.as_ref().and_then(|(remark, _)| remark_groups(remark).ok()).unwrap_or_default();
+                let spans = MetarSpans {
+                    kind: kind.as_ref().map(|(_, span)| span.clone()),
+                    identifier: identifier.1,
+                    observation_time: observation_time.as_ref().map(|(_, span)| span.clone()),
+                    wind: wind.as_ref().map(|(_, span)| span.clone()),
+                    visibility: visibility.as_ref().map(|(_, span)| span.clone()),
+                    runway_visibilities: runway_visibilities.iter().map(|(_, span)| span.clone()).collect(),
+                    runway_reports: runway_reports.iter().map(|(_, span)| span.clone()).collect(),
+                    weather: weather.iter().map(|(_, span)| span.clone()).collect(),
+                    cloud_cover: cloud_cover.iter().map(|(_, span)| span.clone()).collect(),
+                    temperatures: temperatures.as_ref().map(|(_, span)| span.clone()),
+                    pressure: pressure.as_ref().map(|(_, span)| span.clone()),
+                    cavok: cavok.as_ref().map(|(_, span)| span.clone()),
+                    accumulated_rainfall: accumulated_rainfall.as_ref().map(|(_, span)| span.clone()),
+                    color: color.as_ref().map(|(_, span)| span.clone()),
+                    trends: trends.iter().map(|(_, span)| span.clone()).collect(),
+                    remark: remark.as_ref().map(|(_, span)| span.clone()),
+                };
+                (MetarReport {
+                    kind: kind.map(|(kind, _)| kind),
+                    identifier: identifier.0.into(),
+                    observation_time: observation_time.map(|(observation_time, _)| observation_time),
                     observation_validity_range,
                     observation_flags: pre_observation_flags.iter().copied().chain(observation_flags).collect(),
-                    wind: wind.flatten(),
-                    visibility: visibility.flatten(),
-                    runway_visibilities: runway_visibilities.iter().copied().chain(runway_visibilities_post_pressure).flatten().collect(),
-                    runway_reports: runway_reports.iter().copied().flatten().collect(),
-                    weather: weather.unwrap_or_default().drain(..).chain(weather_post_pressure.unwrap_or_default()).collect(),
-                    cloud_cover: cloud_cover.iter().copied().chain(cloud_cover_post_pressure).chain(cloud_cover_post_recent_weather).flatten().collect(),
+                    wind: wind.map(|(wind, _)| wind),
+                    visibility: visibility.map(|(visibility, _)| visibility),
+                    runway_visibilities: runway_visibilities.into_iter().map(|(rvr, _)| rvr).collect(),
+                    runway_reports: runway_reports.into_iter().map(|(runway_report, _)| runway_report).collect(),
+                    weather: weather.into_iter().map(|(weather, _)| weather).collect(),
+                    cloud_cover: cloud_cover.into_iter().map(|(cloud_cover, _)| cloud_cover).collect(),
                     cavok: cavok.is_some(),
-                    temperatures: pre_temperatures.flatten().or_else(|| temperatures.flatten()).or_else(|| temperatures_post_pressure.flatten()).or_else(|| temperatures_post_recent_weather.flatten()),
-                    pressure: pressure.flatten(),
-                    accumulated_rainfall,
+                    temperatures: temperatures.map(|(temperatures, _)| temperatures),
+                    pressure: pressure.as_ref().map(|((pressure, _), _)| *pressure),
+                    reported_pressure: pressure.map(|((_, reported), _)| reported),
+                    field_pressure: pre_field_pressure.or_else(|| field_pressures.into_iter().flatten().next()).or(remark_field_pressure),
+                    accumulated_rainfall: accumulated_rainfall.map(|(accumulated_rainfall, _)| accumulated_rainfall),
                     recent_weather: pre_recent_weather.unwrap_or_default().iter().cloned().chain(recent_weather.unwrap_or_default()).collect(),
-                    color,
+                    wind_shear,
+                    color: color.map(|(color, _)| color),
                     water_conditions,
-                    trends,
-                    remark,
+                    trends: trends.into_iter().map(|(trend, _)| trend).collect(),
+                    remark: remark.map(|(remark, _)| Cow::Borrowed(remark)),
+                    obscured,
                     maintenance_needed: maintenance_needed.is_some(),
-                }
+                }, spans)
             }
-        rule report_name() -> &'input str = quiet!{$("METAR" / "SPECI")} / expected!("report name");
+        rule spanned<T>(r: rule<T>) -> (T, Range<usize>) = start:position!() value:r() end:position!() { (value, start..end) }
+
+        rule report_name() -> ReportKind = kind:$(quiet!{"METAR" / "SPECI"} / expected!("report name")) { ReportKind::try_from(kind).unwrap() }
 
         pub rule icao_identifier() -> &'input str = $(quiet!{letter() letter_or_digit()*<3>} / expected!("ICAO identifier"));
 
@@ -132,30 +181,25 @@ peg::parser! {
         rule correction() -> &'input str = $("COR" / ("CC" letter()));
 
         pub rule wind() -> Option<Wind> =
-            direction:$("VRB" / (digit()*<3>))? speed:$(("P" digit()*<2>) / (digit()+ ("." digit()+)?))? peak_gust:$("G" ("//" / digit()+))? unit:windspeed_unit() whitespace() variance:wind_variance()? {
-                let speed = speed.map(|speed| speed.trim_start_matches('P').parse().unwrap());
-                Some(Wind {
+            direction:$("VRB" / (digit()*<3>))? speed:$(("P" digit()*<2>) / (digit()+ ("." digit()+)?))? peak_gust:$("G" ("//" / digit()+))? unit:windspeed_unit() whitespace() variance:wind_variance()? {?
+                let unit = SpeedUnit::try_from(unit).unwrap();
+                let reported_speed = speed.map(|speed| ReportedValue::try_from(speed.trim_start_matches('P'))).transpose().or(Err("wind speed"))?;
+                let reported_peak_gust = peak_gust.filter(|gusts| *gusts != "G//").map(|gusts| ReportedValue::try_from(gusts.trim_start_matches('G'))).transpose().or(Err("peak gust"))?;
+                Ok(Some(Wind {
                     direction: direction.filter(|dir| *dir != "VRB").map(|direction| Angle::new::<degree>(direction.parse().unwrap())),
-                    speed: speed.map(|speed| match unit {
-                        "MPS" => Velocity::new::<meter_per_second>(speed),
-                        "KT" | "KTS" | "KTM" => Velocity::new::<knot>(speed),
-                        "KMH" => Velocity::new::<kilometer_per_hour>(speed),
-                        _ => unreachable!()
-                    }),
-                    peak_gust: peak_gust.filter(|gusts| *gusts != "G//").map(|gusts| gusts.trim_start_matches('G').parse().unwrap()).map(|gusts| match unit {
-                        "MPS" => Velocity::new::<meter_per_second>(gusts),
-                        "KT" | "KTS" | "KTM" => Velocity::new::<knot>(gusts),
-                        "KMH" => Velocity::new::<kilometer_per_hour>(gusts),
-                        _ => unreachable!()
-                    }),
+                    speed: reported_speed.map(|speed| velocity(speed, unit)),
+                    peak_gust: reported_peak_gust.map(|gusts| velocity(gusts, unit)),
                     variance,
-                })
+                    unit,
+                    reported_speed,
+                    reported_peak_gust,
+                }))
             }
             / ("//////" / "/////") windspeed_unit() whitespace() variance:("///V///")? {
                 None
             }
         rule windspeed_unit() -> &'input str = $(quiet!{"MPS" / "KTM" / "KTS" / "KT" / "KMH"} / expected!("velocity unit"))
-        rule wind_variance() -> (Angle, Angle) = variance_begin:$(digit()*<3>) "V" variance_end:$(digit()*<3>) {
+        pub rule wind_variance() -> (Angle, Angle) = variance_begin:$(digit()*<3>) "V" variance_end:$(digit()*<3>) {
             (
                 Angle::new::<degree>(variance_begin.parse().unwrap()),
                 Angle::new::<degree>(variance_end.parse().unwrap()),
@@ -212,45 +256,13 @@ peg::parser! {
             }
         }
         rule raw_visibility() -> RawVisibility =
-            out_of_range:out_of_range()? whole:$(digit()+) whitespace() numerator:$(digit()+) "/" denominator:$(digit()+) unit:visibility_unit()? {
-                let value = whole.parse::<f64>().unwrap() + numerator.parse::<f64>().unwrap() / denominator.parse::<f64>().unwrap();
-
-                let distance = match unit {
-                    Some("KM") => Length::new::<kilometer>(value),
-                    Some("SM") => Length::new::<mile>(value),
-                    Some("M") | None => Length::new::<meter>(value),
-                    _ => unreachable!()
-                };
-                RawVisibility {
-                    distance,
-                    out_of_range,
-                }
-            }
-            / out_of_range:out_of_range()? numerator:$(digit()+) "/" denominator:$(digit()+) unit:visibility_unit()? {
-                let value = numerator.parse::<f64>().unwrap() / denominator.parse::<f64>().unwrap();
-                let distance = match unit {
-                    Some("KM") => Length::new::<kilometer>(value),
-                    Some("SM") => Length::new::<mile>(value),
-                    Some("M") | None => Length::new::<meter>(value),
-                    _ => unreachable!()
-                };
-                RawVisibility {
-                    distance,
-                    out_of_range,
-                }
+            out_of_range:out_of_range()? whole:$(digit()+) whitespace() fraction:$(digit()+ "/" digit()+) unit:visibility_unit()? {?
+                let reported_distance = ReportedValue::try_from(format!("{} {}", whole, fraction).as_str()).or(Err("visibility"))?;
+                Ok(raw_visibility(out_of_range, reported_distance, unit))
             }
-            / out_of_range:out_of_range()? value:$(digit()+) unit:visibility_unit()? {
-                let value = value.parse::<f64>().unwrap();
-                let distance = match unit {
-                    Some("KM") => Length::new::<kilometer>(value),
-                    Some("SM") => Length::new::<mile>(value),
-                    Some("M") | None => Length::new::<meter>(value),
-                    _ => unreachable!()
-                };
-                RawVisibility {
-                    distance,
-                    out_of_range,
-                }
+            / out_of_range:out_of_range()? value:$(digit()+ ("/" digit()+)?) unit:visibility_unit()? {?
+                let reported_distance = ReportedValue::try_from(value).or(Err("visibility"))?;
+                Ok(raw_visibility(out_of_range, reported_distance, unit))
             }
 
         rule compass_direction() -> CompassDirection = val:$(quiet!{"NE" / "NW" / "N" / "SE" / "SW" / "S" / "E" / "W"} / expected!("8-point compass direction")) {
@@ -280,46 +292,14 @@ peg::parser! {
             / "R" designator:designator()? ("/////" "/"*) &required_whitespace_or_eof() {
                 None
             }
-        rule raw_runway_visibility_range() -> (RawVisibility, RawVisibility) = lower_out_of_range:out_of_range()? lower_value:$(digit()+) "V" upper_out_of_range:out_of_range()? upper_value:$(digit()+) unit:$("FT")? {
-            let lower_value = lower_value.parse::<f64>().unwrap();
-            let upper_value = upper_value.parse::<f64>().unwrap();
-            if let Some("FT") = unit {
-                (
-                    RawVisibility {
-                        distance: Length::new::<foot>(lower_value),
-                        out_of_range: lower_out_of_range,
-                    },
-                    RawVisibility {
-                        distance: Length::new::<foot>(upper_value),
-                        out_of_range: upper_out_of_range,
-                    },
-                )
-            } else {
-                (
-                    RawVisibility {
-                        distance: Length::new::<meter>(lower_value),
-                        out_of_range: lower_out_of_range,
-                    },
-                    RawVisibility {
-                        distance: Length::new::<meter>(upper_value),
-                        out_of_range: upper_out_of_range,
-                    },
-                )
-            }
+        rule raw_runway_visibility_range() -> (RawVisibility, RawVisibility) = lower_out_of_range:out_of_range()? lower_value:$(digit()+) "V" upper_out_of_range:out_of_range()? upper_value:$(digit()+) unit:$("FT")? {?
+            Ok((
+                raw_visibility(lower_out_of_range, ReportedValue::try_from(lower_value).or(Err("runway visibility"))?, unit),
+                raw_visibility(upper_out_of_range, ReportedValue::try_from(upper_value).or(Err("runway visibility"))?, unit),
+            ))
         }
-        rule raw_runway_visibility() -> RawVisibility = out_of_range:out_of_range()? value:$(digit()+) unit:$("FT")? {
-            let value = value.parse::<f64>().unwrap();
-            if let Some("FT") = unit {
-                RawVisibility {
-                    distance: Length::new::<foot>(value),
-                    out_of_range,
-                }
-            } else {
-                RawVisibility {
-                    distance: Length::new::<meter>(value),
-                    out_of_range,
-                }
-            }
+        rule raw_runway_visibility() -> RawVisibility = out_of_range:out_of_range()? value:$(digit()+) unit:$("FT")? {?
+            Ok(raw_visibility(out_of_range, ReportedValue::try_from(value).or(Err("runway visibility"))?, unit))
         }
         rule out_of_range() -> OutOfRange = val:$(quiet!{"M" / "P"} / expected!("bound")) { OutOfRange::try_from(val).unwrap() };
         rule visibility_trend() -> VisibilityTrend = "/"? val:$(quiet!{("D" / "N" / "U")} / expected!("visibility trend")) { VisibilityTrend::try_from(val.trim_start_matches('/')).unwrap() };
@@ -331,6 +311,15 @@ peg::parser! {
                     report_info,
                 })
             }
+            // Legacy 8 digit group still reported by CIS stations
+            / designator:legacy_runway_designator() report_info:runway_report_info() &required_whitespace_or_eof() {
+                Some(RunwayReport {
+                    designator: designator.into(),
+                    report_info,
+                })
+            }
+        /// Runways 01-36, 51-86 for the right of parallel runways, 88 for all runways and 99 for a repeated report
+        rule legacy_runway_designator() -> &'input str = $("0" ['1'..='9'] / ['1'..='2'] digit() / "3" ['0'..='6'] / "5" ['1'..='9'] / ['6'..='7'] digit() / "8" ['0'..='6'] / "88" / "99")
         rule runway_report_info() -> RunwayReportInfo =
             "CLRD" friction:$("//" / digit()+) {
                 RunwayReportInfo::Cleared {
@@ -387,17 +376,22 @@ peg::parser! {
         }
         rule friction_coefficient() -> f64 = digits:$(['0'..='8'] digit()) { digits.parse::<f64>().unwrap() / 100. }
 
-        rule designator() -> &'input str = $(quiet!{digit()+ ("L"/"C"/"R"/"D")?} / expected!("runway designator"));
+        rule designator() -> Cow<'input, str> = designator:$(quiet!{digit()+ ("L"/"C"/"R"/"D")?} / expected!("runway designator")) { designator.into() }
 
 
         rule recent_weather_sequence() -> Vec<Weather> = recent_weather:recent_weather() ++ whitespace() &required_whitespace_or_eof() {
-            recent_weather.iter().cloned().flatten().collect()
+            recent_weather.iter().flatten().cloned().collect()
         }
         rule recent_weather() -> Option<Weather> =
             "RE" weather:weather() &required_whitespace_or_eof() { Some(weather) }
             / "RE//" &required_whitespace_or_eof() { None }
 
-        rule weather_sequence() -> Vec<Weather> = weather:weather() ++ whitespace() &required_whitespace_or_eof() { weather }
+        rule wind_shear() -> WindShear<'input> =
+            "WS" whitespace() "ALL" whitespace() "RWY" "S"? &required_whitespace_or_eof() { WindShear::AllRunways }
+            / "WS" whitespace() ("RWY" / "R") designator:designator() &required_whitespace_or_eof() { WindShear::Runway(designator) }
+
+        rule weather_sequence() -> Vec<Weather> = weather:spanned_weather_sequence() { weather.into_iter().map(|(weather, _)| weather).collect() }
+        rule spanned_weather_sequence() -> Vec<(Weather, Range<usize>)> = weather:spanned(<weather()>) ++ whitespace() &required_whitespace_or_eof() { weather }
 
         pub rule weather() -> Weather =
             intensity:intensity() vicinity:"VC"? descriptor:descriptor()? precipitation:precipitation()+ {
@@ -497,20 +491,23 @@ peg::parser! {
                     coverage,
                     base: None,
                     cloud_type: cloud_type.flatten(),
+                    reported_base: None,
                 })
             }
-            / coverage:cloud_coverage() whitespace() base:$(digit()*<3, 4>) whitespace() "//" required_whitespace_or_eof() {
+            / coverage:cloud_coverage() whitespace() base:cloud_base() whitespace() "//" required_whitespace_or_eof() {
                 Some(CloudCover {
                     coverage,
-                    base: Some(Length::new::<foot>(base.parse().unwrap()) * 100.),
+                    base: Some(Length::new::<foot>(f64::from(base)) * 100.),
                     cloud_type: None,
+                    reported_base: Some(base),
                 })
             }
-            / coverage:cloud_coverage() whitespace() base:$(digit()*<3, 4>) whitespace() cloud_type:cloud_type()? {
+            / coverage:cloud_coverage() whitespace() base:cloud_base() whitespace() cloud_type:cloud_type()? {
                 Some(CloudCover {
                     coverage,
-                    base: Some(Length::new::<foot>(base.parse().unwrap()) * 100.),
+                    base: Some(Length::new::<foot>(f64::from(base)) * 100.),
                     cloud_type: cloud_type.flatten(),
+                    reported_base: Some(base),
                 })
             }
             / coverage:cloud_coverage() {
@@ -518,8 +515,10 @@ peg::parser! {
                     coverage,
                     base: None,
                     cloud_type: None,
+                    reported_base: None,
                 })
             }
+        rule cloud_base() -> ReportedValue = base:$(digit()*<3, 4>) { ReportedValue::try_from(base).unwrap() }
 
         rule cloud_coverage() -> CloudCoverage =
             val:$(quiet!{
@@ -566,16 +565,79 @@ peg::parser! {
                 None
             }
 
-        pub rule pressure() -> Option<Pressure> =
-            pressure_unit:pressure_unit() whitespace() pressure:$(digit()+ ("." digit()+)?) {
-                match pressure_unit {
-                    "A" => Some(Pressure::new::<hectopascal>(pressure.parse().unwrap())),
-                    _ => Some(Pressure::new::<inch_of_mercury>(pressure.parse::<f64>().unwrap() / 100.))
-                }
+        pub rule pressure() -> Option<(Pressure, ReportedPressure)> =
+            unit:pressure_unit() whitespace() value:$(digit()+ ("." digit()+)?) {?
+                let reported = ReportedPressure {
+                    unit: PressureUnit::try_from(unit).unwrap(),
+                    value: ReportedValue::try_from(value).or(Err("pressure"))?,
+                };
+                let value = f64::from(reported.value);
+                let pressure = match (reported.unit, reported.value) {
+                    // Altimeter settings are usually in hundredths but a few stations include the decimal point
+                    (PressureUnit::InchesOfMercury, ReportedValue::Decimal { scale: 0, .. }) => Pressure::new::<inch_of_mercury>(value / 100.),
+                    (PressureUnit::InchesOfMercury, _) => Pressure::new::<inch_of_mercury>(value),
+                    (PressureUnit::Hectopascals, _) => Pressure::new::<hectopascal>(value),
+                };
+                Ok(Some((pressure, reported)))
             }
             / pressure_unit() whitespace() ("////" / "NIL") { None }
         rule pressure_unit() -> &'input str = $(quiet!{"QFE" / "QNH" / "Q" / "A"} / expected!("pressure unit"));
 
+        /// QFE in millimeters of mercury and optionally hectopascals, or only in hectopascals if it has a decimal point
+        pub rule field_pressure() -> FieldPressure =
+            "QFE" whitespace() value:$(digit()*<3>) "/" hectopascals:$(digit()*<3,4>) group_end() {
+                let value = ReportedValue::try_from(value).unwrap();
+                FieldPressure {
+                    pressure: Pressure::new::<millimeter_of_mercury>(f64::from(value)),
+                    unit: Some(FieldPressureUnit::MillimetersOfMercury),
+                    value,
+                    reported_hectopascals: Some(ReportedValue::try_from(hectopascals).unwrap()),
+                }
+            }
+            / "QFE" whitespace() value:$(digit()+ "." digit()+) group_end() {?
+                let value = ReportedValue::try_from(value).or(Err("QFE"))?;
+                Ok(FieldPressure {
+                    pressure: Pressure::new::<hectopascal>(f64::from(value)),
+                    unit: Some(FieldPressureUnit::Hectopascals),
+                    value,
+                    reported_hectopascals: None,
+                })
+            }
+            / "QFE" whitespace() value:$(digit()+) group_end() {?
+                let value = ReportedValue::try_from(value).or(Err("QFE"))?;
+                Ok(FieldPressure {
+                    pressure: if f64::from(value) <= MAX_UNITLESS_QFE_MILLIMETERS_OF_MERCURY {
+                        Pressure::new::<millimeter_of_mercury>(f64::from(value))
+                    } else {
+                        Pressure::new::<hectopascal>(f64::from(value))
+                    },
+                    unit: None,
+                    value,
+                    reported_hectopascals: None,
+                })
+            }
+
+        rule obscured() -> Obscured = val:$("MTNS" / "MT" / "OBST") whitespace_char()+ "OBSC" group_end() { Obscured::try_from(val).unwrap() }
+
+        /// Typed groups in a remark, skipping any others
+        pub rule remark_groups() -> (Option<FieldPressure>, Vec<Obscured>) = groups:remark_group() ** ((whitespace_char() / "=")+) (whitespace_char() / "=")* {
+            let mut field_pressure = None;
+            let mut obscured = vec![];
+            for group in groups {
+                match group {
+                    RemarkGroup::FieldPressure(value) => field_pressure = field_pressure.or(Some(value)),
+                    RemarkGroup::Obscured(value) => obscured.push(value),
+                    RemarkGroup::Other => {}
+                }
+            }
+            (field_pressure, obscured)
+        }
+        rule remark_group() -> RemarkGroup =
+            field_pressure:field_pressure() { RemarkGroup::FieldPressure(field_pressure) }
+            / obscured:obscured() { RemarkGroup::Obscured(obscured) }
+            / (!whitespace_char() [_])+ { RemarkGroup::Other }
+        rule group_end() = &(whitespace_char() / "=" / "$" / ![_])
+
         rule accumulated_rainfall() -> AccumulatedRainfall = "RF" recent:$(digit()+ "." digit()+) "/" past:$(digit()+ "." digit()+) {
             AccumulatedRainfall {
                 recent: Length::new::<millimeter>(recent.parse().unwrap()),
@@ -616,7 +678,7 @@ peg::parser! {
                 }
             }
 
-        rule trend() -> Trend =
+        pub rule trend() -> Trend =
             $(quiet!{"NOSIG" / "NOISIG" / "NSOIG" / "N0SIG" / "NOS16" / "NOSING" / "NOSG" / "NSG" / "NOSIC" / "NOSIGI" } / expected!("trend")) {
                 Trend::NoSignificantChange
             }
@@ -642,7 +704,7 @@ peg::parser! {
                         _ => unreachable!()
                     }
             }
-        rule trend_time() -> TrendTime =
+        pub rule trend_time() -> TrendTime =
             "FM" from:military_time() whitespace() "TL" until:military_time() {
                 TrendTime::Range {
                     from,
@@ -660,3 +722,32 @@ peg::parser! {
             };
     }
 }
+
+pub(crate) fn velocity(value: ReportedValue, unit: SpeedUnit) -> Velocity {
+    let value = f64::from(value);
+    match unit {
+        SpeedUnit::Knots => Velocity::new::<knot>(value),
+        SpeedUnit::MetersPerSecond => Velocity::new::<meter_per_second>(value),
+        SpeedUnit::KilometersPerHour => Velocity::new::<kilometer_per_hour>(value),
+    }
+}
+
+fn raw_visibility(
+    out_of_range: Option<OutOfRange>,
+    reported_distance: ReportedValue,
+    unit: Option<&str>,
+) -> RawVisibility {
+    let unit = unit.map(|unit| DistanceUnit::try_from(unit).unwrap());
+    let value = f64::from(reported_distance);
+    RawVisibility {
+        out_of_range,
+        distance: match unit {
+            Some(DistanceUnit::Kilometers) => Length::new::<kilometer>(value),
+            Some(DistanceUnit::StatuteMiles) => Length::new::<mile>(value),
+            Some(DistanceUnit::Feet) => Length::new::<foot>(value),
+            Some(DistanceUnit::Meters) | None => Length::new::<meter>(value),
+        },
+        unit,
+        reported_distance,
+    }
+}
@@ -0,0 +1,241 @@
+use peg::ParseLiteral;
+use std::convert::TryFrom;
+use uom::{
+    si::angle::degree,
+    si::f64::{Angle, Length, ThermodynamicTemperature},
+    si::length::{foot, mile, nautical_mile},
+    si::thermodynamic_temperature::degree_celsius,
+};
+
+use super::parser::weather_reports;
+use crate::tokens::{pirep::*, *};
+
+peg::parser! {
+    pub grammar pirep_reports() for str {
+        /// [Pilot report](https://en.wikipedia.org/wiki/Pilot_report) parser
+        ///
+        /// Fields must be in the standard order, but any of them may be omitted.
+        pub rule pirep() -> PirepReport<'input> =
+                    whitespace()
+                    station:(station:$(quiet!{['A'..='Z'] ['A'..='Z' | '0'..='9']*<2,3>} / expected!("station identifier")) whitespace() !"/" { station })?
+                    urgent:report_kind() whitespace()
+                    location:field("OV", <location() ++ (whitespace() "-" whitespace())>)?
+                    time:field("TM", <military_time()>)?
+                    flight_level:field("FL", <flight_level()>)?
+                    aircraft_type:field("TP", <$((!"/" [_])+)>)?
+                    sky_cover:field("SK", <sky_condition() ++ list_separator()>)?
+                    weather:field("WX", <flight_weather()>)?
+                    temperature:field("TA", <temperature()>)?
+                    wind:field("WV", <wind()>)?
+                    turbulence:field("TB", <turbulence() ++ list_separator()>)?
+                    icing:field("IC", <icing() ++ list_separator()>)?
+                    remark:field("RM", <$((!"=" [_])*)>)?
+                    whitespace()
+                    quiet!{"=" [_]*}?
+                    {
+                let (flight_level, flight_phase) = flight_level.unwrap_or((None, None));
+                let (flight_visibility, weather) = weather.unwrap_or((None, vec![]));
+                PirepReport {
+                    station,
+                    urgent,
+                    location: location.unwrap_or_default(),
+                    time,
+                    flight_level,
+                    flight_phase,
+                    aircraft_type: aircraft_type.map(str::trim),
+                    sky_cover: sky_cover.unwrap_or_default(),
+                    flight_visibility,
+                    weather,
+                    temperature,
+                    wind: wind.flatten(),
+                    turbulence: turbulence.unwrap_or_default(),
+                    icing: icing.unwrap_or_default(),
+                    remark: remark.map(str::trim),
+                }
+            }
+
+        rule report_kind() -> bool = quiet!{"UUA" { true } / "UA" { false }} / expected!("report type");
+
+        /// Each field starts with a slash and a two letter name, i.e. `/TM 1522`
+        rule field<T>(name: &'static str, content: rule<T>) -> T = whitespace() "/" whitespace() ##parse_string_literal(name) whitespace() content:content() whitespace() {
+            content
+        }
+
+        rule whitespace() = quiet!{[' ' | '\t' | '\r' | '\n']*};
+        rule list_separator() = whitespace() ("," / ";")? whitespace();
+        rule digit() -> &'input str = quiet!{$(['0'..='9'])} / expected!("digit");
+
+        rule location() -> Location<'input> = fix:$(['A'..='Z'] (!(digit()*<6> ![ '0'..='9']) ['A'..='Z' | '0'..='9'])*) whitespace() offset:(radial:$(digit()*<3>) distance:$(digit()*<3>) { (radial, distance) })? {
+            Location {
+                fix,
+                radial: offset.map(|(radial, _)| Angle::new::<degree>(radial.parse().unwrap())),
+                distance: offset.map(|(_, distance)| Length::new::<nautical_mile>(distance.parse().unwrap())),
+            }
+        }
+
+        rule military_time() -> MilitaryTime = hour:$(digit()*<2>) minute:$(digit()*<2>) {
+            MilitaryTime {
+                hour: hour.parse().unwrap(),
+                minute: minute.parse().unwrap(),
+            }
+        }
+
+        rule flight_level() -> (Option<Length>, Option<FlightPhase>) =
+            phase:$("DURC" / "DURD") { (None, Some(FlightPhase::try_from(phase).unwrap())) }
+            / "UNKN" { (None, None) }
+            / level:hundreds_of_feet() { (Some(level), None) }
+
+        rule hundreds_of_feet() -> Length = hundreds:$(digit()*<3>) {
+            Length::new::<foot>(hundreds.parse::<f64>().unwrap() * 100.)
+        }
+
+        rule sky_condition() -> SkyCondition =
+            cover:$(quiet!{['A'..='Z']*<2,3>} / expected!("cloud coverage")) whitespace() base:$(digit()*<3>)? ("UNKN" / "UNK")? top:(whitespace() "-"? whitespace() "TOP"? whitespace() top:hundreds_of_feet() { top })? {?
+                let cover = match base {
+                    Some(base) => weather_reports::cloud_cover(&format!("{}{}", cover, base)),
+                    None => weather_reports::cloud_cover(cover),
+                };
+                Ok(SkyCondition {
+                    cover: Some(cover.or(Err("cloud coverage"))?.ok_or("cloud coverage")?),
+                    top,
+                })
+            }
+            / "TOP" whitespace() top:hundreds_of_feet() {
+                SkyCondition {
+                    cover: None,
+                    top: Some(top),
+                }
+            }
+
+        /// Flight visibility in statute miles followed by weather phenomena
+        rule flight_weather() -> (Option<Length>, Vec<Weather>) =
+            flight_visibility:("FV" miles:$(digit()+) "SM"? whitespace() { Length::new::<mile>(miles.parse().unwrap()) })?
+            weather:(weather:$((!['/' | ' ' | '='] [_])+) {? weather_reports::weather(weather).or(Err("weather")) }) ** whitespace() {
+                (flight_visibility, weather)
+            }
+
+        rule temperature() -> ThermodynamicTemperature = minus:(quiet!{"M" / "-"} / expected!("minus"))? temperature:$(digit()+) {
+            let temperature: f64 = temperature.parse().unwrap();
+            ThermodynamicTemperature::new::<degree_celsius>(if minus.is_some() { -temperature } else { temperature })
+        }
+
+        /// Always in knots, so the unit may be omitted
+        rule wind() -> Option<Wind> = wind:$(digit()*<3> digit()+ "KT"?) {?
+            if wind.ends_with("KT") {
+                weather_reports::wind(wind)
+            } else {
+                weather_reports::wind(&format!("{}KT", wind))
+            }.or(Err("wind"))
+        }
+
+        rule turbulence() -> Turbulence =
+            frequency:(frequency:frequency() whitespace() { frequency })?
+            intensity:turbulence_intensity() peak_intensity:("-" intensity:turbulence_intensity() { intensity })? whitespace()
+            turbulence_type:(turbulence_type:$("CHOP" / "CAT" / "LLWS") whitespace() { TurbulenceType::try_from(turbulence_type).unwrap() })?
+            altitude:altitude_range()? {
+                Turbulence {
+                    intensity,
+                    peak_intensity,
+                    frequency,
+                    turbulence_type,
+                    altitude,
+                }
+            }
+        rule turbulence_intensity() -> TurbulenceIntensity = intensity:$(quiet!{"NEG" / "SMOOTH" / "SMTH" / "LGT" / "MOD" / "SEV" / "EXTRM" / "EXTM"} / expected!("turbulence intensity")) {
+            TurbulenceIntensity::try_from(intensity).unwrap()
+        }
+        rule frequency() -> Frequency = frequency:$(quiet!{"OCNL" / "INTMT" / "CONS"} / expected!("frequency")) {
+            Frequency::try_from(frequency).unwrap()
+        }
+
+        rule icing() -> Icing =
+            intensity:icing_intensity() peak_intensity:("-" intensity:icing_intensity() { intensity })? whitespace()
+            icing_type:(icing_type:$("RIME" / "CLR" / "MXD" / "MX") whitespace() { IcingType::try_from(icing_type).unwrap() })?
+            altitude:altitude_range()? {
+                Icing {
+                    intensity,
+                    peak_intensity,
+                    icing_type,
+                    altitude,
+                }
+            }
+        rule icing_intensity() -> IcingIntensity = intensity:$(quiet!{"NEG" / "TRACE" / "TRC" / "LGT" / "MOD" / "SEV"} / expected!("icing intensity")) {
+            IcingIntensity::try_from(intensity).unwrap()
+        }
+
+        rule altitude_range() -> AltitudeRange =
+            ("BLO" / "BLW") whitespace() upper:hundreds_of_feet() { AltitudeRange { lower: None, upper: Some(upper) } }
+            / ("ABV" / "ABOVE") whitespace() lower:hundreds_of_feet() { AltitudeRange { lower: Some(lower), upper: None } }
+            / lower:hundreds_of_feet() whitespace() "-" whitespace() upper:hundreds_of_feet() { AltitudeRange { lower: Some(lower), upper: Some(upper) } }
+            / altitude:hundreds_of_feet() { AltitudeRange { lower: Some(altitude), upper: Some(altitude) } }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pirep_reports::*;
+    use crate::tokens::{pirep::*, CloudCoverage};
+    use uom::si::{
+        angle::degree,
+        f64::Length,
+        length::{foot, nautical_mile},
+        thermodynamic_temperature::degree_celsius,
+        velocity::knot,
+    };
+
+    #[test]
+    fn parse_pirep() {
+        let report = pirep("OKC UA /OV OKC063015/TM 1522/FL080/TP C172/SK SCT035-TOP050/WX FV05SM HZ/TA M02/WV 24530KT/TB LGT-MOD CHOP 060-080/IC LGT RIME 080-100/RM SMOOTH BELOW 050").unwrap();
+        assert_eq!(report.station, Some("OKC"));
+        assert!(!report.urgent);
+        assert_eq!(report.location.len(), 1);
+        assert_eq!(report.location[0].fix, "OKC");
+        assert_eq!(report.location[0].radial.unwrap().get::<degree>(), 63.);
+        assert_eq!(
+            report.location[0].distance,
+            Some(Length::new::<nautical_mile>(15.))
+        );
+        assert_eq!(report.time.unwrap().hour, 15);
+        assert_eq!(report.flight_level, Some(Length::new::<foot>(8000.)));
+        assert_eq!(report.aircraft_type, Some("C172"));
+        assert_eq!(report.sky_cover.len(), 1);
+        assert_eq!(
+            report.sky_cover[0].cover.unwrap().coverage,
+            CloudCoverage::Scattered
+        );
+        assert_eq!(report.sky_cover[0].top, Some(Length::new::<foot>(5000.)));
+        assert_eq!(report.weather.len(), 1);
+        assert_eq!(report.temperature.unwrap().get::<degree_celsius>(), -2.);
+        assert_eq!(
+            report.wind.unwrap().speed.unwrap().get::<knot>().round(),
+            30.
+        );
+
+        let turbulence = report.turbulence[0];
+        assert_eq!(turbulence.intensity, TurbulenceIntensity::Light);
+        assert_eq!(
+            turbulence.peak_intensity,
+            Some(TurbulenceIntensity::Moderate)
+        );
+        assert_eq!(turbulence.turbulence_type, Some(TurbulenceType::Chop));
+        assert_eq!(
+            turbulence.altitude.unwrap().upper,
+            Some(Length::new::<foot>(8000.))
+        );
+
+        let icing = report.icing[0];
+        assert_eq!(icing.intensity, IcingIntensity::Light);
+        assert_eq!(icing.icing_type, Some(IcingType::Rime));
+        assert_eq!(report.remark, Some("SMOOTH BELOW 050"));
+    }
+
+    #[test]
+    fn parse_urgent_pirep() {
+        let report = pirep("UUA /OV DEN-COS/TM 2030/FLDURD/TP B737/TB SEV BLO 100/IC NEG").unwrap();
+        assert!(report.urgent);
+        assert_eq!(report.location.len(), 2);
+        assert_eq!(report.flight_phase, Some(FlightPhase::Descending));
+        assert_eq!(report.turbulence[0].altitude.unwrap().lower, None);
+        assert_eq!(report.icing[0].intensity, IcingIntensity::Negative);
+    }
+}
@@ -0,0 +1,338 @@
+use std::convert::TryFrom;
+use uom::{
+    si::f64::{Length, Pressure},
+    si::pressure::hectopascal,
+};
+
+use super::parser::weather_reports;
+use crate::tokens::{sao::*, *};
+
+peg::parser! {
+    pub grammar sao_reports() for str {
+        /// Legacy [Surface Aviation Observation](https://en.wikipedia.org/wiki/Surface_weather_observation) parser
+        ///
+        /// Groups that are missing, marked with `M`, are absent from the report.
+        pub rule sao() -> SaoReport<'input> =
+                    whitespace()
+                    station:$(quiet!{letter() letter_or_digit()*<2>} / expected!("station identifier")) required_whitespace()
+                    kind:kind() required_whitespace()
+                    time:military_time() required_whitespace()
+                    automatic_station:(station:$("AUTO" digit()?) required_whitespace() { station })?
+                    sky_condition:(sky:sky_condition() required_whitespace() { sky })?
+                    visibility:(visibility:visibility() whitespace() { visibility })?
+                    weather:(weather:sao_weather() whitespace() { weather })?
+                    sea_level_pressure:sea_level_pressure() "/"
+                    air:temperature() "/"
+                    dewpoint:temperature() "/"
+                    wind:wind() "/"
+                    altimeter:altimeter()
+                    remark:("/" remark:$((!"=" [_])*) { remark })?
+                    quiet!{"=" [_]*}?
+                    {
+                let (sky_condition, ceiling) = sky_condition.unwrap_or_default();
+                SaoReport {
+                    station,
+                    kind,
+                    time,
+                    automatic_station,
+                    sky_condition,
+                    ceiling,
+                    visibility: visibility.flatten(),
+                    weather: weather.unwrap_or_default(),
+                    sea_level_pressure,
+                    temperatures: air.map(|air| SaoTemperatures { air, dewpoint }),
+                    wind,
+                    altimeter,
+                    remark: remark.map(str::trim).filter(|remark| !remark.is_empty()),
+                }
+            }
+
+        rule kind() -> SaoKind = kind:$(quiet!{"SA" / "SP" / "RS"} / expected!("report type")) {
+            SaoKind::try_from(kind).unwrap()
+        }
+
+        rule whitespace() = quiet!{[' ' | '\t' | '\r' | '\n']*};
+        rule required_whitespace() = quiet!{[' ' | '\t' | '\r' | '\n']+} / expected!("whitespace");
+        rule digit() -> &'input str = quiet!{$(['0'..='9'])} / expected!("digit");
+        rule letter() -> &'input str = quiet!{$(['A'..='Z'])} / expected!("letter");
+        rule letter_or_digit() -> &'input str = letter() / digit();
+        /// A group that was not reported
+        rule missing() = "M" !['A'..='Z' | '0'..='9' | '+' | '-'];
+
+        rule military_time() -> MilitaryTime = hour:$(digit()*<2>) minute:$(digit()*<2>) {
+            MilitaryTime {
+                hour: hour.parse().unwrap(),
+                minute: minute.parse().unwrap(),
+            }
+        }
+
+        /// Cloud layers and the base of the layer marked as the ceiling
+        rule sky_condition() -> (Vec<CloudCover>, Option<Length>) =
+            missing() { (vec![], None) }
+            / "CLR" { (vec![weather_reports::cloud_cover("CLR").unwrap().unwrap()], None) }
+            / layers:cloud_layer() ++ required_whitespace() {
+                let ceiling = layers.iter().find(|(_, ceiling)| *ceiling).and_then(|(cover, _)| cover.and_then(|cover| cover.base));
+                (layers.into_iter().filter_map(|(cover, _)| cover).collect(), ceiling)
+            }
+        /// Height in hundreds of feet followed by its coverage, where `-X` is a partially obscured sky
+        rule cloud_layer() -> (Option<CloudCover>, bool) =
+            "-X" { (None, false) }
+            / ceiling:$(ceiling_designator())? height:$(digit()+) whitespace() "-"? coverage:$(quiet!{"FEW" / "SCT" / "BKN" / "OVC" / "X"} / expected!("cloud coverage")) {?
+                let coverage = if coverage == "X" { "VV" } else { coverage };
+                let cover = weather_reports::cloud_cover(&format!("{}{:0>3}", coverage, height)).or(Err("cloud layer"))?;
+                Ok((cover, ceiling.is_some()))
+            }
+        /// How the ceiling was determined, i.e. `E` for estimated
+        rule ceiling_designator() = quiet!{['M' | 'E' | 'W' | 'A' | 'B' | 'R' | 'P']} / expected!("ceiling designator");
+
+        /// In statute miles, where a trailing `+` means greater than
+        rule visibility() -> Option<RawVisibility> =
+            missing() { None }
+            / value:$(digit()+ "/" digit()+ / digit()+ (" " digit()+ "/" digit()+)?) above:"+"? &([' ' | '\t' | '\r' | '\n'] / ['A'..='Z']) {?
+                let visibility = weather_reports::visibility(&format!("{}{}SM", if above.is_some() { "P" } else { "" }, value)).or(Err("visibility"))?;
+                Ok(visibility.and_then(|visibility| visibility.prevailing))
+            }
+
+        /// Weather and obstructions to vision, i.e. `R-F` for light rain and fog
+        rule sao_weather() -> Vec<Weather> =
+            missing() { vec![] }
+            / weather:weather() ++ whitespace() { weather }
+        rule weather() -> Weather = code:$(quiet!{
+                "TRW" / "ZR" / "ZL" / "RW" / "SW" / "SG" / "SP" / "IPW" / "IP" / "IC" / "GF" / "IF" / "BS" / "BD" / "BN" / "BY"
+                / "T" / "R" / "L" / "S" / "A" / "F" / "K" / "H" / "D"
+            } / expected!("weather")) intensity:$("--" / "-" / "+")? {?
+            let code = match code {
+                "TRW" => "TSRA",
+                "ZR" => "FZRA",
+                "ZL" => "FZDZ",
+                "RW" => "SHRA",
+                "SW" => "SHSN",
+                "SP" => "GS",
+                "IPW" => "SHPL",
+                "IP" => "PL",
+                "GF" => "MIFG",
+                "IF" => "FZFG",
+                "BS" => "BLSN",
+                "BD" => "BLDU",
+                "BN" => "BLSA",
+                "BY" => "BLPY",
+                "T" => "TS",
+                "R" => "RA",
+                "L" => "DZ",
+                "S" => "SN",
+                "A" => "GR",
+                "F" => "BR",
+                "K" => "FU",
+                "H" => "HZ",
+                "D" => "DU",
+                other => other,
+            };
+            let intensity = match intensity {
+                Some("--" | "-") => "-",
+                Some(_) => "+",
+                None => "",
+            };
+            weather_reports::weather(&format!("{}{}", intensity, code)).or(Err("weather"))
+        }
+
+        /// Tens, units and tenths of hectopascals
+        rule sea_level_pressure() -> Option<Pressure> =
+            missing() { None }
+            / pressure:$(digit()*<3>) {
+                let tenths: f64 = pressure.parse().unwrap();
+                let hundreds = if tenths < 500. { 1000. } else { 900. };
+                Some(Pressure::new::<hectopascal>(hundreds + tenths / 10.))
+            }
+            / { None }
+
+        /// Whole degrees, Celsius in Canada and Fahrenheit in the United States
+        rule temperature() -> Option<i32> =
+            missing() { None }
+            / temperature:$("-"? digit()+) {?
+                temperature.parse().map(Some).or(Err("temperature"))
+            }
+            / { None }
+
+        /// Direction in tens of degrees, speed and gusts in knots, i.e. `2315G22` or `1905+18`
+        ///
+        /// Absent if the direction or speed is missing, as a missing direction would otherwise read as variable.
+        rule wind() -> Option<Wind> =
+            direction:$(digit()*<2>) speed:$(digit()*<2,3>) gust:(("+" / "G") gust:$(digit()*<2,3>) { gust })? {?
+                let gust = gust.map(|gust| format!("G{}", gust)).unwrap_or_default();
+                weather_reports::wind(&format!("{}0{}{}KT", direction, speed, gust)).or(Err("wind"))
+            }
+            / (digit()*<2> / "MM") (digit()*<2,3> / "MM") { None }
+            / missing() { None }
+            / { None }
+
+        /// Hundredths of an inch of mercury without the leading 2 or 3, i.e. `990` for 29.90
+        rule altimeter() -> Option<(Pressure, ReportedPressure)> =
+            missing() { None }
+            / altimeter:$(digit()*<3>) {?
+                let leading = if altimeter.starts_with(['5', '6', '7', '8', '9']) { "2" } else { "3" };
+                weather_reports::pressure(&format!("A{}{}", leading, altimeter)).or(Err("altimeter"))
+            }
+            / { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sao_reports::*;
+    use crate::{
+        parse::parser::weather_reports,
+        tokens::{sao::*, *},
+    };
+    use std::convert::TryFrom;
+    use uom::si::{
+        angle::degree,
+        length::foot,
+        pressure::{hectopascal, inch_of_mercury},
+        thermodynamic_temperature::degree_celsius,
+        velocity::knot,
+    };
+
+    #[test]
+    fn parse_automatic_sao() {
+        let report = sao(" WAG SA 0100 AUTO8 M M M 172/09/08/0310+18/M/ PK WND 0219\n                        0035Z 0002 76MM").unwrap();
+        assert_eq!(report.station, "WAG");
+        assert_eq!(report.kind, SaoKind::Record);
+        assert_eq!(report.time, MilitaryTime { hour: 1, minute: 0 });
+        assert_eq!(report.automatic_station, Some("AUTO8"));
+        assert!(report.sky_condition.is_empty());
+        assert_eq!(report.visibility, None);
+        assert!(report.weather.is_empty());
+        assert_eq!(
+            report.sea_level_pressure.unwrap().get::<hectopascal>(),
+            1017.2
+        );
+        assert_eq!(
+            report.temperatures,
+            Some(SaoTemperatures {
+                air: 9,
+                dewpoint: Some(8)
+            })
+        );
+        let wind = report.wind.unwrap();
+        assert_eq!(wind.direction.unwrap().get::<degree>().round(), 30.);
+        assert_eq!(wind.speed.unwrap().get::<knot>().round(), 10.);
+        assert_eq!(wind.peak_gust.unwrap().get::<knot>().round(), 18.);
+        assert_eq!(report.altimeter, None);
+        assert_eq!(
+            report.remark,
+            Some("PK WND 0219\n                        0035Z 0002 76MM")
+        );
+
+        for report in [
+            " TUX SA 0100 AUTO8 M M M 141/21/20/1905/M/0043 PCPN 4.3MM\n                        PAST HR 3003 28MM",
+            " WAF SA 0100 AUTO8 M M M M/12/10/0607/M/ M 67MM",
+            " WDA SA 0100 AUTO8 M M M M/09/M/3306/M/M M 2MMM",
+            " WDC SA 0100 AUTO8 M M M 087/21/11/MM01/M/ 0001 69MM",
+            " WMX SA 0100 AUTO8 M M M M/23/06/27MM/M/ M 47MM",
+            " WLC SA 0100 AUTO8 M M M M/13/M/MMMM/M/ M 0MMM",
+        ] {
+            sao(report).expect(report);
+        }
+    }
+
+    #[test]
+    fn parse_manned_sao() {
+        let report =
+            sao("YQB SP 1815 E12 BKN 25 OVC 2 1/2 R-F 132/-2/-4/2315G22/990/ RAE8").unwrap();
+        assert_eq!(report.kind, SaoKind::Special);
+        assert_eq!(report.automatic_station, None);
+        assert_eq!(report.sky_condition.len(), 2);
+        assert_eq!(report.sky_condition[0].coverage, CloudCoverage::Broken);
+        assert_eq!(report.ceiling.unwrap().get::<foot>().round(), 1200.);
+        assert_eq!(
+            report.visibility.unwrap().reported_distance,
+            ReportedValue::try_from("2 1/2").unwrap()
+        );
+        assert_eq!(
+            report.weather,
+            vec![
+                weather_reports::weather("-RA").unwrap(),
+                weather_reports::weather("BR").unwrap()
+            ]
+        );
+        assert_eq!(report.temperatures.unwrap().air, -2);
+        assert_eq!(
+            (report.altimeter.unwrap().0.get::<inch_of_mercury>() * 100.).round(),
+            2990.
+        );
+        assert_eq!(report.remark, Some("RAE8"));
+
+        let report = sao("YVR SA 2300 W2 X 1/4F 155/M/M/M/M").unwrap();
+        assert_eq!(
+            report.sky_condition[0].coverage,
+            CloudCoverage::VerticalVisibility
+        );
+        assert_eq!(report.ceiling.unwrap().get::<foot>().round(), 200.);
+        assert_eq!(
+            report.weather,
+            vec![weather_reports::weather("BR").unwrap()]
+        );
+        assert_eq!(report.temperatures, None);
+        assert_eq!(report.remark, None);
+    }
+
+    #[test]
+    fn convert_to_metar() {
+        let metar = sao("YUL SA 1200 25 SCT 15+ 181/24/18/2712/012/")
+            .unwrap()
+            .into_metar('C', 30);
+        assert_eq!(metar.identifier, "CYUL");
+        assert_eq!(metar.observation_time.unwrap().day_of_month, 30);
+        assert!(metar.observation_flags.is_empty());
+        assert_eq!(
+            metar.visibility.unwrap().prevailing.unwrap().out_of_range,
+            Some(OutOfRange::Above)
+        );
+        assert_eq!(metar.cloud_cover[0].coverage, CloudCoverage::Scattered);
+        assert_eq!(
+            metar.reported_pressure.unwrap().value,
+            ReportedValue::Decimal {
+                digits: 3012,
+                width: 4,
+                scale: 0
+            }
+        );
+        assert_eq!(metar.remark, None);
+        let temperatures = metar.temperatures.unwrap();
+        assert_eq!(temperatures.air.get::<degree_celsius>(), 24.);
+        assert_eq!(temperatures.dewpoint.unwrap().get::<degree_celsius>(), 18.);
+
+        let metar = sao(" WBT SA 0100 AUTO8 M M M 172/13/11/0101/M/ 6009 32MM")
+            .unwrap()
+            .into_metar('C', 30);
+        assert_eq!(metar.observation_flags, vec![ObservationFlag::Auto]);
+        assert_eq!(metar.remark.as_deref(), Some("6009 32MM"));
+    }
+    #[test]
+    fn convert_us_sao_to_metar() {
+        let report = sao("SEA SA 1950 25 SCT 250 -BKN 7 H 154/64/52/1808/000").unwrap();
+        assert_eq!(
+            report.temperatures,
+            Some(SaoTemperatures {
+                air: 64,
+                dewpoint: Some(52)
+            })
+        );
+        let metar = report.into_metar('K', 30);
+        assert_eq!(metar.identifier, "KSEA");
+        let temperatures = metar.temperatures.unwrap();
+        assert_eq!(temperatures.air.get::<degree_celsius>().round(), 18.);
+        assert_eq!(
+            temperatures
+                .dewpoint
+                .unwrap()
+                .get::<degree_celsius>()
+                .round(),
+            11.
+        );
+        assert_eq!(
+            (metar.pressure.unwrap().get::<inch_of_mercury>() * 100.).round(),
+            3000.
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::convert::TryFrom;
+use uom::{
+    si::angle::degree,
+    si::f64::{Angle, Length},
+    si::length::{foot, kilometer, meter, nautical_mile},
+};
+
+use super::parser::velocity;
+use crate::tokens::{pirep::*, sigmet::*, *};
+
+peg::parser! {
+    pub grammar sigmet_reports() for str {
+        /// [SIGMET](https://en.wikipedia.org/wiki/SIGMET) parser for the ICAO format
+        ///
+        /// The WMO heading (i.e. `WSUK31 EGRR 121000`) is optional.
+        pub rule sigmet() -> SigmetReport<'input> =
+                    whitespace()
+                    (wmo_heading() whitespace())?
+                    air_traffic_services_unit:icao_identifier() whitespace()
+                    "SIGMET" whitespace()
+                    sequence:sequence() whitespace()
+                    "VALID" whitespace() validity:validity() whitespace()
+                    meteorological_watch_office:icao_identifier() "-" whitespace()
+                    fir:icao_identifier() whitespace()
+                    fir_name:$((!fir_type() word() whitespace())* fir_type())
+                    content:(
+                        whitespace() "CNL" whitespace() "SIGMET" whitespace() sequence:sequence() whitespace() validity:validity() {
+                            SigmetContent::Cancellation { sequence, validity }
+                        }
+                        / whitespace() hazard:hazard() { SigmetContent::Hazard(hazard) }
+                    )
+                    whitespace()
+                    quiet!{"=" [_]*}?
+                    {
+                SigmetReport {
+                    air_traffic_services_unit,
+                    sequence,
+                    validity,
+                    meteorological_watch_office,
+                    fir,
+                    fir_name,
+                    content,
+                }
+            }
+
+        /// US [AIRMET](https://en.wikipedia.org/wiki/AIRMET) bulletin parser
+        ///
+        /// The WMO heading (i.e. `WAUS45 KKCI 121445`) is optional.
+        pub rule airmet() -> AirmetBulletin<'input> =
+                    whitespace()
+                    (wmo_heading() whitespace())?
+                    area:$(['A'..='Z']*<3>) ['S' | 'T' | 'Z'] whitespace() "WA" whitespace() issue_time:day_time() whitespace()
+                    "AIRMET" whitespace() series:$("SIERRA" / "TANGO" / "ZULU") whitespace()
+                    update:("UPDT" whitespace() update:$(digit()+) whitespace() { update })?
+                    "FOR" whitespace() hazards:airmet_hazard() ++ (whitespace() "AND" whitespace()) whitespace()
+                    "VALID" whitespace() "UNTIL" whitespace() valid_until:day_time()
+                    blocks:(block_separator() block:airmet_block() { block })*
+                    block_separator()?
+                    whitespace()
+                    quiet!{"=" [_]*}?
+                    {
+                let (airmets, other) = blocks.into_iter().fold((vec![], vec![]), |(mut airmets, mut other), block| {
+                    match block {
+                        Ok(airmet) => airmets.push(airmet),
+                        Err(text) => other.push(text),
+                    }
+                    (airmets, other)
+                });
+                AirmetBulletin {
+                    area,
+                    series: AirmetSeries::try_from(series).unwrap(),
+                    issue_time,
+                    update,
+                    hazards,
+                    valid_until,
+                    airmets,
+                    other,
+                }
+            }
+
+        rule whitespace() = quiet!{[' ' | '\t' | '\r' | '\n']*};
+        rule digit() -> &'input str = quiet!{$(['0'..='9'])} / expected!("digit");
+        rule word() -> &'input str = $((!['=' | ' ' | '\t' | '\r' | '\n'] [_])+);
+
+        rule wmo_heading() = ['A'..='Z']*<4> digit()*<2> whitespace() icao_identifier() whitespace() digit()*<6> (" " ['A'..='Z']*<3>)?;
+        rule icao_identifier() -> &'input str = $(quiet!{['A'..='Z'] ['A'..='Z' | '0'..='9']*<3>} / expected!("ICAO identifier"));
+        rule sequence() -> &'input str = $(quiet!{['A'..='Z' | '0'..='9']+ (" " digit()*<1,2> !['0'..='9' | '/'])?} / expected!("sequence number"));
+        rule fir_type() = ("FIR/UIR" / "FIR" / "UIR" / "CTA") !['A'..='Z'];
+
+        rule day_time() -> DateTime = day_of_month:$(digit()*<2>) time:military_time() {
+            DateTime {
+                day_of_month: day_of_month.parse().unwrap(),
+                time,
+                is_zulu: true,
+            }
+        }
+        rule military_time() -> MilitaryTime = hour:$(digit()*<2>) minute:$(digit()*<2>) {
+            MilitaryTime {
+                hour: hour.parse().unwrap(),
+                minute: minute.parse().unwrap(),
+            }
+        }
+        rule validity() -> TimeRange<DateTime> = begin:day_time() "/" end:day_time() { TimeRange { begin, end } }
+
+        rule hazard() -> Hazard<'input> =
+            phenomenon:phenomenon() whitespace()
+            observed:(
+                "OBS" { true }
+                / "FCST" { false }
+            ) time:(whitespace() "AT" whitespace() time:military_time() "Z" { time })? whitespace()
+            levels_before_area:(levels:levels() whitespace() { levels })?
+            area:area(phenomenon) whitespace()
+            levels:(levels:levels() whitespace() { levels })?
+            movement:(movement:movement() whitespace() { movement })?
+            intensity_change:(change:$("INTSF" / "WKN" / "NC") whitespace() { IntensityChange::try_from(change).unwrap() })?
+            forecast:$("FCST" (!"=" [_])*)? {
+                Hazard {
+                    phenomenon,
+                    observed,
+                    time,
+                    area,
+                    levels: levels.or(levels_before_area),
+                    movement,
+                    intensity_change,
+                    forecast: forecast.map(str::trim_end),
+                }
+            }
+
+        rule phenomenon() -> Phenomenon<'input> =
+            qualifier:$("OBSC" / "EMBD" / "FRQ" / "SQL") whitespace() "TS" hail:"GR"? {
+                Phenomenon::Thunderstorm {
+                    qualifier: ThunderstormQualifier::try_from(qualifier).unwrap(),
+                    hail: hail.is_some(),
+                }
+            }
+            / "SEV" whitespace() "TURB" { Phenomenon::SevereTurbulence }
+            / "SEV" whitespace() "ICE" freezing_rain:(whitespace() "(FZRA)")? { Phenomenon::SevereIcing { freezing_rain: freezing_rain.is_some() } }
+            / "SEV" whitespace() "MTW" { Phenomenon::SevereMountainWave }
+            / "HVY" whitespace() "DS" { Phenomenon::HeavyDuststorm }
+            / "HVY" whitespace() "SS" { Phenomenon::HeavySandstorm }
+            / "RDOACT" whitespace() "CLD" { Phenomenon::RadioactiveCloud }
+            / "VA" (whitespace() "ERUPTION")?
+                volcano:(whitespace() "MT" whitespace() name:$(word() ++ (" " !("PSN" / "VA" / "OBS" / "FCST"))) { name })?
+                position:(whitespace() "PSN" whitespace() position:position() { position })?
+                (whitespace() "VA" whitespace() "CLD")? {
+                Phenomenon::VolcanicAsh { volcano, position }
+            }
+            / "TC" whitespace() name:word()
+                position:(whitespace() "PSN" whitespace() position:position() { position })?
+                (whitespace() "CB")? {
+                Phenomenon::TropicalCyclone { name, position }
+            }
+
+        rule area(phenomenon: Phenomenon<'input>) -> Option<Area> =
+            "ENTIRE" whitespace() ("FIR/UIR" / "FIR" / "UIR" / "CTA") { Some(Area::EntireFir) }
+            / "WI" whitespace() radius:distance() whitespace() "OF" whitespace() ("TC" whitespace())? ("CENTRE" / "CENTER") {
+                match phenomenon {
+                    Phenomenon::TropicalCyclone { position, .. } | Phenomenon::VolcanicAsh { position, .. } => position.map(|centre| Area::Circle { centre, radius }),
+                    _ => None,
+                }
+            }
+            / "WI" whitespace() radius:distance() whitespace() "OF" whitespace() "PSN" whitespace() centre:position() {
+                Some(Area::Circle { centre, radius })
+            }
+            / "WI" whitespace() half_width:distance() whitespace() ("EITHER" whitespace() "SIDE" whitespace())? "OF" whitespace() "LINE" whitespace() line:points() {
+                Some(Area::Corridor { line, half_width })
+            }
+            / "APRX" whitespace() width:distance() whitespace() "WID" whitespace() "LINE" whitespace() "BTN" whitespace() line:points() {
+                Some(Area::Corridor { line, half_width: width / 2. })
+            }
+            / "WI" whitespace() vertices:points() { Some(Area::Polygon(vertices)) }
+            / boundaries:boundary() ++ (whitespace() "AND" whitespace()) { Some(Area::Bounded(boundaries)) }
+
+        rule boundary() -> Boundary =
+            side:compass_direction() whitespace() "OF" whitespace() "LINE" whitespace() points:points() {
+                Boundary::Line { side, points }
+            }
+            / side:compass_direction() whitespace() "OF" whitespace() latitude:latitude() {
+                Boundary::Latitude { side, latitude }
+            }
+            / side:compass_direction() whitespace() "OF" whitespace() longitude:longitude() {
+                Boundary::Longitude { side, longitude }
+            }
+        rule compass_direction() -> CompassDirection = direction:$(quiet!{"NE" / "NW" / "N" / "SE" / "SW" / "S" / "E" / "W"} / expected!("8-point compass direction")) {
+            CompassDirection::try_from(direction).unwrap()
+        }
+
+        rule points() -> Vec<Position> = position() ++ (whitespace() "-" whitespace())
+        rule position() -> Position = latitude:latitude() whitespace() longitude:longitude() { Position { latitude, longitude } }
+        /// Degrees and optionally minutes, i.e. `N5530` or `S10`
+        rule latitude() -> Angle = hemisphere:$(['N' | 'S']) degrees:$(digit()*<2>) minutes:$(digit()*<2>)? !digit() {
+            angle(hemisphere == "S", degrees, minutes)
+        }
+        rule longitude() -> Angle = hemisphere:$(['E' | 'W']) degrees:$(digit()*<3>) minutes:$(digit()*<2>)? !digit() {
+            angle(hemisphere == "W", degrees, minutes)
+        }
+
+        rule distance() -> Length = value:$(digit()+) unit:$("NM" / "KM") {
+            let value = value.parse().unwrap();
+            if unit == "NM" { Length::new::<nautical_mile>(value) } else { Length::new::<kilometer>(value) }
+        }
+
+        rule levels() -> AltitudeRange =
+            "TOP" whitespace() ("ABV" whitespace())? upper:level() { AltitudeRange { lower: None, upper: Some(upper) } }
+            / "ABV" whitespace() lower:level() { AltitudeRange { lower: Some(lower), upper: None } }
+            / "BLW" whitespace() upper:level() { AltitudeRange { lower: None, upper: Some(upper) } }
+            / lower:level() "/" upper:level() { AltitudeRange { lower: Some(lower), upper: Some(upper) } }
+            / level:level() { AltitudeRange { lower: Some(level), upper: Some(level) } }
+        /// The flight level prefix may be omitted after a slash, i.e. `FL250/370`
+        rule level() -> Length =
+            "SFC" { Length::new::<foot>(0.) }
+            / feet:$(digit()+) "FT" { Length::new::<foot>(feet.parse().unwrap()) }
+            / meters:$(digit()+) "M" { Length::new::<meter>(meters.parse().unwrap()) }
+            / "FL"? hundreds:$(digit()*<3>) !digit() { Length::new::<foot>(hundreds.parse::<f64>().unwrap() * 100.) }
+
+        rule movement() -> Movement =
+            "STNR" {
+                Movement {
+                    direction: None,
+                    speed: None,
+                }
+            }
+            / "MOV" whitespace() direction:compass_direction() speed:(whitespace() speed:$(digit()+) unit:$("KT" / "KMH") {?
+                Ok(velocity(ReportedValue::try_from(speed).or(Err("movement speed"))?, SpeedUnit::try_from(unit).unwrap()))
+            })? {
+                Movement {
+                    direction: Some(direction),
+                    speed,
+                }
+            }
+
+        rule airmet_hazard() -> AirmetHazard = hazard:$(quiet!{"IFR" / "MTN OBSCN" / "TURB" / "STG SFC WNDS" / "LLWS" / "ICE" / "FRZLVL"} / expected!("AIRMET hazard")) {
+            AirmetHazard::try_from(hazard).unwrap()
+        }
+
+        /// Blocks are separated by a line with only a period
+        rule block_separator() = [' ' | '\t']* ("\r\n" / "\n") [' ' | '\t']* "." &([' ' | '\t']* ("\r\n" / "\n" / "=" / ![_]));
+        rule block_text() -> &'input str = $((!block_separator() !"=" [_])*);
+
+        rule airmet_block() -> Result<Airmet<'input>, &'input str> =
+            whitespace() "AIRMET" whitespace() hazard:airmet_hazard() "..." states:$(['A'..='Z']*<2>) ++ " " [' ' | '\t']* ("\r\n" / "\n")
+            vertices:(whitespace() "FROM" whitespace() vertices:vertex() ++ (whitespace() "TO" whitespace()) { vertices })?
+            description:block_text() {
+                Ok(Airmet {
+                    hazard,
+                    states,
+                    vertices: vertices.unwrap_or_default(),
+                    description: description.trim(),
+                })
+            }
+            / whitespace() text:block_text() {? if text.trim().is_empty() { Err("AIRMET") } else { Ok(Err(text.trim())) } }
+
+        /// Navigation aid with an optional distance in nautical miles and 16 point compass direction, i.e. `50NNW GEG`
+        rule vertex() -> Location<'input> =
+            distance:$(digit()+) direction:$(['N' | 'E' | 'S' | 'W']*<1, 3>) " " fix:$(['A'..='Z' | '0'..='9']+) {?
+                Ok(Location {
+                    fix,
+                    radial: Some(sixteen_point_bearing(direction).ok_or("compass direction")?),
+                    distance: Some(Length::new::<nautical_mile>(distance.parse().unwrap())),
+                })
+            }
+            / fix:$(['A'..='Z'] ['A'..='Z' | '0'..='9']+) {
+                Location {
+                    fix,
+                    radial: None,
+                    distance: None,
+                }
+            }
+    }
+}
+
+fn angle(negative: bool, degrees: &str, minutes: Option<&str>) -> Angle {
+    let value = degrees.parse::<f64>().unwrap()
+        + minutes
+            .map(|minutes| minutes.parse::<f64>().unwrap() / 60.)
+            .unwrap_or(0.);
+    Angle::new::<degree>(if negative { -value } else { value })
+}
+
+fn sixteen_point_bearing(direction: &str) -> Option<Angle> {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    POINTS
+        .iter()
+        .position(|point| *point == direction)
+        .map(|i| Angle::new::<degree>(i as f64 * 22.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sigmet_reports::*;
+    use crate::tokens::{sigmet::*, CompassDirection, Position};
+    use uom::si::{angle::degree, f64::Angle, f64::Length, length::foot};
+
+    fn position(latitude: f64, longitude: f64) -> Position {
+        Position {
+            latitude: Angle::new::<degree>(latitude),
+            longitude: Angle::new::<degree>(longitude),
+        }
+    }
+
+    #[test]
+    fn parse_sigmet() {
+        let report = sigmet("WSNT01 EGRR 121000\nEGGX SIGMET 3 VALID 121000/121400 EGRR-\nEGGX SHANWICK OCEANIC FIR SEV TURB FCST WI N5500 W01500 - N5500 W02000 - N5300 W02000 - N5300 W01500 - N5500 W01500 FL250/370 MOV E 20KT NC=").unwrap();
+        assert_eq!(report.fir, "EGGX");
+        assert_eq!(report.fir_name, "SHANWICK OCEANIC FIR");
+        assert_eq!(report.validity.end.time.hour, 14);
+        let hazard = match &report.content {
+            SigmetContent::Hazard(hazard) => hazard,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(hazard.phenomenon, Phenomenon::SevereTurbulence);
+        assert_eq!(hazard.phenomenon.kind(), SigmetKind::Weather);
+        assert!(!hazard.observed);
+        assert_eq!(
+            hazard.levels.unwrap().upper,
+            Some(Length::new::<foot>(37000.))
+        );
+        assert_eq!(
+            hazard.movement.unwrap().direction,
+            Some(CompassDirection::East)
+        );
+        assert_eq!(hazard.intensity_change, Some(IntensityChange::NoChange));
+        assert_eq!(report.applies_to(position(54., -17.5)), Some(true));
+        assert_eq!(report.applies_to(position(52., -17.5)), Some(false));
+
+        assert!(sigmet("EGGX SIGMET 3 VALID 121000/121400 EGRR- EGGX SHANWICK OCEANIC FIR SEV TURB FCST WI N5500 W01500 - N5500 W02000 - N5300 W02000 - N5500 W01500 FL250/370 MOV E 99999999999KT NC=").is_err());
+    }
+
+    #[test]
+    fn parse_sigmet_areas() {
+        let report = sigmet("YMMM SIGMET E01 VALID 120300/120700 YPRM- YMMM MELBOURNE FIR EMBD TSGR OBS AT 0250Z N OF S3000 AND W OF E13500 TOP FL450 STNR WKN=").unwrap();
+        assert_eq!(report.sequence, "E01");
+        assert_eq!(report.applies_to(position(-25., 130.)), Some(true));
+        assert_eq!(report.applies_to(position(-25., 140.)), Some(false));
+
+        let report = sigmet("KNHC SIGMET ALFA 2 VALID 121500/122100 KNHC- KZMA MIAMI OCEANIC FIR TC GLORIA PSN N2706 W07306 CB OBS AT 1500Z WI 150NM OF TC CENTRE TOP FL500 NC FCST AT 2100Z TC CENTRE N2740 W07345=").unwrap();
+        assert_eq!(report.sequence, "ALFA 2");
+        assert_eq!(report.applies_to(position(27.5, -73.)), Some(true));
+        match report.content {
+            SigmetContent::Hazard(hazard) => {
+                assert_eq!(hazard.phenomenon.kind(), SigmetKind::TropicalCyclone);
+                assert_eq!(
+                    hazard.forecast,
+                    Some("FCST AT 2100Z TC CENTRE N2740 W07345")
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let report = sigmet("EGGX SIGMET 4 VALID 121200/121400 EGRR- EGGX SHANWICK OCEANIC FIR CNL SIGMET 3 121000/121400=").unwrap();
+        assert!(matches!(
+            report.content,
+            SigmetContent::Cancellation { sequence: "3", .. }
+        ));
+    }
+
+    #[test]
+    fn parse_airmet() {
+        let bulletin = airmet("WAUS45 KKCI 121445\nSLCS WA 121445\nAIRMET SIERRA UPDT 3 FOR IFR AND MTN OBSCN VALID UNTIL 122100\n.\nAIRMET IFR...ID MT WY\nFROM 50NNW GEG TO 40ESE MLP TO 30SW BOI TO 50NNW GEG\nCIG BLW 010/VIS BLW 3SM BR. CONDS CONTG BYD 21Z THRU 03Z.\n.\nOTLK VALID 2100-0300Z...IFR WA OR\nBOUNDED BY 40W YDC-SEA-40W YDC\nCIG BLW 010.\n.").unwrap();
+        assert_eq!(bulletin.area, "SLC");
+        assert_eq!(bulletin.series, AirmetSeries::Sierra);
+        assert_eq!(bulletin.update, Some("3"));
+        assert_eq!(
+            bulletin.hazards,
+            vec![
+                AirmetHazard::InstrumentFlightRules,
+                AirmetHazard::MountainObscuration
+            ]
+        );
+        assert_eq!(bulletin.airmets.len(), 1);
+        assert_eq!(bulletin.other.len(), 1);
+
+        let airmet = &bulletin.airmets[0];
+        assert_eq!(airmet.states, vec!["ID", "MT", "WY"]);
+        assert_eq!(airmet.vertices.len(), 4);
+        assert_eq!(airmet.vertices[0].fix, "GEG");
+        assert_eq!(airmet.vertices[0].radial, Some(Angle::new::<degree>(337.5)));
+        assert!(airmet.description.starts_with("CIG BLW 010"));
+
+        let area = airmet
+            .area(|fix| match fix {
+                "GEG" => Some(position(47.56, -117.63)),
+                "MLP" => Some(position(47.46, -115.65)),
+                "BOI" => Some(position(43.55, -116.19)),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(area.contains(position(46., -116.)), Some(true));
+        assert_eq!(airmet.area(|_| None), None);
+    }
+}
@@ -0,0 +1,791 @@
+use std::convert::TryFrom;
+use uom::{
+    si::angle::degree,
+    si::f64::{Angle, Length, Pressure, ThermodynamicTemperature, Time},
+    si::length::{centimeter, decimeter, kilometer, meter, millimeter},
+    si::pressure::hectopascal,
+    si::thermodynamic_temperature::degree_celsius,
+    si::time::second,
+};
+
+use super::parser::velocity;
+use crate::tokens::{synop::*, *};
+
+peg::parser! {
+    pub grammar synop_reports() for str {
+        /// [SYNOP](https://en.wikipedia.org/wiki/SYNOP) parser for reports from land stations (FM-12) and ships (FM-13)
+        ///
+        /// Section 0 may omit AAXX or BBXX when the report was taken from a bulletin.
+        pub rule synop() -> SynopReport<'input> =
+                    separator()?
+                    section_0:section_0()
+                    nil:(separator() "NIL" group_end())?
+                    indicators:(separator() indicators:indicators() { indicators })?
+                    wind:(separator() total_cloud_cover:code(1) wind:wind(section_0.2) { (total_cloud_cover, wind) })?
+                    air:(separator() "1" temperature:signed_temperature() { temperature })?
+                    dewpoint:(separator() "2" temperature:signed_temperature() { temperature })?
+                    relative_humidity:(separator() "29" humidity:code(3) group_end() { humidity })?
+                    station_pressure:(separator() "3" pressure:pressure() { pressure })?
+                    sea_level_pressure:(separator() "4" !['1' | '2' | '5' | '7' | '8'] pressure:pressure() { pressure })?
+                    geopotential_height:(separator() "4" height:geopotential_height() { height })?
+                    pressure_tendency:(separator() "5" tendency:pressure_tendency() { tendency })?
+                    precipitation:(separator() "6" precipitation:precipitation() { precipitation })?
+                    weather:(separator() "7" present:code(2) past_1:code(1) past_2:code(1) group_end() { (present, past_1, past_2) })?
+                    clouds:(separator() "8" clouds:cloud_types() { clouds })?
+                    exact_observation_time:(separator() "9" time:military_time() group_end() { time })?
+                    section_2:(separator() "222" ship_direction:code(1) ship_speed:code(1) group_end() groups:(separator() group:ship_maritime_group() { group })* {
+                        maritime_data(ship_direction, ship_speed, groups)
+                    })?
+                    section_3:(separator() "333" group_end() data:climatological_data() { data })?
+                    section_4:(separator() "444" group_end() groups:raw_groups() { groups })?
+                    section_5:(separator() "555" group_end() groups:raw_groups() { groups })?
+                    separator()?
+                    // Reports are terminated with =
+                    quiet!{"=" [_]*}? separator()?
+                    {
+                let (station, observation_time, wind_indicator, position) = section_0;
+                let (precipitation_indicator, station_type, lowest_cloud_base, visibility) =
+                    indicators.unwrap_or((None, None, None, None));
+                let (total_cloud_cover, wind) = wind.unwrap_or((None, None));
+                let (present_weather, past_weather_1, past_weather_2) = weather.unwrap_or((None, None, None));
+                SynopReport {
+                    observation_time,
+                    wind_indicator,
+                    station,
+                    position,
+                    nil: nil.is_some(),
+                    precipitation_indicator,
+                    station_type,
+                    lowest_cloud_base,
+                    visibility,
+                    total_cloud_cover: total_cloud_cover.map(|oktas| oktas as u8),
+                    wind,
+                    temperatures: air.flatten().map(|air| Temperatures {
+                        air,
+                        dewpoint: dewpoint.flatten(),
+                    }),
+                    relative_humidity: relative_humidity.flatten().map(|humidity| humidity as u8),
+                    station_pressure: station_pressure.flatten(),
+                    sea_level_pressure: sea_level_pressure.flatten(),
+                    geopotential_height: geopotential_height.flatten(),
+                    pressure_tendency,
+                    precipitation,
+                    present_weather: present_weather.map(|code| code as u8),
+                    past_weather: (past_weather_1.map(|code| code as u8), past_weather_2.map(|code| code as u8)),
+                    clouds,
+                    exact_observation_time,
+                    section_2,
+                    section_3,
+                    section_4,
+                    section_5,
+                }
+            }
+
+        /// [Buoy](https://en.wikipedia.org/wiki/Weather_buoy) parser (FM-18)
+        pub rule buoy() -> BuoyReport<'input> =
+                    separator()?
+                    ("ZZYY" separator())?
+                    station:$(quiet!{['0'..='9']*<5>} / expected!("buoy identifier")) group_end() separator()
+                    day_of_month:$(digit()*<2>) month:$(digit()*<2>) year_digit:digit() group_end() separator()
+                    time:military_time() wind_indicator:wind_indicator() group_end() separator()
+                    position:buoy_position()
+                    // Quality of the position and time
+                    (separator() "6" code(4) group_end())?
+                    section_1:(separator() "111" code(2) group_end()
+                        wind:(separator() "0" wind:wind(wind_indicator) { wind })?
+                        air:(separator() "1" temperature:signed_temperature() { temperature })?
+                        dewpoint:(separator() "2" temperature:signed_temperature() { temperature })?
+                        relative_humidity:(separator() "29" humidity:code(3) group_end() { humidity })?
+                        station_pressure:(separator() "3" pressure:pressure() { pressure })?
+                        sea_level_pressure:(separator() "4" pressure:pressure() { pressure })?
+                        pressure_tendency:(separator() "5" tendency:pressure_tendency() { tendency })? {
+                            (wind.flatten(), air.flatten(), dewpoint.flatten(), relative_humidity.flatten(), station_pressure.flatten(), sea_level_pressure.flatten(), pressure_tendency)
+                        }
+                    )?
+                    section_2:(separator() "222" code(2) group_end() groups:(separator() group:buoy_maritime_group() { group })* {
+                        maritime_data(None, None, groups)
+                    })?
+                    section_3:(separator() "333" code(2) group_end() groups:raw_groups() { groups })?
+                    section_4:(separator() "444" group_end() groups:raw_groups() { groups })?
+                    separator()?
+                    quiet!{"=" [_]*}? separator()?
+                    {
+                let (wind, air, dewpoint, relative_humidity, station_pressure, sea_level_pressure, pressure_tendency) =
+                    section_1.unwrap_or((None, None, None, None, None, None, None));
+                BuoyReport {
+                    station,
+                    observation_time: DateTime {
+                        day_of_month: day_of_month.parse().unwrap(),
+                        time,
+                        is_zulu: true,
+                    },
+                    month: month.parse().unwrap(),
+                    year_digit: year_digit.parse().unwrap(),
+                    wind_indicator,
+                    position,
+                    wind,
+                    temperatures: air.map(|air| Temperatures { air, dewpoint }),
+                    relative_humidity: relative_humidity.map(|humidity| humidity as u8),
+                    station_pressure,
+                    sea_level_pressure,
+                    pressure_tendency,
+                    section_2,
+                    section_3,
+                    section_4,
+                }
+            }
+
+        rule section_0() -> (&'input str, DateTime, Option<WindIndicator>, Option<Position>) =
+            ("BBXX" separator())? !"AAXX" station:$(quiet!{['A'..='Z' | '0'..='9']+} / expected!("call sign")) separator()
+                observation_time:synop_time() wind_indicator:wind_indicator() group_end() separator()
+                position:ship_position() {
+                (station, observation_time, wind_indicator, position)
+            }
+            / ("AAXX" separator())?
+                observation_time:synop_time() wind_indicator:wind_indicator() group_end() separator()
+                station:$(quiet!{['0'..='9']*<5>} / expected!("station number")) group_end() {
+                (station, observation_time, wind_indicator, None)
+            }
+
+        /// Tenths of a degree in two groups, 99LaLaLa QcLoLoLoLo
+        rule ship_position() -> Option<Position> = "99" latitude:code(3) group_end() separator() quadrant:quadrant() longitude:code(4) group_end() {
+            position(quadrant, latitude? as f64 / 10., longitude? as f64 / 10.)
+        }
+        /// Thousandths of a degree in two groups, QcLaLaLaLaLa LoLoLoLoLoLo
+        rule buoy_position() -> Option<Position> = quadrant:quadrant() latitude:wide_code(5) group_end() separator() longitude:wide_code(6) group_end() {
+            position(quadrant, latitude? as f64 / 1000., longitude? as f64 / 1000.)
+        }
+        rule quadrant() -> Option<u16> = quadrant:code(1) {? match quadrant {
+            Some(1 | 3 | 5 | 7) | None => Ok(quadrant),
+            _ => Err("quadrant of the globe"),
+        } }
+
+        rule ship_maritime_group() -> MaritimeGroup<'input> =
+            "0" temperature:sea_surface_temperature() { MaritimeGroup::SeaSurfaceTemperature(temperature) }
+            / "1" waves:waves() { MaritimeGroup::Waves(waves) }
+            / "2" waves:waves() { MaritimeGroup::WindWaves(waves) }
+            / "3" first_direction:code(2) second_direction:code(2) group_end() { MaritimeGroup::SwellDirections(first_direction, second_direction) }
+            / "4" waves:waves() { MaritimeGroup::FirstSwell(waves) }
+            / "5" waves:waves() { MaritimeGroup::SecondSwell(waves) }
+            / "6" source:code(1) thickness:code(2) rate:code(1) group_end() {
+                MaritimeGroup::IceAccretion(IceAccretion {
+                    source: source.map(|source| source as u8),
+                    thickness: thickness.map(|thickness| Length::new::<centimeter>(thickness as f64)),
+                    rate: rate.map(|rate| rate as u8),
+                })
+            }
+            / "70" height:code(3) group_end() { MaritimeGroup::WaveHeight(height) }
+            / "8" sign:code(1) tenths:code(3) group_end() {
+                MaritimeGroup::WetBulbTemperature(sign.zip(tenths).map(|(sign, tenths)| {
+                    // Code table 3855, where 2 and 7 are measured on an iced bulb
+                    ThermodynamicTemperature::new::<degree_celsius>(if matches!(sign, 1 | 2 | 6 | 7) { -(tenths as f64) } else { tenths as f64 } / 10.)
+                }))
+            }
+            // Plain language or coded ice conditions, always the last groups in the section
+            / ice:$("ICE" group_end() (separator() raw_group())*) { MaritimeGroup::Other(ice) }
+            / group:raw_group() { MaritimeGroup::Other(group) }
+        rule buoy_maritime_group() -> MaritimeGroup<'input> =
+            "0" temperature:sea_surface_temperature() { MaritimeGroup::SeaSurfaceTemperature(temperature) }
+            / "1" waves:waves() { MaritimeGroup::Waves(waves) }
+            / "20" period:code(3) group_end() { MaritimeGroup::WavePeriod(period) }
+            / "21" height:code(3) group_end() { MaritimeGroup::WaveHeight(height) }
+            / group:raw_group() { MaritimeGroup::Other(group) }
+
+        /// The sign is the parity of the indicator, which may also give the method of measurement
+        rule sea_surface_temperature() -> Option<ThermodynamicTemperature> = sign:code(1) tenths:code(3) group_end() {
+            let tenths = tenths? as f64;
+            Some(ThermodynamicTemperature::new::<degree_celsius>(if sign? % 2 == 1 { -tenths } else { tenths } / 10.))
+        }
+        /// Period in seconds and height in half meters
+        rule waves() -> Waves = period:code(2) height:code(2) group_end() {
+            Waves {
+                direction: None,
+                // 99 is a confused sea
+                period: period.filter(|period| *period != 99).map(|period| Time::new::<second>(period as f64)),
+                height: height.map(|height| Length::new::<meter>(height as f64 / 2.)),
+            }
+        }
+
+        rule separator() = quiet!{[' ' | '\t' | '\r' | '\n']+} / expected!("whitespace");
+        rule group_end() = &(separator() / "=" / ![_]);
+        rule digit() -> &'input str = quiet!{$(['0'..='9'])} / expected!("digit");
+
+        /// A fixed width code of up to 4 digits where any `/` means it was not reported
+        rule code(width: usize) -> Option<u16> = value:wide_code(width) { value.map(|value| value as u16) }
+        /// [code] for the 5 and 6 digit fields of buoy positions
+        rule wide_code(width: usize) -> Option<u32> = digits:$(quiet!{['0'..='9' | '/']*<{width}>} / expected!("code figure")) {
+            if digits.contains('/') {
+                None
+            } else {
+                Some(digits.parse().unwrap())
+            }
+        }
+
+        rule synop_time() -> DateTime = day_of_month:$(digit()*<2>) hour:$(digit()*<2>) {
+            DateTime {
+                day_of_month: day_of_month.parse().unwrap(),
+                time: MilitaryTime {
+                    hour: hour.parse().unwrap(),
+                    minute: 0,
+                },
+                is_zulu: true,
+            }
+        }
+        rule military_time() -> MilitaryTime = hour:$(digit()*<2>) minute:$(digit()*<2>) {
+            MilitaryTime {
+                hour: hour.parse().unwrap(),
+                minute: minute.parse().unwrap(),
+            }
+        }
+
+        rule wind_indicator() -> Option<WindIndicator> = indicator:$(quiet!{['0' | '1' | '3' | '4' | '/']} / expected!("wind speed indicator")) {
+            match indicator {
+                "0" => Some(WindIndicator::EstimatedMetersPerSecond),
+                "1" => Some(WindIndicator::MeasuredMetersPerSecond),
+                "3" => Some(WindIndicator::EstimatedKnots),
+                "4" => Some(WindIndicator::MeasuredKnots),
+                _ => None,
+            }
+        }
+
+        rule indicators() -> (Option<PrecipitationIndicator>, Option<StationType>, Option<HeightRange>, Option<RawVisibility>) =
+            precipitation_indicator:code(1) station_type:code(1) lowest_cloud_base:code(1) visibility:code(2) group_end() {
+                (
+                    precipitation_indicator.and_then(|indicator| match indicator {
+                        0 => Some(PrecipitationIndicator::InSections1And3),
+                        1 => Some(PrecipitationIndicator::InSection1),
+                        2 => Some(PrecipitationIndicator::InSection3),
+                        3 => Some(PrecipitationIndicator::OmittedNone),
+                        4 => Some(PrecipitationIndicator::OmittedUnavailable),
+                        _ => None,
+                    }),
+                    station_type.filter(|station_type| (1..=7).contains(station_type)).map(|station_type| StationType {
+                        manned: station_type <= 3,
+                        weather_included: matches!(station_type, 1 | 4 | 7),
+                    }),
+                    lowest_cloud_base.map(cloud_base_range),
+                    visibility.and_then(synop_visibility),
+                )
+            }
+
+        rule wind(wind_indicator: Option<WindIndicator>) -> Option<Wind> =
+            direction:code(2) speed:code(2) group_end() extended_speed:(separator() "00" speed:$(digit()*<3>) group_end() { speed })? {
+                let unit = match wind_indicator {
+                    Some(WindIndicator::EstimatedKnots) | Some(WindIndicator::MeasuredKnots) => SpeedUnit::Knots,
+                    _ => SpeedUnit::MetersPerSecond,
+                };
+                let reported_speed = match (speed, extended_speed) {
+                    (Some(99), Some(speed)) => Some(ReportedValue::try_from(speed).unwrap()),
+                    (speed, _) => speed.map(|speed| ReportedValue::Decimal { digits: speed as u32, width: 2, scale: 0 }),
+                };
+                if direction.is_none() && reported_speed.is_none() {
+                    return None;
+                }
+                Some(Wind {
+                    // 99 is variable
+                    direction: direction.filter(|direction| *direction != 99).map(|direction| Angle::new::<degree>(direction as f64 * 10.)),
+                    speed: reported_speed.map(|speed| velocity(speed, unit)),
+                    peak_gust: None,
+                    variance: None,
+                    unit,
+                    reported_speed,
+                    reported_peak_gust: None,
+                })
+            }
+
+        /// Sign followed by tenths of a degree
+        rule signed_temperature() -> Option<ThermodynamicTemperature> = sign:$(['0' | '1' | '/']) tenths:code(3) group_end() {
+            let tenths = tenths? as f64;
+            Some(ThermodynamicTemperature::new::<degree_celsius>(match sign {
+                "0" => tenths / 10.,
+                "1" => -tenths / 10.,
+                _ => return None,
+            }))
+        }
+
+        /// Tenths of a hectopascal, omitting the thousands digit
+        rule pressure() -> Option<Pressure> = tenths:code(4) group_end() {
+            let tenths = tenths? as f64;
+            Some(Pressure::new::<hectopascal>(if tenths < 1000. { tenths + 10000. } else { tenths } / 10.))
+        }
+
+        rule geopotential_height() -> Option<GeopotentialHeight> = surface:$(['1' | '2' | '5' | '7' | '8']) height:code(3) group_end() {
+            let height = height? as f64;
+            let (surface, height) = match surface {
+                // Heights below sea level are reported as 500 plus the magnitude
+                "1" => (1000., if height >= 500. { 500. - height } else { height }),
+                "2" => (925., height),
+                "5" => (500., 5000. + height),
+                "7" => (700., if height < 500. { 3000. + height } else { 2000. + height }),
+                _ => (850., 1000. + height),
+            };
+            Some(GeopotentialHeight {
+                surface: Pressure::new::<hectopascal>(surface),
+                height: Length::new::<meter>(height),
+            })
+        }
+
+        rule pressure_tendency() -> PressureTendency = characteristic:code(1) change:code(3) group_end() {
+            let change = change.map(|tenths| Pressure::new::<hectopascal>(tenths as f64 / 10.));
+            PressureTendency {
+                characteristic: characteristic.map(|characteristic| characteristic as u8),
+                change: match characteristic {
+                    // 5 through 8 are decreasing, 4 is steady
+                    Some(5..=8) => change.map(|change| -change),
+                    Some(4) => Some(Pressure::new::<hectopascal>(0.)),
+                    _ => change,
+                },
+            }
+        }
+
+        rule precipitation() -> PrecipitationAmount = amount:code(3) duration:code(1) group_end() {
+            PrecipitationAmount {
+                amount: amount.map(|amount| Length::new::<millimeter>(match amount {
+                    990 => 0.,
+                    991..=999 => (amount - 990) as f64 / 10.,
+                    _ => amount as f64,
+                })),
+                trace: amount == Some(990),
+                period_hours: duration.and_then(|duration| match duration {
+                    1 => Some(6),
+                    2 => Some(12),
+                    3 => Some(18),
+                    4 => Some(24),
+                    5 => Some(1),
+                    6 => Some(2),
+                    7 => Some(3),
+                    8 => Some(9),
+                    9 => Some(15),
+                    _ => None,
+                }),
+            }
+        }
+
+        rule cloud_types() -> CloudTypes = amount:code(1) low:code(1) middle:code(1) high:code(1) group_end() {
+            CloudTypes {
+                amount: amount.map(|amount| amount as u8),
+                low: low.map(|low| low as u8),
+                middle: middle.map(|middle| middle as u8),
+                high: high.map(|high| high as u8),
+            }
+        }
+
+        rule climatological_data() -> ClimatologicalData<'input> = groups:(separator() group:climatological_group() { group })* {
+            let mut data = ClimatologicalData {
+                maximum_temperature: None,
+                minimum_temperature: None,
+                precipitation: None,
+                precipitation_24_hours: None,
+                cloud_layers: vec![],
+                other_groups: vec![],
+            };
+            for group in groups {
+                match group {
+                    ClimatologicalGroup::MaximumTemperature(temperature) => data.maximum_temperature = temperature,
+                    ClimatologicalGroup::MinimumTemperature(temperature) => data.minimum_temperature = temperature,
+                    ClimatologicalGroup::Precipitation(precipitation) => data.precipitation = Some(precipitation),
+                    ClimatologicalGroup::Precipitation24Hours(precipitation) => data.precipitation_24_hours = Some(precipitation),
+                    ClimatologicalGroup::CloudLayer(layer) => data.cloud_layers.push(layer),
+                    ClimatologicalGroup::Other(group) => data.other_groups.push(group),
+                }
+            }
+            data
+        }
+        rule climatological_group() -> ClimatologicalGroup<'input> =
+            "1" temperature:signed_temperature() { ClimatologicalGroup::MaximumTemperature(temperature) }
+            / "2" temperature:signed_temperature() { ClimatologicalGroup::MinimumTemperature(temperature) }
+            / "6" precipitation:precipitation() { ClimatologicalGroup::Precipitation(precipitation) }
+            / "7" amount:code(4) group_end() {
+                ClimatologicalGroup::Precipitation24Hours(PrecipitationAmount {
+                    // Tenths of a millimeter, where 9999 is a trace
+                    amount: amount.map(|amount| Length::new::<millimeter>(if amount == 9999 { 0. } else { amount as f64 / 10. })),
+                    trace: amount == Some(9999),
+                    period_hours: Some(24),
+                })
+            }
+            / "8" amount:code(1) genus:code(1) base:code(2) group_end() {
+                ClimatologicalGroup::CloudLayer(CloudLayer {
+                    amount: amount.map(|amount| amount as u8),
+                    genus: genus.map(|genus| genus as u8),
+                    base: base.and_then(cloud_layer_base),
+                })
+            }
+            / group:$(['0'..='9' | '/']*<5>) group_end() { ClimatologicalGroup::Other(group) }
+
+        /// Groups up to the next section or the end of the report
+        rule raw_groups() -> Vec<&'input str> = groups:(separator() group:raw_group() { group })* {
+            groups
+        }
+        rule raw_group() -> &'input str = !(("222" / "333" / "444" / "555") group_end()) group:$((!['=' | ' ' | '\t' | '\r' | '\n'] [_])+) { group }
+    }
+}
+
+enum ClimatologicalGroup<'input> {
+    MaximumTemperature(Option<ThermodynamicTemperature>),
+    MinimumTemperature(Option<ThermodynamicTemperature>),
+    Precipitation(PrecipitationAmount),
+    Precipitation24Hours(PrecipitationAmount),
+    CloudLayer(CloudLayer),
+    Other(&'input str),
+}
+
+enum MaritimeGroup<'input> {
+    SeaSurfaceTemperature(Option<ThermodynamicTemperature>),
+    Waves(Waves),
+    WindWaves(Waves),
+    SwellDirections(Option<u16>, Option<u16>),
+    FirstSwell(Waves),
+    SecondSwell(Waves),
+    IceAccretion(IceAccretion),
+    /// Tenths of a meter, more precise than [MaritimeGroup::Waves]
+    WaveHeight(Option<u16>),
+    /// Tenths of a second, more precise than [MaritimeGroup::Waves]
+    WavePeriod(Option<u16>),
+    WetBulbTemperature(Option<ThermodynamicTemperature>),
+    Other(&'input str),
+}
+
+fn maritime_data<'input>(
+    ship_direction: Option<u16>,
+    ship_speed: Option<u16>,
+    groups: Vec<MaritimeGroup<'input>>,
+) -> MaritimeData<'input> {
+    let mut data = MaritimeData {
+        ship_direction: ship_direction.map(|direction| direction as u8),
+        ship_speed: ship_speed.map(|speed| speed as u8),
+        sea_surface_temperature: None,
+        waves: None,
+        wind_waves: None,
+        swells: vec![],
+        ice_accretion: None,
+        wet_bulb_temperature: None,
+        other_groups: vec![],
+    };
+    let (mut swell_directions, mut first_swell, mut second_swell) = ((None, None), None, None);
+    let mut precise_height = None;
+    let mut precise_period = None;
+    for group in groups {
+        match group {
+            MaritimeGroup::SeaSurfaceTemperature(temperature) => {
+                data.sea_surface_temperature = temperature
+            }
+            MaritimeGroup::Waves(waves) => data.waves = Some(waves),
+            MaritimeGroup::WindWaves(waves) => data.wind_waves = Some(waves),
+            MaritimeGroup::SwellDirections(first_direction, second_direction) => {
+                swell_directions = (first_direction, second_direction)
+            }
+            MaritimeGroup::FirstSwell(waves) => first_swell = Some(waves),
+            MaritimeGroup::SecondSwell(waves) => second_swell = Some(waves),
+            MaritimeGroup::IceAccretion(ice_accretion) => data.ice_accretion = Some(ice_accretion),
+            MaritimeGroup::WaveHeight(height) => precise_height = height,
+            MaritimeGroup::WavePeriod(period) => precise_period = period,
+            MaritimeGroup::WetBulbTemperature(temperature) => {
+                data.wet_bulb_temperature = temperature
+            }
+            MaritimeGroup::Other(group) => data.other_groups.push(group),
+        }
+    }
+
+    if precise_height.is_some() || precise_period.is_some() {
+        let waves = data.waves.get_or_insert(Waves {
+            direction: None,
+            period: None,
+            height: None,
+        });
+        if let Some(height) = precise_height {
+            waves.height = Some(Length::new::<decimeter>(height as f64));
+        }
+        if let Some(period) = precise_period {
+            waves.period = Some(Time::new::<second>(period as f64 / 10.));
+        }
+    }
+
+    // Direction is in tens of degrees, where 00 is no swell and 99 is confused
+    let swell_direction = |direction: Option<u16>| {
+        direction
+            .filter(|direction| (1..=36).contains(direction))
+            .map(|direction| Angle::new::<degree>(direction as f64 * 10.))
+    };
+    for (swell, direction) in [
+        (first_swell, swell_directions.0),
+        (second_swell, swell_directions.1),
+    ] {
+        if let Some(mut swell) = swell {
+            swell.direction = swell_direction(direction);
+            data.swells.push(swell);
+        }
+    }
+    data
+}
+
+/// Code table 3333
+fn position(quadrant: Option<u16>, latitude: f64, longitude: f64) -> Option<Position> {
+    let (latitude_sign, longitude_sign) = match quadrant? {
+        1 => (1., 1.),
+        3 => (-1., 1.),
+        5 => (-1., -1.),
+        _ => (1., -1.),
+    };
+    Some(Position {
+        latitude: Angle::new::<degree>(latitude * latitude_sign),
+        longitude: Angle::new::<degree>(longitude * longitude_sign),
+    })
+}
+
+/// Code table 1600
+fn cloud_base_range(code: u16) -> HeightRange {
+    let bounds = [0., 50., 100., 200., 300., 600., 1000., 1500., 2000., 2500.];
+    let code = (code as usize).min(9);
+    HeightRange {
+        lower: Length::new::<meter>(bounds[code]),
+        upper: bounds
+            .get(code + 1)
+            .map(|upper| Length::new::<meter>(*upper)),
+    }
+}
+
+/// Code table 1677
+fn cloud_layer_base(code: u16) -> Option<HeightRange> {
+    let exact = |height: f64| {
+        Some(HeightRange {
+            lower: Length::new::<meter>(height),
+            upper: Some(Length::new::<meter>(height)),
+        })
+    };
+    match code {
+        0 => Some(HeightRange {
+            lower: Length::new::<meter>(0.),
+            upper: Some(Length::new::<meter>(30.)),
+        }),
+        1..=50 => exact(code as f64 * 30.),
+        56..=80 => exact((code - 50) as f64 * 300.),
+        81..=88 => exact((code - 80) as f64 * 1500. + 9000.),
+        89 => Some(HeightRange {
+            lower: Length::new::<meter>(21000.),
+            upper: None,
+        }),
+        90..=99 => Some(cloud_base_range(code - 90)),
+        _ => None,
+    }
+}
+
+/// Code table 4377
+fn synop_visibility(code: u16) -> Option<RawVisibility> {
+    let (out_of_range, digits, scale) = match code {
+        0 => (Some(OutOfRange::Below), 1, 1),
+        1..=50 => (None, code as u32, 1),
+        56..=80 => (None, (code - 50) as u32, 0),
+        81..=88 => (None, (code - 80) as u32 * 5 + 30, 0),
+        89 => (Some(OutOfRange::Above), 70, 0),
+        90 => (Some(OutOfRange::Below), 5, 2),
+        91 => (None, 5, 2),
+        92 => (None, 2, 1),
+        93 => (None, 5, 1),
+        94 => (None, 1, 0),
+        95 => (None, 2, 0),
+        96 => (None, 4, 0),
+        97 => (None, 10, 0),
+        98 => (None, 20, 0),
+        99 => (Some(OutOfRange::Above), 50, 0),
+        _ => return None,
+    };
+    let reported_distance = ReportedValue::Decimal {
+        digits,
+        width: digits.to_string().len() as u8,
+        scale,
+    };
+    Some(RawVisibility {
+        out_of_range,
+        distance: Length::new::<kilometer>(f64::from(reported_distance)),
+        unit: Some(DistanceUnit::Kilometers),
+        reported_distance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::synop_reports::*;
+    use crate::tokens::{synop::*, WaterSurfaceState};
+    use uom::si::{
+        angle::degree,
+        f64::{Length, Pressure, Time},
+        length::{decimeter, kilometer, meter, millimeter},
+        pressure::hectopascal,
+        thermodynamic_temperature::degree_celsius,
+        time::second,
+        velocity::knot,
+    };
+
+    #[test]
+    fn parse_synop() {
+        let report = synop("AAXX 01124 03772 21675 71212 10095 20048 30138 40233 52010 60011 71022 84502 333 10132 20061 70015 84625 555 10/// 29113=").unwrap();
+        assert_eq!(report.station, "03772");
+        assert_eq!(report.observation_time.day_of_month, 1);
+        assert_eq!(report.observation_time.time.hour, 12);
+        assert_eq!(report.wind_indicator, Some(WindIndicator::MeasuredKnots));
+        assert_eq!(
+            report.precipitation_indicator,
+            Some(PrecipitationIndicator::InSection3)
+        );
+        assert_eq!(
+            report.station_type,
+            Some(StationType {
+                manned: true,
+                weather_included: true
+            })
+        );
+        assert_eq!(
+            report.lowest_cloud_base.unwrap().lower,
+            Length::new::<meter>(1000.)
+        );
+        assert_eq!(
+            report.visibility.unwrap().distance,
+            Length::new::<kilometer>(25.)
+        );
+        assert_eq!(report.total_cloud_cover, Some(7));
+        let wind = report.wind.unwrap();
+        assert_eq!(wind.speed.unwrap().get::<knot>().round(), 12.);
+        let temperatures = report.temperatures.unwrap();
+        assert!((temperatures.air.get::<degree_celsius>() - 9.5).abs() < 1e-9);
+        assert!((temperatures.dewpoint.unwrap().get::<degree_celsius>() - 4.8).abs() < 1e-9);
+        assert_eq!(
+            report.station_pressure,
+            Some(Pressure::new::<hectopascal>(1013.8))
+        );
+        assert_eq!(
+            report.sea_level_pressure,
+            Some(Pressure::new::<hectopascal>(1023.3))
+        );
+        assert_eq!(
+            report.pressure_tendency.unwrap().change,
+            Some(Pressure::new::<hectopascal>(1.))
+        );
+        assert_eq!(
+            report.precipitation.unwrap().amount,
+            Some(Length::new::<millimeter>(1.))
+        );
+        assert_eq!(report.precipitation.unwrap().period_hours, Some(6));
+        assert_eq!(report.present_weather, Some(10));
+        assert_eq!(report.past_weather, (Some(2), Some(2)));
+        assert_eq!(report.clouds.unwrap().low, Some(5));
+
+        let section_3 = report.section_3.unwrap();
+        assert!(
+            (section_3
+                .maximum_temperature
+                .unwrap()
+                .get::<degree_celsius>()
+                - 13.2)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            section_3.precipitation_24_hours.unwrap().amount,
+            Some(Length::new::<millimeter>(1.5))
+        );
+        assert_eq!(section_3.cloud_layers.len(), 1);
+        assert_eq!(
+            section_3.cloud_layers[0].base.unwrap().lower,
+            Length::new::<meter>(750.)
+        );
+        assert_eq!(report.section_5, Some(vec!["10///", "29113"]));
+    }
+
+    #[test]
+    fn parse_ship() {
+        let report = synop("BBXX WDCF4 18004 99401 70712 41496 82310 10050 20017 40193 52015 70222 86800 22222 00065 20201 31818 40503 70015 ICE 1////=").unwrap();
+        assert_eq!(report.station, "WDCF4");
+        let position = report.position.unwrap();
+        assert!((position.latitude.get::<degree>() - 40.1).abs() < 1e-9);
+        assert!((position.longitude.get::<degree>() + 71.2).abs() < 1e-9);
+        assert_eq!(
+            report.wind.unwrap().direction.unwrap().get::<degree>(),
+            230.
+        );
+
+        let maritime = report.section_2.unwrap();
+        assert_eq!(maritime.ship_direction, Some(2));
+        assert!(
+            (maritime
+                .sea_surface_temperature
+                .unwrap()
+                .get::<degree_celsius>()
+                - 6.5)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            maritime.wind_waves.unwrap().height,
+            Some(Length::new::<meter>(0.5))
+        );
+        assert_eq!(maritime.swells.len(), 1);
+        assert_eq!(maritime.swells[0].direction.unwrap().get::<degree>(), 180.);
+        assert_eq!(maritime.swells[0].period, Some(Time::new::<second>(5.)));
+        assert_eq!(maritime.other_groups, vec!["ICE 1////"]);
+
+        let water_conditions = maritime.water_conditions();
+        assert_eq!(
+            water_conditions.significant_wave_height,
+            Some(Length::new::<decimeter>(15.))
+        );
+        assert_eq!(
+            water_conditions.surface_state,
+            Some(WaterSurfaceState::Moderate)
+        );
+    }
+
+    #[test]
+    fn parse_buoy() {
+        let report = buoy("ZZYY 62442 18106 1200/ 752301 006412 6112/ 111// 0//// 10077 29083 40135 52006 222// 00121 1//// 20050 21011 444 92021 1////=").unwrap();
+        assert_eq!(report.station, "62442");
+        assert_eq!(report.month, 10);
+        assert_eq!(report.year_digit, 6);
+        let position = report.position.unwrap();
+        assert!((position.latitude.get::<degree>() - 52.301).abs() < 1e-9);
+        assert!((position.longitude.get::<degree>() + 6.412).abs() < 1e-9);
+        assert_eq!(report.wind, None);
+        assert_eq!(report.relative_humidity, Some(83));
+        assert_eq!(
+            report.sea_level_pressure,
+            Some(Pressure::new::<hectopascal>(1013.5))
+        );
+
+        let waves = report.section_2.unwrap().waves.unwrap();
+        assert_eq!(waves.period, Some(Time::new::<second>(5.)));
+        assert_eq!(waves.height, Some(Length::new::<decimeter>(11.)));
+        assert_eq!(report.section_4, Some(vec!["92021", "1////"]));
+    }
+
+    #[test]
+    fn parse_distant_buoy_positions() {
+        for (position, latitude, longitude) in [
+            ("177000 012500", 77., 12.5),
+            ("365600 120500", -65.6, 120.5),
+            ("512345 179999", -12.345, -179.999),
+            ("780000 065536", 80., -65.536),
+        ] {
+            let input = format!("ZZYY 62442 18106 1200/ {} 6112/ 111// 10077=", position);
+            let position = buoy(&input).expect(&input).position.unwrap();
+            assert!((position.latitude.get::<degree>() - latitude).abs() < 1e-9);
+            assert!((position.longitude.get::<degree>() - longitude).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn parse_nil_synop() {
+        let report = synop("AAXX 01061 26063 NIL=").unwrap();
+        assert!(report.nil);
+        assert_eq!(report.wind, None);
+    }
+
+    #[test]
+    fn parse_missing_groups() {
+        for val in [
+            "AAXX 18001 10384 41/// /////",
+            "AAXX 18001 10384 31/58 /0000 11008 21020 39960 40181 5//// 333 55300",
+            "AAXX 18001 10384 31/58 /0000 11008 21020 39960 41540 56005 91545",
+        ] {
+            synop(val).expect(val);
+        }
+    }
+}
@@ -1,5 +1,21 @@
+use std::borrow::Cow;
+use std::ops::Range;
 
+use uom::si::angle::degree;
 use uom::si::f64::{Angle, Length, Pressure, ThermodynamicTemperature, Velocity};
+use uom::si::length::{foot, kilometer, meter};
+
+pub mod bulletin;
+#[cfg(feature = "chrono_helpers")]
+mod chrono_helpers;
+pub mod groups;
+pub mod pirep;
+pub mod sao;
+pub mod sigmet;
+pub mod synop;
+pub mod taf;
+#[cfg(feature = "time_helpers")]
+mod time_helpers;
 
 macro_rules! enum_with_str_repr {
     (
@@ -11,6 +27,7 @@ macro_rules! enum_with_str_repr {
             )*
     }) => {
         #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize))]
         $(#[$enum_attr])*
         pub enum $ident {
             $(
@@ -45,8 +62,160 @@ macro_rules! enum_with_str_repr {
         }
     };
 }
+pub(crate) use enum_with_str_repr;
+
+/// A number exactly as written in a report, before any unit conversion
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReportedValue {
+    /// i.e. `0800` or `29.92`, which is `digits / 10^scale` written with `width` digits
+    Decimal { digits: u32, width: u8, scale: u8 },
+    /// i.e. `1 1/2` or `1/4`
+    Fraction {
+        whole: u32,
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+impl From<ReportedValue> for f64 {
+    fn from(slf: ReportedValue) -> Self {
+        use ReportedValue::*;
+        match slf {
+            Decimal { digits, scale, .. } => digits as f64 / 10f64.powi(scale as i32),
+            Fraction {
+                whole,
+                numerator,
+                denominator,
+            } => whole as f64 + numerator as f64 / denominator as f64,
+        }
+    }
+}
+
+impl From<ReportedValue> for String {
+    fn from(slf: ReportedValue) -> Self {
+        use ReportedValue::*;
+        match slf {
+            Decimal {
+                digits,
+                width,
+                scale: 0,
+            } => format!("{:0width$}", digits, width = width as usize),
+            Decimal {
+                digits,
+                width,
+                scale,
+            } => {
+                let digits = format!("{:0width$}", digits, width = width.max(scale + 1) as usize);
+                let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+                format!("{}.{}", integer, fraction)
+            }
+            Fraction {
+                whole: 0,
+                numerator,
+                denominator,
+            } => format!("{}/{}", numerator, denominator),
+            Fraction {
+                whole,
+                numerator,
+                denominator,
+            } => format!("{} {}/{}", whole, numerator, denominator),
+        }
+    }
+}
+
+impl<'input> std::convert::TryFrom<&'input str> for ReportedValue {
+    type Error = ();
+
+    fn try_from(val: &'input str) -> Result<Self, Self::Error> {
+        use ReportedValue::*;
+        // Up to 9 digits always fit in a u32, and no group in a report has more
+        let parse = |digits: &str| -> Result<u32, ()> {
+            if digits.is_empty() || digits.len() > 9 || !digits.chars().all(|c| c.is_ascii_digit())
+            {
+                Err(())
+            } else {
+                digits.parse().map_err(|_| ())
+            }
+        };
+        if let Some((whole_and_numerator, denominator)) = val.split_once('/') {
+            let (whole, numerator) = match whole_and_numerator.split_once(' ') {
+                Some((whole, numerator)) => (parse(whole)?, parse(numerator)?),
+                None => (0, parse(whole_and_numerator)?),
+            };
+            Ok(Fraction {
+                whole,
+                numerator,
+                denominator: parse(denominator)?,
+            })
+        } else {
+            let (integer, fraction) = val.split_once('.').unwrap_or((val, ""));
+            let digits = format!("{}{}", integer, fraction);
+            Ok(Decimal {
+                digits: parse(&digits)?,
+                width: digits.len() as u8,
+                scale: fraction.len() as u8,
+            })
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ReportedPressure {
+    pub unit: PressureUnit,
+    /// Altimeter settings are reported in hundredths of an inch of mercury
+    pub value: ReportedValue,
+}
+
+/// Pressure at the aerodrome elevation, which CIS stations report in the remark
+///
+/// i.e. `QFE748/0997` is 748 mmHg, followed by the same pressure in hectopascals.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldPressure {
+    pub pressure: Pressure,
+    /// Absent if the report did not specify a unit
+    ///
+    /// The pressure is then read as millimeters of mercury up to [MAX_UNITLESS_QFE_MILLIMETERS_OF_MERCURY],
+    /// and as hectopascals above it.
+    pub unit: Option<FieldPressureUnit>,
+    /// [FieldPressure::pressure] exactly as reported in [FieldPressure::unit]
+    pub value: ReportedValue,
+    pub reported_hectopascals: Option<ReportedValue>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FieldPressureUnit {
+    MillimetersOfMercury,
+    Hectopascals,
+}
+
+/// Largest QFE without a unit that is read as millimeters of mercury
+///
+/// The highest sea level pressure on record is about 1084 hPa, or 813 mmHg, so no QFE in mmHg is above this.
+/// A QFE in hectopascals is only this low at aerodromes above roughly 1,700 m, such as La Paz or Lhasa, where a unitless
+/// QFE is misread as mmHg unless a [Dialect](crate::dialect::Dialect) with a fixed unit is chosen.
+pub const MAX_UNITLESS_QFE_MILLIMETERS_OF_MERCURY: f64 = 820.;
+
+enum_with_str_repr! {
+    /// Features reported as obscured in the remark, i.e. `MT OBSC`
+    Obscured {
+        Mountains => "MT" | "MTNS",
+        Obstacles => "OBST",
+    }
+}
+
+enum_with_str_repr! {
+    PressureUnit {
+        InchesOfMercury => "A",
+        Hectopascals => "Q" | "QNH" | "QFE",
+    }
+}
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ObservationFlag {
     Auto,
     Nil,
@@ -124,6 +293,7 @@ enum_with_str_repr! {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DateTime {
     pub day_of_month: u8,
     pub time: MilitaryTime,
@@ -132,59 +302,64 @@ pub struct DateTime {
     pub is_zulu: bool,
 }
 
-impl DateTime {
-    #[cfg(feature = "chrono_helpers")]
-    pub fn as_datetime(&self, year: i32, month: u32) -> chrono::DateTime<chrono_tz::Tz> {
-        self.time.as_datetime(chrono::TimeZone::ymd(
-            &chrono_tz::Greenwich,
-            year,
-            month,
-            self.day_of_month as u32,
-        ))
-    }
-}
-
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MilitaryTime {
     pub hour: u8,
     pub minute: u8,
 }
 
 impl MilitaryTime {
-    #[cfg(feature = "chrono_helpers")]
-    pub fn as_datetime(
-        &self,
-        date: chrono::Date<chrono_tz::Tz>,
-    ) -> chrono::DateTime<chrono_tz::Tz> {
-        date.and_hms(self.hour as u32, self.minute as u32, 0)
+    /// Hour, minute, and day offset, since some reports use 2400 to mean midnight at the end of the day
+    ///
+    /// Absent if this is not a valid time of day.
+    #[cfg(any(feature = "chrono_helpers", feature = "time_helpers"))]
+    fn time_of_day(&self) -> Option<(u8, u8, i64)> {
+        match (self.hour, self.minute) {
+            (24, 0) => Some((0, 0, 1)),
+            (hour @ 0..=23, minute @ 0..=59) => Some((hour, minute, 0)),
+            _ => None,
+        }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct TimeRange {
-    pub begin: MilitaryTime,
-    pub end: MilitaryTime,
+/// The previous, current, and next month as (year, 1-based month)
+#[cfg(any(feature = "chrono_helpers", feature = "time_helpers"))]
+fn adjacent_months(year: i32, month: u8) -> impl Iterator<Item = (i32, u8)> {
+    (-1..=1).map(move |offset| {
+        let months = year * 12 + month as i32 - 1 + offset;
+        (months.div_euclid(12), months.rem_euclid(12) as u8 + 1)
+    })
 }
 
-impl TimeRange {
-    #[cfg(feature = "chrono_helpers")]
-    pub fn as_start_and_duration(
-        &self,
-        date: chrono::Date<chrono_tz::Tz>,
-    ) -> (chrono::DateTime<chrono_tz::Tz>, chrono::Duration) {
-        let begin = self.begin.as_datetime(date);
-        let end = self.end.as_datetime(date);
-        (begin, (end - begin))
-    }
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TimeRange<T = MilitaryTime> {
+    pub begin: T,
+    pub end: T,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Wind {
     /// A lack of direction indicates variable
     pub direction: Option<Angle>,
     pub speed: Option<Velocity>,
     pub peak_gust: Option<Velocity>,
     pub variance: Option<(Angle, Angle)>,
+    pub unit: SpeedUnit,
+    /// Speed exactly as reported in [Wind::unit]
+    pub reported_speed: Option<ReportedValue>,
+    /// Peak gust exactly as reported in [Wind::unit]
+    pub reported_peak_gust: Option<ReportedValue>,
+}
+
+enum_with_str_repr! {
+    SpeedUnit {
+        Knots => "KT" | "KTS" | "KTM",
+        MetersPerSecond => "MPS",
+        KilometersPerHour => "KMH",
+    }
 }
 
 impl Wind {
@@ -204,14 +379,27 @@ impl Wind {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RunwayVisibility<'input> {
-    pub designator: &'input str,
+    pub designator: Cow<'input, str>,
     pub visibility: VisibilityType,
     pub trend: Option<VisibilityTrend>,
 }
 
+impl RunwayVisibility<'_> {
+    /// Copies borrowed strings so the visibility outlives its input
+    pub fn into_owned(self) -> RunwayVisibility<'static> {
+        RunwayVisibility {
+            designator: Cow::Owned(self.designator.into_owned()),
+            visibility: self.visibility,
+            trend: self.trend,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum VisibilityType {
     Varying {
         lower: RawVisibility,
@@ -221,10 +409,24 @@ pub enum VisibilityType {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RawVisibility {
     /// If present, visibility is out of the observable range
     pub out_of_range: Option<OutOfRange>,
     pub distance: Length,
+    /// Absent if the report did not specify a unit, which implies meters
+    pub unit: Option<DistanceUnit>,
+    /// Distance exactly as reported in [RawVisibility::unit]
+    pub reported_distance: ReportedValue,
+}
+
+enum_with_str_repr! {
+    DistanceUnit {
+        Meters => "M",
+        Kilometers => "KM",
+        StatuteMiles => "SM",
+        Feet => "FT",
+    }
 }
 
 enum_with_str_repr! {
@@ -242,13 +444,50 @@ enum_with_str_repr! {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RunwayReport<'input> {
-    pub designator: &'input str,
+    pub designator: Cow<'input, str>,
     pub report_info: RunwayReportInfo,
 }
 
+impl RunwayReport<'_> {
+    /// Copies borrowed strings so the report outlives its input
+    pub fn into_owned(self) -> RunwayReport<'static> {
+        RunwayReport {
+            designator: Cow::Owned(self.designator.into_owned()),
+            report_info: self.report_info,
+        }
+    }
+
+    /// Runway the report applies to, following the numeric designator conventions
+    ///
+    /// `88` is all runways, `99` repeats the previous report, and 50 is added to the number of right runways.
+    pub fn runway(&self) -> ReportedRunway<'_> {
+        match self.designator.as_ref() {
+            "88" => ReportedRunway::AllRunways,
+            "99" => ReportedRunway::RepeatedReport,
+            designator => match designator.parse::<u8>() {
+                Ok(number @ 51..=86) if designator.len() == 2 => {
+                    ReportedRunway::Runway(Cow::Owned(format!("{:02}R", number - 50)))
+                }
+                _ => ReportedRunway::Runway(Cow::Borrowed(designator)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReportedRunway<'a> {
+    Runway(Cow<'a, str>),
+    AllRunways,
+    /// No new information was received, so the previous report was repeated
+    RepeatedReport,
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum RunwayReportInfo {
     /// Runway has been cleared of any deposits
     Cleared {
@@ -300,6 +539,7 @@ enum_with_str_repr! {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Weather {
     pub intensity: Intensity,
     /// If true, the described phenomenon was not observed above the station, but within 8KM of it
@@ -314,15 +554,15 @@ impl From<Weather> for String {
         let intensity = <&str>::from(slf.intensity);
         let vicinity = match slf.vicinity {
             true => "VC",
-            false => ""
+            false => "",
         };
         let descriptor = match slf.descriptor {
             Some(d) => <&str>::from(d),
-            None => ""
+            None => "",
         };
         let condition = match slf.condition {
             Some(c) => c.into(),
-            None => "".to_string()
+            None => "".to_string(),
         };
         format!("{}{}{}{}", intensity, vicinity, descriptor, condition)
     }
@@ -350,6 +590,7 @@ enum_with_str_repr! {
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Condition {
     /// There can be multiple forms of precipitation observed
     Precipitation(Vec<Precipitation>),
@@ -361,12 +602,11 @@ impl From<Condition> for String {
     fn from(slf: Condition) -> Self {
         use Condition::*;
         match slf {
-            Precipitation(p) => {
-                p.iter()
-                    .map(|p| -> &str { From::from(*p) })
-                    .flat_map(|s|{s.chars()})
-                    .collect::<String>()
-            }
+            Precipitation(p) => p
+                .iter()
+                .map(|p| -> &str { From::from(*p) })
+                .flat_map(|s| s.chars())
+                .collect::<String>(),
             Obscuration(o) => <&str>::from(o).to_string(),
             Other(o) => <&str>::from(o).to_string(),
         }
@@ -411,15 +651,19 @@ enum_with_str_repr! {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CloudCover {
     pub coverage: CloudCoverage,
     /// The absence of a base indicates it is below station level or an inability of an automated system to make an assessment
     pub base: Option<Length>,
     pub cloud_type: Option<CloudType>,
+    /// Base exactly as reported, in hundreds of feet
+    pub reported_base: Option<ReportedValue>,
 }
 
 /// If negative, these are rounded up to the more positive whole degree
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Temperatures {
     pub air: ThermodynamicTemperature,
     /// Some stations don't report this, hence it is marked as optional
@@ -427,6 +671,7 @@ pub struct Temperatures {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AccumulatedRainfall {
     /// In the 10 minutes prior to the report time
     pub recent: Length,
@@ -450,7 +695,50 @@ enum_with_str_repr! {
     }
 }
 
+impl ColorState {
+    /// Minimum visibility and base of the lowest cloud layer covering at least 3/8 of the sky (SCT) for each state,
+    /// from best to worst
+    ///
+    /// | State | Visibility | Cloud base |
+    /// |-------|-----------:|-----------:|
+    /// | BLU+  |     8000 m |   20000 ft |
+    /// | BLU   |     8000 m |    2500 ft |
+    /// | WHT   |     5000 m |    1500 ft |
+    /// | GRN   |     3700 m |     700 ft |
+    /// | YLO1  |     2500 m |     500 ft |
+    /// | YLO2  |     1600 m |     300 ft |
+    /// | AMB   |      800 m |     200 ft |
+    /// | RED   |          - |          - |
+    const THRESHOLDS: [(ColorState, f64, f64); 7] = [
+        (ColorState::BluePlus, 8000., 20000.),
+        (ColorState::Blue, 8000., 2500.),
+        (ColorState::White, 5000., 1500.),
+        (ColorState::Green, 3700., 700.),
+        (ColorState::YellowOne, 2500., 500.),
+        (ColorState::YellowTwo, 1600., 300.),
+        (ColorState::Amber, 800., 200.),
+    ];
+
+    /// Computes the color state from the NATO table given visibility and the base of the lowest cloud layer
+    /// with SCT or greater coverage
+    ///
+    /// An absent cloud base means there is no such layer.
+    pub fn from_conditions(visibility: Length, cloud_base: Option<Length>) -> Self {
+        Self::THRESHOLDS
+            .iter()
+            .find(|(_, min_visibility, min_cloud_base)| {
+                visibility.get::<meter>() >= *min_visibility
+                    && cloud_base
+                        .map(|base| base.get::<foot>() >= *min_cloud_base)
+                        .unwrap_or(true)
+            })
+            .map(|(state, _, _)| *state)
+            .unwrap_or(ColorState::Red)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Color {
     /// `BLACK` in a METAR indicates the airfield is closed
     pub is_black: bool,
@@ -459,6 +747,7 @@ pub struct Color {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Visibility {
     pub prevailing: Option<RawVisibility>,
     /// Typically reported when visibility in a particular direction differs significantly from prevailing visibility
@@ -471,12 +760,14 @@ pub struct Visibility {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DirectionalOrRawVisiblity {
     Raw(RawVisibility),
     Directional(DirectionalVisibility),
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DirectionalVisibility {
     pub direction: CompassDirection,
     pub distance: RawVisibility,
@@ -496,7 +787,35 @@ enum_with_str_repr! {
     }
 }
 
+impl CompassDirection {
+    /// True bearing of the compass point
+    pub fn bearing(&self) -> Angle {
+        use CompassDirection::*;
+        Angle::new::<degree>(match self {
+            North => 0.,
+            NorthEast => 45.,
+            East => 90.,
+            SouthEast => 135.,
+            South => 180.,
+            SouthWest => 225.,
+            West => 270.,
+            NorthWest => 315.,
+        })
+    }
+}
+
+/// A point on the surface of the earth
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Position {
+    /// Positive in the northern hemisphere
+    pub latitude: Angle,
+    /// Positive east of the prime meridian
+    pub longitude: Angle,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct WaterConditions {
     /// Water temperature at the surface
     pub temperature: Option<ThermodynamicTemperature>,
@@ -520,7 +839,37 @@ enum_with_str_repr! {
     }
 }
 
+impl WaterSurfaceState {
+    /// Classifies a significant wave height according to Table 3700
+    pub fn from_wave_height(height: Length) -> Self {
+        use WaterSurfaceState::*;
+        let meters = height.get::<meter>();
+        if meters <= 0. {
+            GlassyCalm
+        } else if meters <= 0.1 {
+            RippledCalm
+        } else if meters <= 0.5 {
+            Smooth
+        } else if meters <= 1.25 {
+            Slight
+        } else if meters <= 2.5 {
+            Moderate
+        } else if meters <= 4. {
+            Rough
+        } else if meters <= 6. {
+            VeryRough
+        } else if meters <= 9. {
+            High
+        } else if meters <= 14. {
+            VeryHigh
+        } else {
+            Phenomenal
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Trend {
     /// No significant change in weather expected for the next 2 hours
     NoSignificantChange,
@@ -531,6 +880,7 @@ pub enum Trend {
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TrendReport {
     pub time: Option<TrendTime>,
     pub wind: Option<Wind>,
@@ -541,20 +891,48 @@ pub struct TrendReport {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum TrendTime {
-    At(MilitaryTime),
-    Range {
-        from: MilitaryTime,
-        until: MilitaryTime,
-    },
-    From(MilitaryTime),
-    Until(MilitaryTime),
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TrendTime<T = MilitaryTime> {
+    At(T),
+    Range { from: T, until: T },
+    From(T),
+    Until(T),
+}
+
+enum_with_str_repr! {
+    ReportKind {
+        /// Routine report
+        Metar => "METAR",
+        /// Special report, issued when conditions change significantly between routine reports
+        Speci => "SPECI",
+    }
+}
+
+/// Wind shear along the take-off or approach paths
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum WindShear<'input> {
+    AllRunways,
+    Runway(Cow<'input, str>),
+}
+
+impl WindShear<'_> {
+    /// Copies borrowed strings so the wind shear outlives its input
+    pub fn into_owned(self) -> WindShear<'static> {
+        match self {
+            Self::AllRunways => WindShear::AllRunways,
+            Self::Runway(designator) => WindShear::Runway(Cow::Owned(designator.into_owned())),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MetarReport<'input> {
+    /// Absent if the report did not start with its name
+    pub kind: Option<ReportKind>,
     /// Station [ICAO identifier](https://en.wikipedia.org/wiki/ICAO_airport_code)
-    pub identifier: &'input str,
+    pub identifier: Cow<'input, str>,
     pub observation_time: Option<DateTime>,
     /// Usually used by TAFs, but some stations include this
     pub observation_validity_range: Option<TimeRange>,
@@ -575,6 +953,10 @@ pub struct MetarReport<'input> {
     pub cavok: bool,
     pub temperatures: Option<Temperatures>,
     pub pressure: Option<Pressure>,
+    /// [MetarReport::pressure] exactly as reported
+    pub reported_pressure: Option<ReportedPressure>,
+    /// QFE, usually found in the remark of CIS stations
+    pub field_pressure: Option<FieldPressure>,
     /// Often reported by Australian stations
     ///
     /// See the Australian Government [Bureau of Meteorology FAQ](http://www.bom.gov.au/aviation/about-us/faq/)
@@ -582,15 +964,225 @@ pub struct MetarReport<'input> {
     /// Often reported by military stations
     pub color: Option<Color>,
     pub recent_weather: Vec<Weather>,
+    pub wind_shear: Vec<WindShear<'input>>,
     /// Often reported by stations at sea
     ///
     /// i.e. [ENQA](https://en.wikipedia.org/wiki/Troll_A_platform), an offshore natural gas platform.
     pub water_conditions: Option<WaterConditions>,
     pub trends: Vec<Trend>,
     /// Additional information outside of the METAR specification
-    pub remark: Option<&'input str>,
+    pub remark: Option<Cow<'input, str>>,
+    /// Features obscured by weather, from the remark
+    pub obscured: Vec<Obscured>,
     /// Some automated METARs indicate if the system needs maintenance
     ///
     /// This may indicate that measurements are unreliable
     pub maintenance_needed: bool,
 }
+
+impl<'input> MetarReport<'input> {
+    /// Copies borrowed strings so the report outlives its input
+    ///
+    /// Owned reports can be cached or sent to other threads.
+    pub fn into_owned(self) -> MetarReport<'static> {
+        MetarReport {
+            kind: self.kind,
+            identifier: Cow::Owned(self.identifier.into_owned()),
+            observation_time: self.observation_time,
+            observation_validity_range: self.observation_validity_range,
+            observation_flags: self.observation_flags,
+            wind: self.wind,
+            visibility: self.visibility,
+            runway_visibilities: self
+                .runway_visibilities
+                .into_iter()
+                .map(RunwayVisibility::into_owned)
+                .collect(),
+            runway_reports: self
+                .runway_reports
+                .into_iter()
+                .map(RunwayReport::into_owned)
+                .collect(),
+            weather: self.weather,
+            cloud_cover: self.cloud_cover,
+            cavok: self.cavok,
+            temperatures: self.temperatures,
+            pressure: self.pressure,
+            reported_pressure: self.reported_pressure,
+            field_pressure: self.field_pressure,
+            accumulated_rainfall: self.accumulated_rainfall,
+            color: self.color,
+            recent_weather: self.recent_weather,
+            wind_shear: self
+                .wind_shear
+                .into_iter()
+                .map(WindShear::into_owned)
+                .collect(),
+            water_conditions: self.water_conditions,
+            trends: self.trends,
+            remark: self.remark.map(|remark| Cow::Owned(remark.into_owned())),
+            obscured: self.obscured,
+            maintenance_needed: self.maintenance_needed,
+        }
+    }
+
+    /// Color state implied by the observed visibility and cloud cover, see [ColorState::from_conditions]
+    ///
+    /// Absent if visibility was not reported or a relevant cloud layer has no base.
+    pub fn observed_color_state(&self) -> Option<ColorState> {
+        let visibility = if self.cavok {
+            Length::new::<kilometer>(10.)
+        } else {
+            let visibility = self.visibility?;
+            match (visibility.prevailing, visibility.minimum) {
+                (Some(prevailing), _) => prevailing.distance,
+                (None, Some(DirectionalOrRawVisiblity::Raw(raw))) => raw.distance,
+                (None, Some(DirectionalOrRawVisiblity::Directional(directional))) => {
+                    directional.distance.distance
+                }
+                (None, None) => return None,
+            }
+        };
+
+        let mut cloud_base = None;
+        for cloud_cover in self.cloud_cover.iter().filter(|cloud_cover| {
+            matches!(
+                cloud_cover.coverage,
+                CloudCoverage::Scattered
+                    | CloudCoverage::Broken
+                    | CloudCoverage::Overcast
+                    | CloudCoverage::VerticalVisibility
+            )
+        }) {
+            let base = cloud_cover.base?;
+            if cloud_base.map(|lowest| base < lowest).unwrap_or(true) {
+                cloud_base = Some(base);
+            }
+        }
+        // CAVOK only guarantees there is no cloud below 5000ft
+        if self.cavok && cloud_base.is_none() {
+            cloud_base = Some(Length::new::<foot>(5000.));
+        }
+
+        Some(ColorState::from_conditions(visibility, cloud_base))
+    }
+
+    /// Checks whether the reported [Color::current_color] agrees with observed conditions
+    ///
+    /// Absent if there is no reported color or it can't be computed.
+    /// Not all stations use BLU+, so BLU is considered consistent with conditions that would be BLU+.
+    pub fn is_color_consistent(&self) -> Option<bool> {
+        let reported = self.color?.current_color;
+        let observed = self.observed_color_state()?;
+        Some(
+            reported == observed
+                || (reported == ColorState::Blue && observed == ColorState::BluePlus),
+        )
+    }
+}
+
+/// Byte ranges of the groups in a [MetarReport], as returned by [crate::parse::metar_with_spans]
+///
+/// Lists are in the same order as their counterparts in the report.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MetarSpans {
+    pub kind: Option<Range<usize>>,
+    pub identifier: Range<usize>,
+    pub observation_time: Option<Range<usize>>,
+    pub wind: Option<Range<usize>>,
+    pub visibility: Option<Range<usize>>,
+    pub runway_visibilities: Vec<Range<usize>>,
+    pub runway_reports: Vec<Range<usize>>,
+    pub weather: Vec<Range<usize>>,
+    pub cloud_cover: Vec<Range<usize>>,
+    pub temperatures: Option<Range<usize>>,
+    pub pressure: Option<Range<usize>>,
+    pub cavok: Option<Range<usize>>,
+    pub accumulated_rainfall: Option<Range<usize>>,
+    pub color: Option<Range<usize>>,
+    pub trends: Vec<Range<usize>>,
+    pub remark: Option<Range<usize>>,
+}
+
+impl MetarSpans {
+    /// Name and span of every group, in the order they appear in the report
+    pub fn groups(&self) -> Vec<(&'static str, Range<usize>)> {
+        let mut groups = vec![("identifier", self.identifier.clone())];
+        let single = [
+            ("report name", &self.kind),
+            ("observation time", &self.observation_time),
+            ("wind", &self.wind),
+            ("visibility", &self.visibility),
+            ("temperatures", &self.temperatures),
+            ("pressure", &self.pressure),
+            ("CAVOK", &self.cavok),
+            ("accumulated rainfall", &self.accumulated_rainfall),
+            ("color", &self.color),
+            ("remark", &self.remark),
+        ];
+        for (name, span) in single.iter() {
+            groups.extend(span.iter().map(|span| (*name, span.clone())));
+        }
+        let lists = [
+            ("runway visibility", &self.runway_visibilities),
+            ("runway state", &self.runway_reports),
+            ("weather", &self.weather),
+            ("cloud cover", &self.cloud_cover),
+            ("trend", &self.trends),
+        ];
+        for (name, spans) in lists.iter() {
+            groups.extend(spans.iter().map(|span| (*name, span.clone())));
+        }
+        groups.sort_by_key(|(_, span)| span.start);
+        groups
+    }
+
+    /// Secondary labels naming each group, for annotating a report with [codespan_reporting]
+    #[cfg(feature = "codespan_helpers")]
+    pub fn labels(&self) -> Vec<codespan_reporting::diagnostic::Label<()>> {
+        self.groups()
+            .into_iter()
+            .map(|(name, span)| {
+                codespan_reporting::diagnostic::Label::secondary((), span).with_message(name)
+            })
+            .collect()
+    }
+
+    /// Excludes whitespace and terminators consumed after each group
+    pub(crate) fn trim_end(&mut self, text: &str) {
+        let trim = |span: &mut Range<usize>| {
+            let trimmed = text[span.clone()]
+                .trim_end_matches(|c: char| c.is_whitespace() || c == '>' || c == '=');
+            span.end = span.start + trimmed.len();
+        };
+        trim(&mut self.identifier);
+        for span in [
+            &mut self.kind,
+            &mut self.observation_time,
+            &mut self.wind,
+            &mut self.visibility,
+            &mut self.temperatures,
+            &mut self.pressure,
+            &mut self.cavok,
+            &mut self.accumulated_rainfall,
+            &mut self.color,
+            &mut self.remark,
+        ]
+        .iter_mut()
+        {
+            span.iter_mut().for_each(trim);
+        }
+        for spans in [
+            &mut self.runway_visibilities,
+            &mut self.runway_reports,
+            &mut self.weather,
+            &mut self.cloud_cover,
+            &mut self.trends,
+        ]
+        .iter_mut()
+        {
+            spans.iter_mut().for_each(trim);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Types for the [WMO bulletin](https://community.wmo.int/en/activity-areas/wis/wmo-manual-on-the-gts) envelope that carries reports over the GTS
+use std::ops::Range;
+
+use super::{enum_with_str_repr, DateTime};
+
+/// A bulletin with one or more `=` terminated reports
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Bulletin<'input> {
+    pub heading: AbbreviatedHeading<'input>,
+    /// Everything after the heading
+    pub body: &'input str,
+    /// Byte offset of the body in the bulletin
+    pub body_offset: usize,
+}
+
+impl<'input> Bulletin<'input> {
+    /// Splits the body into its reports, skipping empty ones
+    pub fn reports(&self) -> impl Iterator<Item = BulletinReport<'input>> + 'input {
+        let body = self.body;
+        let body_offset = self.body_offset;
+        let mut start = 0;
+        std::iter::from_fn(move || loop {
+            if start >= body.len() {
+                return None;
+            }
+            let end = body[start..]
+                .find('=')
+                .map(|i| start + i)
+                .unwrap_or(body.len());
+            let chunk = &body[start..end];
+            start = end + 1;
+
+            let trimmed = chunk.trim_start();
+            let leading = chunk.len() - trimmed.len();
+            let text = trimmed.trim_end();
+            if !text.is_empty() {
+                let begin = body_offset + end - chunk.len() + leading;
+                return Some(BulletinReport {
+                    text,
+                    span: begin..begin + text.len(),
+                });
+            }
+        })
+    }
+}
+
+/// A single report within a bulletin, without the terminating `=`
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BulletinReport<'input> {
+    pub text: &'input str,
+    /// Byte offsets of the text in the bulletin
+    pub span: Range<usize>,
+}
+
+/// The first line of a bulletin (TTAAii CCCC YYGGgg BBB)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AbbreviatedHeading<'input> {
+    /// Data type designators, i.e. `SA` for routine aviation reports
+    pub data_type: &'input str,
+    /// Country or area designator, i.e. `US`
+    pub area: &'input str,
+    /// Distinguishes bulletins with the same data type and area from the same centre
+    pub number: u8,
+    /// ICAO identifier of the centre that compiled the bulletin
+    pub originating_centre: &'input str,
+    /// Day of month and time the bulletin was compiled
+    pub time: DateTime,
+    pub indicator: Option<BulletinIndicator<'input>>,
+}
+
+/// Marks a bulletin as delayed, corrected, amended or one segment of a longer one (BBB)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BulletinIndicator<'input> {
+    pub kind: IndicatorKind,
+    /// Letters counting up from `A` for each subsequent bulletin of this kind, or `AA` through `ZZ` for segments
+    pub sequence: &'input str,
+}
+
+enum_with_str_repr! {
+    IndicatorKind {
+        Delayed => "RR",
+        Correction => "CC",
+        Amendment => "AA",
+        Segment => "P",
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::{Greenwich, Tz};
+
+use super::{adjacent_months, DateTime, MilitaryTime, TimeRange, TrendTime};
+
+impl DateTime {
+    /// Absent if the day of month does not exist in the given year and month
+    pub fn as_datetime(&self, year: i32, month: u32) -> Option<chrono::DateTime<Tz>> {
+        self.time.as_datetime(NaiveDate::from_ymd_opt(
+            year,
+            month,
+            self.day_of_month as u32,
+        )?)
+    }
+
+    /// Resolves into an absolute time using a reference instant close to it, such as when the report was received
+    ///
+    /// Only the day of month is reported, so the year and month are chosen from the months around the reference
+    /// to give the nearest valid time. This correctly handles a report for the 31st received on the 1st.
+    ///
+    /// The time is always taken as UTC, even if [DateTime::is_zulu] is false; check it first if that matters.
+    pub fn resolve<T: TimeZone>(
+        &self,
+        reference: &chrono::DateTime<T>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let reference = reference.naive_utc();
+        adjacent_months(reference.year(), reference.month() as u8)
+            .filter_map(|(year, month)| {
+                self.time.as_naive_datetime(NaiveDate::from_ymd_opt(
+                    year,
+                    month as u32,
+                    self.day_of_month as u32,
+                )?)
+            })
+            .min_by_key(|candidate| (*candidate - reference).num_seconds().abs())
+            .map(|resolved| Greenwich.from_utc_datetime(&resolved))
+    }
+}
+
+impl MilitaryTime {
+    /// Absent if this is not a valid time of day
+    pub fn as_datetime(&self, date: NaiveDate) -> Option<chrono::DateTime<Tz>> {
+        self.as_naive_datetime(date)
+            .map(|datetime| Greenwich.from_utc_datetime(&datetime))
+    }
+
+    /// Resolves into the occurrence of this time of day nearest to the reference instant
+    pub fn resolve<T: TimeZone>(
+        &self,
+        reference: &chrono::DateTime<T>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let reference = reference.naive_utc();
+        let today = self.as_naive_datetime(reference.date())?;
+        [today - Duration::days(1), today, today + Duration::days(1)]
+            .iter()
+            .min_by_key(|candidate| (**candidate - reference).num_seconds().abs())
+            .map(|resolved| Greenwich.from_utc_datetime(resolved))
+    }
+
+    /// Resolves into the first occurrence of this time of day at or after the reference instant
+    pub fn resolve_after<T: TimeZone>(
+        &self,
+        reference: &chrono::DateTime<T>,
+    ) -> Option<chrono::DateTime<Tz>> {
+        let reference = reference.naive_utc();
+        let today = self.as_naive_datetime(reference.date())?;
+        [today - Duration::days(1), today, today + Duration::days(1)]
+            .iter()
+            .find(|candidate| **candidate >= reference)
+            .map(|resolved| Greenwich.from_utc_datetime(resolved))
+    }
+
+    fn as_naive_datetime(&self, date: NaiveDate) -> Option<NaiveDateTime> {
+        let (hour, minute, day_offset) = self.time_of_day()?;
+        Some(
+            date.and_time(NaiveTime::from_hms_opt(hour as u32, minute as u32, 0)?)
+                + Duration::days(day_offset),
+        )
+    }
+}
+
+impl TimeRange {
+    /// A range where `end < begin` crosses midnight, so it ends on the following day
+    pub fn as_start_and_duration(
+        &self,
+        date: NaiveDate,
+    ) -> Option<(chrono::DateTime<Tz>, Duration)> {
+        let begin = self.begin.as_datetime(date)?;
+        let end = self.end.resolve_after(&begin)?;
+        Some((begin, end - begin))
+    }
+
+    /// Resolves into absolute times, beginning nearest to the reference instant
+    ///
+    /// A range where `end < begin` crosses midnight, so it ends on the following day.
+    pub fn resolve<T: TimeZone>(
+        &self,
+        reference: &chrono::DateTime<T>,
+    ) -> Option<TimeRange<chrono::DateTime<Tz>>> {
+        let begin = self.begin.resolve(reference)?;
+        let end = self.end.resolve_after(&begin)?;
+        Some(TimeRange { begin, end })
+    }
+}
+
+impl TimeRange<DateTime> {
+    /// Resolves a TAF validity period into absolute times, beginning nearest to the reference instant
+    ///
+    /// The end is resolved nearest to the beginning, so a period such as `3118/0124` ends in the following month.
+    pub fn resolve<T: TimeZone>(
+        &self,
+        reference: &chrono::DateTime<T>,
+    ) -> Option<TimeRange<chrono::DateTime<Tz>>> {
+        let begin = self.begin.resolve(reference)?;
+        let end = self.end.resolve(&begin)?;
+        Some(TimeRange { begin, end })
+    }
+}
+
+impl TrendTime {
+    /// Resolves into absolute times using the observation time of the report
+    ///
+    /// Trends forecast the period following the observation, so each time is the first occurrence at or after it.
+    pub fn resolve<T: TimeZone>(
+        &self,
+        observation_time: &chrono::DateTime<T>,
+    ) -> Option<TrendTime<chrono::DateTime<Tz>>> {
+        use TrendTime::*;
+        Some(match self {
+            At(time) => At(time.resolve_after(observation_time)?),
+            Range { from, until } => {
+                let from = from.resolve_after(observation_time)?;
+                Range {
+                    until: until.resolve_after(&from)?,
+                    from,
+                }
+            }
+            From(time) => From(time.resolve_after(observation_time)?),
+            Until(time) => Until(time.resolve_after(observation_time)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_across_month_boundary() {
+        let observation_time = DateTime {
+            day_of_month: 31,
+            time: MilitaryTime {
+                hour: 23,
+                minute: 50,
+            },
+            is_zulu: true,
+        };
+        let received = chrono::Utc.with_ymd_and_hms(2021, 9, 1, 0, 5, 0).unwrap();
+        assert_eq!(
+            observation_time.resolve(&received).unwrap(),
+            Greenwich.with_ymd_and_hms(2021, 8, 31, 23, 50, 0).unwrap()
+        );
+        assert_eq!(observation_time.as_datetime(2021, 9), None);
+    }
+
+    #[test]
+    fn resolve_range_across_midnight() {
+        let range = TimeRange {
+            begin: MilitaryTime {
+                hour: 22,
+                minute: 0,
+            },
+            end: MilitaryTime { hour: 4, minute: 0 },
+        };
+        let reference = chrono::Utc
+            .with_ymd_and_hms(2021, 12, 31, 21, 50, 0)
+            .unwrap();
+        let resolved = range.resolve(&reference).unwrap();
+        assert_eq!(resolved.end - resolved.begin, Duration::hours(6));
+        assert_eq!(
+            resolved.end,
+            Greenwich.with_ymd_and_hms(2022, 1, 1, 4, 0, 0).unwrap()
+        );
+        assert_eq!(
+            range
+                .as_start_and_duration(reference.date_naive())
+                .unwrap()
+                .1,
+            Duration::hours(6)
+        );
+    }
+
+    #[test]
+    fn resolve_taf_period_across_month_boundary() {
+        let day_time = |day_of_month, hour| DateTime {
+            day_of_month,
+            time: MilitaryTime { hour, minute: 0 },
+            is_zulu: true,
+        };
+        let period = TimeRange {
+            begin: day_time(31, 18),
+            end: day_time(1, 24),
+        };
+        let issued = chrono::Utc
+            .with_ymd_and_hms(2021, 8, 31, 17, 20, 0)
+            .unwrap();
+        let resolved = period.resolve(&issued).unwrap();
+        assert_eq!(
+            resolved.begin,
+            Greenwich.with_ymd_and_hms(2021, 8, 31, 18, 0, 0).unwrap()
+        );
+        assert_eq!(
+            resolved.end,
+            Greenwich.with_ymd_and_hms(2021, 9, 2, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Types for classifying the groups of a METAR without fully parsing it
+use std::ops::Range;
+
+/// A whitespace separated group and what it appears to be
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Group<'input> {
+    pub kind: GroupKind,
+    pub text: &'input str,
+    /// Byte range of [Group::text] in the report
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum GroupKind {
+    /// `METAR` or `SPECI`
+    ReportName,
+    /// Station ICAO identifier
+    Identifier,
+    /// i.e. `AUTO` or `COR`
+    ObservationFlag,
+    /// Observation time or the time of a trend
+    Time,
+    /// Wind or its variance
+    Wind,
+    /// Prevailing or directional visibility, including `CAVOK`
+    Visibility,
+    RunwayVisibility,
+    /// Present or recent weather, including `NSW`
+    Weather,
+    Cloud,
+    Temperature,
+    Pressure,
+    /// Runway state, i.e. `R24/290550`
+    RunwayState,
+    /// Military color state
+    Color,
+    /// Trend keyword, i.e. `NOSIG` or `TEMPO`
+    Trend,
+    /// Everything from `RMK` to the end of the report
+    Remark,
+    Unknown,
+}
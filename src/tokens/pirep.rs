@@ -0,0 +1,129 @@
+//! Types for [pilot reports](https://en.wikipedia.org/wiki/Pilot_report) (PIREP)
+use uom::si::f64::{Angle, Length, ThermodynamicTemperature};
+
+use super::{enum_with_str_repr, CloudCover, MilitaryTime, Weather, Wind};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PirepReport<'input> {
+    /// Weather reporting location nearest to where the report was made, if it was prefixed
+    pub station: Option<&'input str>,
+    /// An urgent report (UUA) of hazardous conditions
+    pub urgent: bool,
+    /// A single location, or the start and end of the route segment the report applies to
+    pub location: Vec<Location<'input>>,
+    pub time: Option<MilitaryTime>,
+    pub flight_level: Option<Length>,
+    /// Present if the report was made while climbing or descending instead of at a flight level
+    pub flight_phase: Option<FlightPhase>,
+    pub aircraft_type: Option<&'input str>,
+    pub sky_cover: Vec<SkyCondition>,
+    pub flight_visibility: Option<Length>,
+    pub weather: Vec<Weather>,
+    pub temperature: Option<ThermodynamicTemperature>,
+    pub wind: Option<Wind>,
+    pub turbulence: Vec<Turbulence>,
+    pub icing: Vec<Icing>,
+    pub remark: Option<&'input str>,
+}
+
+/// A point given relative to a navigation aid, airport or waypoint
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Location<'input> {
+    pub fix: &'input str,
+    /// Magnetic bearing from the fix
+    pub radial: Option<Angle>,
+    /// In nautical miles from the fix
+    pub distance: Option<Length>,
+}
+
+enum_with_str_repr! {
+    FlightPhase {
+        Climbing => "DURC",
+        Descending => "DURD",
+    }
+}
+
+/// Cloud layer as seen by the pilot
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SkyCondition {
+    /// Absent if the pilot only reported where the tops are
+    pub cover: Option<CloudCover>,
+    pub top: Option<Length>,
+}
+
+/// Vertical extent of a condition, where a missing bound means it extends above or below the other
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AltitudeRange {
+    pub lower: Option<Length>,
+    pub upper: Option<Length>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Turbulence {
+    pub intensity: TurbulenceIntensity,
+    /// Present if the intensity was given as a range, i.e. `LGT-MOD`
+    pub peak_intensity: Option<TurbulenceIntensity>,
+    pub frequency: Option<Frequency>,
+    pub turbulence_type: Option<TurbulenceType>,
+    pub altitude: Option<AltitudeRange>,
+}
+
+enum_with_str_repr! {
+    TurbulenceIntensity {
+        Negative => "NEG",
+        Smooth => "SMTH" | "SMOOTH",
+        Light => "LGT",
+        Moderate => "MOD",
+        Severe => "SEV",
+        Extreme => "EXTM" | "EXTRM",
+    }
+}
+
+enum_with_str_repr! {
+    TurbulenceType {
+        Chop => "CHOP",
+        ClearAir => "CAT",
+        LowLevelWindShear => "LLWS",
+    }
+}
+
+enum_with_str_repr! {
+    Frequency {
+        Occasional => "OCNL",
+        Intermittent => "INTMT",
+        Continuous => "CONS",
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Icing {
+    pub intensity: IcingIntensity,
+    /// Present if the intensity was given as a range, i.e. `LGT-MOD`
+    pub peak_intensity: Option<IcingIntensity>,
+    pub icing_type: Option<IcingType>,
+    pub altitude: Option<AltitudeRange>,
+}
+
+enum_with_str_repr! {
+    IcingIntensity {
+        Negative => "NEG",
+        Trace => "TRC" | "TRACE",
+        Light => "LGT",
+        Moderate => "MOD",
+        Severe => "SEV",
+    }
+}
+
+enum_with_str_repr! {
+    IcingType {
+        Rime => "RIME",
+        Clear => "CLR",
+        Mixed => "MX" | "MXD",
+    }
+}
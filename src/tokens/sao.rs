@@ -0,0 +1,146 @@
+//! Types for legacy [Surface Aviation Observations](https://en.wikipedia.org/wiki/Surface_weather_observation) (SAO)
+//!
+//! SAOs were replaced by METARs in 1996, but some Canadian automatic stations still report them.
+//! US SAOs, found in older archives, report temperatures in degrees Fahrenheit rather than Celsius.
+use std::borrow::Cow;
+use uom::si::{
+    f64::{Length, Pressure, ThermodynamicTemperature},
+    thermodynamic_temperature::{degree_celsius, degree_fahrenheit},
+};
+
+use super::{
+    enum_with_str_repr, CloudCover, DateTime, MetarReport, MilitaryTime, ObservationFlag,
+    RawVisibility, ReportedPressure, Temperatures, Visibility, Weather, Wind,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SaoReport<'input> {
+    /// Three letter station identifier, without the country prefix of its ICAO identifier
+    pub station: &'input str,
+    pub kind: SaoKind,
+    pub time: MilitaryTime,
+    /// Automatic station type, if any, i.e. `AUTO8`
+    pub automatic_station: Option<&'input str>,
+    /// Cloud layers from lowest to highest, where an obscured sky is a vertical visibility
+    pub sky_condition: Vec<CloudCover>,
+    /// Base of the layer marked as the ceiling
+    pub ceiling: Option<Length>,
+    pub visibility: Option<RawVisibility>,
+    /// Weather and obstructions to vision, converted to their METAR form
+    pub weather: Vec<Weather>,
+    pub sea_level_pressure: Option<Pressure>,
+    /// In the unit of the reporting country, see [SaoTemperatureUnit::from_country_prefix]
+    pub temperatures: Option<SaoTemperatures>,
+    /// Always in knots
+    pub wind: Option<Wind>,
+    /// Altimeter setting, reported without its leading digit
+    pub altimeter: Option<(Pressure, ReportedPressure)>,
+    /// Everything after the altimeter setting, i.e. precipitation amounts and pressure tendency
+    pub remark: Option<&'input str>,
+}
+
+enum_with_str_repr! {
+    SaoKind {
+        /// Hourly observation
+        Record => "SA",
+        /// Observation made because conditions changed significantly
+        Special => "SP",
+        /// Hourly observation that also meets the criteria of a special
+        RecordSpecial => "RS",
+    }
+}
+
+/// Air temperature and dewpoint in whole degrees, as reported
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SaoTemperatures {
+    pub air: i32,
+    pub dewpoint: Option<i32>,
+}
+
+impl SaoTemperatures {
+    pub fn in_unit(self, unit: SaoTemperatureUnit) -> Temperatures {
+        let temperature = |degrees: i32| match unit {
+            SaoTemperatureUnit::Celsius => {
+                ThermodynamicTemperature::new::<degree_celsius>(degrees.into())
+            }
+            SaoTemperatureUnit::Fahrenheit => {
+                ThermodynamicTemperature::new::<degree_fahrenheit>(degrees.into())
+            }
+        };
+        Temperatures {
+            air: temperature(self.air),
+            dewpoint: self.dewpoint.map(temperature),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SaoTemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl SaoTemperatureUnit {
+    /// Fahrenheit for stations in the United States and its territories (`K` and `P`), Celsius elsewhere
+    pub fn from_country_prefix(country_prefix: char) -> Self {
+        match country_prefix {
+            'K' | 'P' => Self::Fahrenheit,
+            _ => Self::Celsius,
+        }
+    }
+}
+
+impl<'input> SaoReport<'input> {
+    /// Converts the report into a METAR so it can be handled alongside other stations
+    ///
+    /// SAOs include neither the day nor the country prefix of the ICAO identifier (`C` in Canada),
+    /// so both must be supplied by the caller.
+    /// The country prefix also decides the unit of the temperatures, see [SaoTemperatureUnit::from_country_prefix].
+    /// The sea level pressure is not kept, but the altimeter setting is.
+    pub fn into_metar(self, country_prefix: char, day_of_month: u8) -> MetarReport<'input> {
+        MetarReport {
+            kind: None,
+            identifier: Cow::Owned(format!("{}{}", country_prefix, self.station)),
+            observation_time: Some(DateTime {
+                day_of_month,
+                time: self.time,
+                is_zulu: true,
+            }),
+            observation_validity_range: None,
+            observation_flags: if self.automatic_station.is_some() {
+                vec![ObservationFlag::Auto]
+            } else {
+                vec![]
+            },
+            wind: self.wind,
+            visibility: self.visibility.map(|prevailing| Visibility {
+                prevailing: Some(prevailing),
+                minimum: None,
+                maximum_directional: None,
+            }),
+            runway_visibilities: vec![],
+            runway_reports: vec![],
+            weather: self.weather,
+            cloud_cover: self.sky_condition,
+            cavok: false,
+            temperatures: self.temperatures.map(|temperatures| {
+                temperatures.in_unit(SaoTemperatureUnit::from_country_prefix(country_prefix))
+            }),
+            pressure: self.altimeter.map(|(pressure, _)| pressure),
+            reported_pressure: self.altimeter.map(|(_, reported)| reported),
+            field_pressure: None,
+            accumulated_rainfall: None,
+            color: None,
+            recent_weather: vec![],
+            wind_shear: vec![],
+            water_conditions: None,
+            trends: vec![],
+            remark: self.remark.map(Cow::Borrowed),
+            obscured: vec![],
+            maintenance_needed: false,
+        }
+    }
+}
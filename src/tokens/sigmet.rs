@@ -0,0 +1,541 @@
+//! Types for hazard area reports: [SIGMET](https://en.wikipedia.org/wiki/SIGMET) and [AIRMET](https://en.wikipedia.org/wiki/AIRMET)
+use uom::si::angle::{degree, radian};
+use uom::si::f64::{Angle, Length, Velocity};
+use uom::si::length::nautical_mile;
+
+use super::pirep::{AltitudeRange, Location};
+use super::{enum_with_str_repr, CompassDirection, DateTime, MilitaryTime, Position, TimeRange};
+
+/// SIGMET in the ICAO format
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SigmetReport<'input> {
+    /// ICAO identifier of the unit serving the region
+    pub air_traffic_services_unit: &'input str,
+    /// Sequence number within the day, which may include letters
+    pub sequence: &'input str,
+    pub validity: TimeRange<DateTime>,
+    /// ICAO identifier of the meteorological watch office that issued the SIGMET
+    pub meteorological_watch_office: &'input str,
+    /// ICAO identifier of the flight information region
+    pub fir: &'input str,
+    /// Including the type of region, i.e. `SHANWICK OCEANIC FIR`
+    pub fir_name: &'input str,
+    pub content: SigmetContent<'input>,
+}
+
+impl<'input> SigmetReport<'input> {
+    /// Whether a point is within the hazard area, see [Area::contains]
+    ///
+    /// Absent if the SIGMET was cancelled or the area can't be determined.
+    pub fn applies_to(&self, point: Position) -> Option<bool> {
+        match &self.content {
+            SigmetContent::Hazard(hazard) => hazard.area.as_ref()?.contains(point),
+            SigmetContent::Cancellation { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SigmetContent<'input> {
+    Hazard(Hazard<'input>),
+    /// Cancels an earlier SIGMET
+    Cancellation {
+        sequence: &'input str,
+        validity: TimeRange<DateTime>,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Hazard<'input> {
+    pub phenomenon: Phenomenon<'input>,
+    /// Whether the phenomenon was observed, otherwise it is forecast
+    pub observed: bool,
+    /// Time of the observation or forecast, if different from the beginning of the validity period
+    pub time: Option<MilitaryTime>,
+    /// Absent if the area is given relative to something that wasn't reported, i.e. a cyclone without a position
+    pub area: Option<Area>,
+    pub levels: Option<AltitudeRange>,
+    pub movement: Option<Movement>,
+    pub intensity_change: Option<IntensityChange>,
+    /// Forecast position at the end of the validity period, not decoded
+    pub forecast: Option<&'input str>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Phenomenon<'input> {
+    Thunderstorm {
+        qualifier: ThunderstormQualifier,
+        hail: bool,
+    },
+    SevereTurbulence,
+    SevereIcing {
+        freezing_rain: bool,
+    },
+    SevereMountainWave,
+    HeavyDuststorm,
+    HeavySandstorm,
+    RadioactiveCloud,
+    VolcanicAsh {
+        volcano: Option<&'input str>,
+        position: Option<Position>,
+    },
+    TropicalCyclone {
+        name: &'input str,
+        position: Option<Position>,
+    },
+}
+
+impl<'input> Phenomenon<'input> {
+    pub fn kind(&self) -> SigmetKind {
+        match self {
+            Phenomenon::VolcanicAsh { .. } => SigmetKind::VolcanicAsh,
+            Phenomenon::TropicalCyclone { .. } => SigmetKind::TropicalCyclone,
+            _ => SigmetKind::Weather,
+        }
+    }
+}
+
+enum_with_str_repr! {
+    /// Data type designator used in the WMO heading of the bulletin
+    SigmetKind {
+        Weather => "WS",
+        VolcanicAsh => "WV",
+        TropicalCyclone => "WC",
+    }
+}
+
+enum_with_str_repr! {
+    ThunderstormQualifier {
+        Obscured => "OBSC",
+        Embedded => "EMBD",
+        Frequent => "FRQ",
+        SquallLine => "SQL",
+    }
+}
+
+enum_with_str_repr! {
+    IntensityChange {
+        Intensifying => "INTSF",
+        Weakening => "WKN",
+        NoChange => "NC",
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Movement {
+    /// Direction of movement, absent if stationary
+    pub direction: Option<CompassDirection>,
+    pub speed: Option<Velocity>,
+}
+
+/// Where a hazard is
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Area {
+    /// Bounded by the flight information region, which isn't known here
+    EntireFir,
+    /// Vertices in order, where the first may be repeated at the end
+    Polygon(Vec<Position>),
+    /// Within a distance either side of a line
+    Corridor {
+        line: Vec<Position>,
+        half_width: Length,
+    },
+    Circle {
+        centre: Position,
+        radius: Length,
+    },
+    /// Every boundary applies
+    Bounded(Vec<Boundary>),
+}
+
+/// One side of a line of latitude, longitude or a line between points
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Boundary {
+    Latitude {
+        side: CompassDirection,
+        latitude: Angle,
+    },
+    Longitude {
+        side: CompassDirection,
+        longitude: Angle,
+    },
+    Line {
+        side: CompassDirection,
+        points: Vec<Position>,
+    },
+}
+
+/// Mean radius
+const EARTH_RADIUS_NAUTICAL_MILES: f64 = 3440.065;
+
+impl Area {
+    /// Whether a point is within this area
+    ///
+    /// Points on the boundary are considered inside. Polygons and lines are treated as straight in latitude and longitude,
+    /// which is accurate enough for the size of a typical hazard area. Longitudes are compared relative to the area,
+    /// so areas crossing the antimeridian such as in Pacific FIRs work as long as they span less than 180°.
+    ///
+    /// Absent for [Area::EntireFir].
+    pub fn contains(&self, point: Position) -> Option<bool> {
+        match self {
+            Area::EntireFir => None,
+            Area::Polygon(vertices) => Some(polygon_contains(vertices, point)),
+            Area::Corridor { line, half_width } => Some(match line.as_slice() {
+                [single] => distance(*single, point) <= *half_width,
+                _ => line.windows(2).any(|segment| {
+                    distance_to_segment(segment[0], segment[1], point) <= *half_width
+                }),
+            }),
+            Area::Circle { centre, radius } => Some(distance(*centre, point) <= *radius),
+            Area::Bounded(boundaries) => {
+                Some(boundaries.iter().all(|boundary| boundary.contains(point)))
+            }
+        }
+    }
+}
+
+impl Boundary {
+    /// Whether a point is on the given side of this boundary
+    pub fn contains(&self, point: Position) -> bool {
+        use CompassDirection::*;
+        let (latitude, longitude) = coordinates(point);
+        match self {
+            Boundary::Latitude {
+                side,
+                latitude: line,
+            } => match side {
+                North | NorthEast | NorthWest => latitude >= line.get::<degree>(),
+                _ => latitude <= line.get::<degree>(),
+            },
+            Boundary::Longitude {
+                side,
+                longitude: line,
+            } => match side {
+                East | NorthEast | SouthEast => {
+                    longitude_difference(longitude, line.get::<degree>()) >= 0.
+                }
+                _ => longitude_difference(longitude, line.get::<degree>()) <= 0.,
+            },
+            // The point is on that side if looking back in the opposite direction crosses the line
+            Boundary::Line { side, points } => {
+                let bearing = side.bearing().get::<radian>();
+                let (dx, dy) = (-bearing.sin(), -bearing.cos());
+                points.windows(2).any(|segment| {
+                    let (y1, x1) = coordinates(segment[0]);
+                    let (y2, x2) = coordinates(segment[1]);
+                    let x1 = longitude + longitude_difference(x1, longitude);
+                    let (ex, ey) = (longitude_difference(x2, x1), y2 - y1);
+                    let denominator = dx * ey - dy * ex;
+                    if denominator.abs() < f64::EPSILON {
+                        return false;
+                    }
+                    let (fx, fy) = (x1 - longitude, y1 - latitude);
+                    let along_ray = (fx * ey - fy * ex) / denominator;
+                    let along_segment = (fx * dy - fy * dx) / denominator;
+                    along_ray >= 0. && (0. ..=1.).contains(&along_segment)
+                })
+            }
+        }
+    }
+}
+
+/// Latitude and longitude in degrees
+fn coordinates(point: Position) -> (f64, f64) {
+    (
+        point.latitude.get::<degree>(),
+        point.longitude.get::<degree>(),
+    )
+}
+
+/// Difference in degrees wrapped into -180..180, so that lines may cross the antimeridian
+fn longitude_difference(to: f64, from: f64) -> f64 {
+    (to - from + 180.).rem_euclid(360.) - 180.
+}
+
+/// Even-odd rule, after checking whether the point is on an edge which the rule may count either way
+fn polygon_contains(vertices: &[Position], point: Position) -> bool {
+    /// Tolerance in degrees, about 1 cm
+    const ON_EDGE: f64 = 1e-7;
+
+    let reference = vertices
+        .first()
+        .map_or(0., |first| first.longitude.get::<degree>());
+    // Longitudes relative to the first vertex, so a polygon crossing the antimeridian stays continuous
+    let relative = |position: Position| {
+        let (latitude, longitude) = coordinates(position);
+        (latitude, longitude_difference(longitude, reference))
+    };
+    let (latitude, longitude) = relative(point);
+    let mut inside = false;
+    for (i, vertex) in vertices.iter().enumerate() {
+        let (y1, x1) = relative(*vertex);
+        let (y2, x2) = relative(vertices[(i + 1) % vertices.len()]);
+        let cross = (x2 - x1) * (latitude - y1) - (y2 - y1) * (longitude - x1);
+        let length = (x2 - x1).hypot(y2 - y1);
+        if cross.abs() <= ON_EDGE * length.max(1.)
+            && (x1.min(x2) - ON_EDGE..=x1.max(x2) + ON_EDGE).contains(&longitude)
+            && (y1.min(y2) - ON_EDGE..=y1.max(y2) + ON_EDGE).contains(&latitude)
+        {
+            return true;
+        }
+        if (y1 > latitude) != (y2 > latitude)
+            && longitude < (x2 - x1) * (latitude - y1) / (y2 - y1) + x1
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// [Great-circle distance](https://en.wikipedia.org/wiki/Haversine_formula)
+fn distance(from: Position, to: Position) -> Length {
+    let (lat1, lon1) = (
+        from.latitude.get::<radian>(),
+        from.longitude.get::<radian>(),
+    );
+    let (lat2, lon2) = (to.latitude.get::<radian>(), to.longitude.get::<radian>());
+    let a = ((lat2 - lat1) / 2.).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.).sin().powi(2);
+    Length::new::<nautical_mile>(2. * EARTH_RADIUS_NAUTICAL_MILES * a.sqrt().asin())
+}
+
+/// Projects onto a plane tangent at the point, so only accurate for nearby segments
+fn distance_to_segment(start: Position, end: Position, point: Position) -> Length {
+    let (latitude, longitude) = coordinates(point);
+    let scale = latitude.to_radians().cos();
+    let (start_latitude, start_longitude) = coordinates(start);
+    let (end_latitude, end_longitude) = coordinates(end);
+    // The end follows on from the start, so a segment crossing the antimeridian isn't taken the long way round
+    let x1 = longitude_difference(start_longitude, longitude) * scale;
+    let x2 = x1 + longitude_difference(end_longitude, start_longitude) * scale;
+    let (y1, y2) = (start_latitude - latitude, end_latitude - latitude);
+    let (ex, ey) = (x2 - x1, y2 - y1);
+    let length_squared = ex * ex + ey * ey;
+    let t = if length_squared < f64::EPSILON {
+        0.
+    } else {
+        (-(x1 * ex + y1 * ey) / length_squared).clamp(0., 1.)
+    };
+    let (nearest_x, nearest_y) = (x1 + t * ex, y1 + t * ey);
+    Length::new::<nautical_mile>((nearest_x * nearest_x + nearest_y * nearest_y).sqrt() * 60.)
+}
+
+/// US AIRMET bulletin for one of the forecast areas
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AirmetBulletin<'input> {
+    /// Identifier of the forecast area, i.e. SLC
+    pub area: &'input str,
+    pub series: AirmetSeries,
+    pub issue_time: DateTime,
+    /// Present if this bulletin amends an earlier one
+    pub update: Option<&'input str>,
+    /// Hazards that the bulletin covers, as listed in its heading
+    pub hazards: Vec<AirmetHazard>,
+    pub valid_until: DateTime,
+    pub airmets: Vec<Airmet<'input>>,
+    /// Outlooks and statements that are not decoded, in order
+    pub other: Vec<&'input str>,
+}
+
+enum_with_str_repr! {
+    AirmetSeries {
+        Sierra => "SIERRA",
+        Tango => "TANGO",
+        Zulu => "ZULU",
+    }
+}
+
+enum_with_str_repr! {
+    AirmetHazard {
+        InstrumentFlightRules => "IFR",
+        MountainObscuration => "MTN OBSCN",
+        Turbulence => "TURB",
+        StrongSurfaceWinds => "STG SFC WNDS",
+        LowLevelWindShear => "LLWS",
+        Icing => "ICE",
+        FreezingLevel => "FRZLVL",
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Airmet<'input> {
+    pub hazard: AirmetHazard,
+    /// Abbreviations of the states and coastal waters affected
+    pub states: Vec<&'input str>,
+    /// Vertices of the area, relative to navigation aids
+    ///
+    /// Empty if the AIRMET applies to the whole forecast area.
+    pub vertices: Vec<Location<'input>>,
+    /// Conditions and levels in plain language
+    pub description: &'input str,
+}
+
+impl<'input> Airmet<'input> {
+    /// Converts the vertices into a [Area::Polygon] using the positions of the navigation aids they are relative to
+    ///
+    /// Radials are treated as true bearings. Absent if there are no vertices or any navigation aid can't be found.
+    pub fn area(&self, mut find_fix: impl FnMut(&str) -> Option<Position>) -> Option<Area> {
+        if self.vertices.is_empty() {
+            return None;
+        }
+        self.vertices
+            .iter()
+            .map(|vertex| {
+                let fix = find_fix(vertex.fix)?;
+                Some(match (vertex.radial, vertex.distance) {
+                    (Some(radial), Some(distance)) => destination(fix, radial, distance),
+                    _ => fix,
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Area::Polygon)
+    }
+}
+
+/// The point reached by travelling along a great circle
+fn destination(from: Position, bearing: Angle, distance: Length) -> Position {
+    let angular_distance = distance.get::<nautical_mile>() / EARTH_RADIUS_NAUTICAL_MILES;
+    let bearing = bearing.get::<radian>();
+    let (latitude, longitude) = (
+        from.latitude.get::<radian>(),
+        from.longitude.get::<radian>(),
+    );
+    let destination_latitude = (latitude.sin() * angular_distance.cos()
+        + latitude.cos() * angular_distance.sin() * bearing.cos())
+    .asin();
+    let destination_longitude = longitude
+        + (bearing.sin() * angular_distance.sin() * latitude.cos())
+            .atan2(angular_distance.cos() - latitude.sin() * destination_latitude.sin());
+    Position {
+        latitude: Angle::new::<radian>(destination_latitude),
+        longitude: Angle::new::<radian>(destination_longitude),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(latitude: f64, longitude: f64) -> Position {
+        Position {
+            latitude: Angle::new::<degree>(latitude),
+            longitude: Angle::new::<degree>(longitude),
+        }
+    }
+
+    #[test]
+    fn point_in_area() {
+        let polygon = Area::Polygon(vec![
+            position(55., -15.),
+            position(55., -20.),
+            position(53., -20.),
+            position(53., -15.),
+        ]);
+        assert_eq!(polygon.contains(position(54., -17.)), Some(true));
+        assert_eq!(polygon.contains(position(56., -17.)), Some(false));
+        for boundary in [
+            position(55., -17.),
+            position(54., -15.),
+            position(53., -20.),
+            position(55., -15.),
+            position(53., -17.5),
+        ] {
+            assert_eq!(polygon.contains(boundary), Some(true), "{:?}", boundary);
+        }
+        assert_eq!(polygon.contains(position(55.001, -17.)), Some(false));
+        let triangle = Area::Polygon(vec![
+            position(0., 0.),
+            position(10., 10.),
+            position(0., 10.),
+        ]);
+        assert_eq!(triangle.contains(position(5., 5.)), Some(true));
+        assert_eq!(triangle.contains(position(5., 4.99)), Some(false));
+
+        let corridor = Area::Corridor {
+            line: vec![position(50., 0.), position(50., 10.)],
+            half_width: Length::new::<nautical_mile>(30.),
+        };
+        assert_eq!(corridor.contains(position(50.4, 5.)), Some(true));
+        assert_eq!(corridor.contains(position(51., 5.)), Some(false));
+
+        let bounded = Area::Bounded(vec![
+            Boundary::Latitude {
+                side: CompassDirection::North,
+                latitude: Angle::new::<degree>(50.),
+            },
+            Boundary::Line {
+                side: CompassDirection::West,
+                points: vec![position(45., 0.), position(60., 10.)],
+            },
+        ]);
+        assert_eq!(bounded.contains(position(52., 0.)), Some(true));
+        assert_eq!(bounded.contains(position(52., 5.)), Some(false));
+        assert_eq!(Area::EntireFir.contains(position(0., 0.)), None);
+    }
+
+    #[test]
+    fn area_across_antimeridian() {
+        let polygon = Area::Polygon(vec![
+            position(-20., 170.),
+            position(-20., -170.),
+            position(-30., -170.),
+            position(-30., 170.),
+        ]);
+        for inside in [
+            position(-25., 175.),
+            position(-25., -175.),
+            position(-25., 180.),
+            position(-25., -180.),
+        ] {
+            assert_eq!(polygon.contains(inside), Some(true), "{:?}", inside);
+        }
+        for outside in [
+            position(-25., 0.),
+            position(-25., 160.),
+            position(-25., -160.),
+        ] {
+            assert_eq!(polygon.contains(outside), Some(false), "{:?}", outside);
+        }
+
+        let corridor = Area::Corridor {
+            line: vec![position(0., 175.), position(0., -175.)],
+            half_width: Length::new::<nautical_mile>(30.),
+        };
+        assert_eq!(corridor.contains(position(0.2, 180.)), Some(true));
+        assert_eq!(corridor.contains(position(0.2, 0.)), Some(false));
+
+        let bounded = Area::Bounded(vec![
+            Boundary::Longitude {
+                side: CompassDirection::East,
+                longitude: Angle::new::<degree>(170.),
+            },
+            Boundary::Line {
+                side: CompassDirection::North,
+                points: vec![position(-30., 175.), position(-30., -175.)],
+            },
+        ]);
+        assert_eq!(bounded.contains(position(-25., -178.)), Some(true));
+        assert_eq!(bounded.contains(position(-25., 165.)), Some(false));
+        assert_eq!(bounded.contains(position(-35., -178.)), Some(false));
+    }
+
+    #[test]
+    fn destination_and_distance_agree() {
+        let from = position(47.62, -117.53);
+        let to = destination(
+            from,
+            Angle::new::<degree>(337.5),
+            Length::new::<nautical_mile>(50.),
+        );
+        assert!((distance(from, to).get::<nautical_mile>() - 50.).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,254 @@
+//! Types for [SYNOP](https://en.wikipedia.org/wiki/SYNOP) (WMO FM-12) surface observations
+//! and the closely related SHIP (FM-13) and BUOY (FM-18) codes
+//!
+//! Code tables referenced here are from the [WMO Manual on Codes](https://library.wmo.int/doc_num.php?explnum_id=10235).
+use uom::si::f64::{Angle, Length, Pressure, ThermodynamicTemperature, Time};
+
+use super::{
+    DateTime, MilitaryTime, Position, RawVisibility, Temperatures, WaterConditions,
+    WaterSurfaceState, Wind,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SynopReport<'input> {
+    /// Minutes are always zero, use [SynopReport::exact_observation_time] if present
+    pub observation_time: DateTime,
+    pub wind_indicator: Option<WindIndicator>,
+    /// WMO block and station number (IIiii), or call sign for ships
+    pub station: &'input str,
+    /// Only reported by ships
+    pub position: Option<Position>,
+    /// Indicates the station did not report
+    pub nil: bool,
+    pub precipitation_indicator: Option<PrecipitationIndicator>,
+    pub station_type: Option<StationType>,
+    /// Height above ground of the lowest cloud, see code table 1600
+    pub lowest_cloud_base: Option<HeightRange>,
+    /// Horizontal visibility, see code table 4377
+    pub visibility: Option<RawVisibility>,
+    /// Total cloud cover in oktas, where 9 means the sky is obscured
+    pub total_cloud_cover: Option<u8>,
+    /// In the unit given by [SynopReport::wind_indicator]
+    pub wind: Option<Wind>,
+    pub temperatures: Option<Temperatures>,
+    /// Reported instead of dewpoint by some stations, in percent
+    pub relative_humidity: Option<u8>,
+    pub station_pressure: Option<Pressure>,
+    /// Pressure reduced to mean sea level
+    pub sea_level_pressure: Option<Pressure>,
+    /// Reported instead of sea level pressure by high altitude stations
+    pub geopotential_height: Option<GeopotentialHeight>,
+    pub pressure_tendency: Option<PressureTendency>,
+    pub precipitation: Option<PrecipitationAmount>,
+    /// Present weather from code table 4677 (manned) or 4680 (automatic)
+    pub present_weather: Option<u8>,
+    /// Past weather from code table 4561 (manned) or 4531 (automatic)
+    pub past_weather: (Option<u8>, Option<u8>),
+    pub clouds: Option<CloudTypes>,
+    /// Exact time of observation if it differs from the nominal hour
+    pub exact_observation_time: Option<MilitaryTime>,
+    /// Maritime data, only reported by ships and coastal stations
+    pub section_2: Option<MaritimeData<'input>>,
+    /// Climatological data for regional exchange
+    pub section_3: Option<ClimatologicalData<'input>>,
+    /// Clouds with bases below the station level
+    pub section_4: Option<Vec<&'input str>>,
+    /// Groups for national use
+    pub section_5: Option<Vec<&'input str>>,
+}
+
+/// Wind speed unit and how it was determined (iw), see code table 1855
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum WindIndicator {
+    EstimatedMetersPerSecond,
+    MeasuredMetersPerSecond,
+    EstimatedKnots,
+    MeasuredKnots,
+}
+
+/// Whether precipitation data is included (iR), see code table 1819
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PrecipitationIndicator {
+    InSections1And3,
+    InSection1,
+    InSection3,
+    /// Precipitation did not occur
+    OmittedNone,
+    /// Precipitation was not measured
+    OmittedUnavailable,
+}
+
+/// Whether the station is manned and includes weather groups (ix), see code table 1860
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StationType {
+    pub manned: bool,
+    /// Present and past weather group is included
+    pub weather_included: bool,
+}
+
+/// A coded height band
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HeightRange {
+    pub lower: Length,
+    /// Absent if the band has no upper bound, i.e. 2500 meters or more in code table 1600
+    pub upper: Option<Length>,
+}
+
+/// Height of a standard isobaric surface (4a3hhh)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GeopotentialHeight {
+    pub surface: Pressure,
+    pub height: Length,
+}
+
+/// Pressure change over the last three hours (5appp)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PressureTendency {
+    /// Characteristic of the tendency from code table 0200
+    pub characteristic: Option<u8>,
+    /// Negative when pressure is lower than three hours ago
+    pub change: Option<Pressure>,
+}
+
+/// Amount of precipitation over a period (6RRRtR)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PrecipitationAmount {
+    /// Absent when the amount was not measured
+    pub amount: Option<Length>,
+    /// Less than 0.05 mm, in which case the amount is zero
+    pub trace: bool,
+    /// Absent if the period ends at the time of observation but has a regionally defined length
+    pub period_hours: Option<u8>,
+}
+
+/// Cloud amount and types (8NhCLCMCH)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CloudTypes {
+    /// Amount of low cloud, or middle cloud if there is no low cloud, in oktas
+    pub amount: Option<u8>,
+    /// Code table 0513
+    pub low: Option<u8>,
+    /// Code table 0515
+    pub middle: Option<u8>,
+    /// Code table 0509
+    pub high: Option<u8>,
+}
+
+/// Section 3 (333)
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClimatologicalData<'input> {
+    pub maximum_temperature: Option<ThermodynamicTemperature>,
+    pub minimum_temperature: Option<ThermodynamicTemperature>,
+    pub precipitation: Option<PrecipitationAmount>,
+    pub precipitation_24_hours: Option<PrecipitationAmount>,
+    pub cloud_layers: Vec<CloudLayer>,
+    /// Groups that are not decoded, in order
+    pub other_groups: Vec<&'input str>,
+}
+
+/// Individual cloud layer (8NsChshs)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CloudLayer {
+    /// In oktas, where 9 means the sky is obscured
+    pub amount: Option<u8>,
+    /// Cloud genus from code table 0500
+    pub genus: Option<u8>,
+    /// Height of the base, see code table 1677
+    pub base: Option<HeightRange>,
+}
+
+/// Section 2 (222) of SHIP and BUOY reports
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MaritimeData<'input> {
+    /// True direction the ship is moving in from code table 0700
+    pub ship_direction: Option<u8>,
+    /// Ship speed from code table 4451
+    pub ship_speed: Option<u8>,
+    pub sea_surface_temperature: Option<ThermodynamicTemperature>,
+    /// Waves measured by instruments
+    pub waves: Option<Waves>,
+    /// Waves estimated by an observer
+    pub wind_waves: Option<Waves>,
+    /// Up to two swell systems
+    pub swells: Vec<Waves>,
+    pub ice_accretion: Option<IceAccretion>,
+    pub wet_bulb_temperature: Option<ThermodynamicTemperature>,
+    /// Groups that are not decoded, in order
+    pub other_groups: Vec<&'input str>,
+}
+
+impl<'input> MaritimeData<'input> {
+    /// The same conditions as reported by offshore platforms in a METAR
+    ///
+    /// Measured waves are preferred over estimated ones, and the sea state is derived from their height.
+    pub fn water_conditions(&self) -> WaterConditions {
+        let significant_wave_height = self
+            .waves
+            .and_then(|waves| waves.height)
+            .or_else(|| self.wind_waves.and_then(|waves| waves.height));
+        WaterConditions {
+            temperature: self.sea_surface_temperature,
+            surface_state: significant_wave_height.map(WaterSurfaceState::from_wave_height),
+            significant_wave_height,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Waves {
+    /// Direction the waves come from, only reported for swells
+    pub direction: Option<Angle>,
+    pub period: Option<Time>,
+    pub height: Option<Length>,
+}
+
+/// Ice accreting on a ship (6IsEsEsRs)
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IceAccretion {
+    /// Code table 1751
+    pub source: Option<u8>,
+    pub thickness: Option<Length>,
+    /// Code table 3551
+    pub rate: Option<u8>,
+}
+
+/// Drifting or moored [buoy](https://en.wikipedia.org/wiki/Weather_buoy) report (FM-18)
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BuoyReport<'input> {
+    /// WMO buoy identifier (A1bwnbnbnb)
+    pub station: &'input str,
+    pub observation_time: DateTime,
+    pub month: u8,
+    /// Last digit of the year
+    pub year_digit: u8,
+    pub wind_indicator: Option<WindIndicator>,
+    pub position: Option<Position>,
+    /// In the unit given by [BuoyReport::wind_indicator]
+    pub wind: Option<Wind>,
+    pub temperatures: Option<Temperatures>,
+    /// In percent
+    pub relative_humidity: Option<u8>,
+    pub station_pressure: Option<Pressure>,
+    pub sea_level_pressure: Option<Pressure>,
+    pub pressure_tendency: Option<PressureTendency>,
+    pub section_2: Option<MaritimeData<'input>>,
+    /// Subsurface temperature, salinity and current profiles
+    pub section_3: Option<Vec<&'input str>>,
+    /// Quality control and engineering information
+    pub section_4: Option<Vec<&'input str>>,
+}
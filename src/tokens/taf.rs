@@ -0,0 +1,69 @@
+//! Types for [Terminal Aerodrome Forecasts](https://en.wikipedia.org/wiki/Terminal_aerodrome_forecast) (TAF)
+//!
+//! TAFs span more than a day, so unlike trends in a METAR, their times include the day of the month.
+use std::borrow::Cow;
+
+use super::{
+    enum_with_str_repr, CloudCover, DateTime, ObservationFlag, TimeRange, Visibility, Weather, Wind,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TafReport<'input> {
+    /// Station [ICAO identifier](https://en.wikipedia.org/wiki/ICAO_airport_code)
+    pub identifier: Cow<'input, str>,
+    pub issue_time: DateTime,
+    /// Absent for a NIL TAF
+    pub valid_period: Option<TimeRange<DateTime>>,
+    /// Only [ObservationFlag::Correction] and [ObservationFlag::Nil] apply to forecasts
+    pub flags: Vec<ObservationFlag>,
+    /// Absent for a NIL TAF
+    pub base_forecast: Option<ForecastConditions>,
+    pub change_forecasts: Vec<ChangeForecast>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ForecastConditions {
+    pub wind: Option<Wind>,
+    pub visibility: Option<Visibility>,
+    pub weather: Vec<Weather>,
+    pub cloud_cover: Vec<CloudCover>,
+    pub cavok: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChangeForecast {
+    pub indicator: ChangeIndicator,
+    /// For [ChangeIndicator::From], this lasts until the next change or the end of the valid period
+    pub period: TimeRange<DateTime>,
+    pub conditions: ForecastConditions,
+}
+
+enum_with_str_repr! {
+    ChangeIndicator {
+        Becoming => "BECMG",
+        TemporaryFluctuations => "TEMPO",
+        /// Conditions change completely at the beginning of the period
+        From => "FM",
+        /// 30% probability
+        Probability30 => "PROB30",
+        /// 40% probability
+        Probability40 => "PROB40",
+        Probability30TemporaryFluctuations => "PROB30 TEMPO",
+        Probability40TemporaryFluctuations => "PROB40 TEMPO",
+    }
+}
+
+impl ChangeIndicator {
+    /// Probability in percent, absent if the change is expected
+    pub fn probability(self) -> Option<u8> {
+        use ChangeIndicator::*;
+        match self {
+            Probability30 | Probability30TemporaryFluctuations => Some(30),
+            Probability40 | Probability40TemporaryFluctuations => Some(40),
+            Becoming | TemporaryFluctuations | From => None,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::convert::TryFrom;
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use super::{adjacent_months, DateTime, MilitaryTime, TimeRange, TrendTime};
+
+impl DateTime {
+    /// Absent if the day of month does not exist in the given year and month
+    pub fn as_offset_datetime(&self, year: i32, month: Month) -> Option<OffsetDateTime> {
+        self.time
+            .as_offset_datetime(Date::from_calendar_date(year, month, self.day_of_month).ok()?)
+    }
+
+    /// Resolves into an absolute time using a reference instant close to it, such as when the report was received
+    ///
+    /// Only the day of month is reported, so the year and month are chosen from the months around the reference
+    /// to give the nearest valid time. This correctly handles a report for the 31st received on the 1st.
+    ///
+    /// The time is always taken as UTC, even if [DateTime::is_zulu] is false; check it first if that matters.
+    pub fn resolve_offset_datetime(&self, reference: OffsetDateTime) -> Option<OffsetDateTime> {
+        let reference = reference.to_offset(UtcOffset::UTC);
+        adjacent_months(reference.year(), reference.month() as u8)
+            .filter_map(|(year, month)| {
+                self.time.as_offset_datetime(
+                    Date::from_calendar_date(year, Month::try_from(month).ok()?, self.day_of_month)
+                        .ok()?,
+                )
+            })
+            .min_by_key(|candidate| (*candidate - reference).whole_seconds().abs())
+    }
+}
+
+impl MilitaryTime {
+    /// Absent if this is not a valid time of day
+    pub fn as_offset_datetime(&self, date: Date) -> Option<OffsetDateTime> {
+        let (hour, minute, day_offset) = self.time_of_day()?;
+        let time = Time::from_hms(hour, minute, 0).ok()?;
+        Some((PrimitiveDateTime::new(date, time) + Duration::days(day_offset)).assume_utc())
+    }
+
+    /// Resolves into the occurrence of this time of day nearest to the reference instant
+    pub fn resolve_offset_datetime(&self, reference: OffsetDateTime) -> Option<OffsetDateTime> {
+        let reference = reference.to_offset(UtcOffset::UTC);
+        let today = self.as_offset_datetime(reference.date())?;
+        [today - Duration::DAY, today, today + Duration::DAY]
+            .iter()
+            .copied()
+            .min_by_key(|candidate| (*candidate - reference).whole_seconds().abs())
+    }
+
+    /// Resolves into the first occurrence of this time of day at or after the reference instant
+    pub fn resolve_offset_datetime_after(
+        &self,
+        reference: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        let reference = reference.to_offset(UtcOffset::UTC);
+        let today = self.as_offset_datetime(reference.date())?;
+        [today - Duration::DAY, today, today + Duration::DAY]
+            .iter()
+            .copied()
+            .find(|candidate| *candidate >= reference)
+    }
+}
+
+impl TimeRange {
+    /// A range where `end < begin` crosses midnight, so it ends on the following day
+    pub fn as_offset_start_and_duration(&self, date: Date) -> Option<(OffsetDateTime, Duration)> {
+        let begin = self.begin.as_offset_datetime(date)?;
+        let end = self.end.resolve_offset_datetime_after(begin)?;
+        Some((begin, end - begin))
+    }
+
+    /// Resolves into absolute times, beginning nearest to the reference instant
+    ///
+    /// A range where `end < begin` crosses midnight, so it ends on the following day.
+    pub fn resolve_offset_datetime(
+        &self,
+        reference: OffsetDateTime,
+    ) -> Option<TimeRange<OffsetDateTime>> {
+        let begin = self.begin.resolve_offset_datetime(reference)?;
+        let end = self.end.resolve_offset_datetime_after(begin)?;
+        Some(TimeRange { begin, end })
+    }
+}
+
+impl TimeRange<DateTime> {
+    /// Resolves a TAF validity period into absolute times, beginning nearest to the reference instant
+    ///
+    /// The end is resolved nearest to the beginning, so a period such as `3118/0124` ends in the following month.
+    pub fn resolve_offset_datetime(
+        &self,
+        reference: OffsetDateTime,
+    ) -> Option<TimeRange<OffsetDateTime>> {
+        let begin = self.begin.resolve_offset_datetime(reference)?;
+        let end = self.end.resolve_offset_datetime(begin)?;
+        Some(TimeRange { begin, end })
+    }
+}
+
+impl TrendTime {
+    /// Resolves into absolute times using the observation time of the report
+    ///
+    /// Trends forecast the period following the observation, so each time is the first occurrence at or after it.
+    pub fn resolve_offset_datetime(
+        &self,
+        observation_time: OffsetDateTime,
+    ) -> Option<TrendTime<OffsetDateTime>> {
+        use TrendTime::*;
+        Some(match self {
+            At(time) => At(time.resolve_offset_datetime_after(observation_time)?),
+            Range { from, until } => {
+                let from = from.resolve_offset_datetime_after(observation_time)?;
+                Range {
+                    until: until.resolve_offset_datetime_after(from)?,
+                    from,
+                }
+            }
+            From(time) => From(time.resolve_offset_datetime_after(observation_time)?),
+            Until(time) => Until(time.resolve_offset_datetime_after(observation_time)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn resolve_across_month_boundary() {
+        let observation_time = DateTime {
+            day_of_month: 31,
+            time: MilitaryTime {
+                hour: 23,
+                minute: 50,
+            },
+            is_zulu: true,
+        };
+        assert_eq!(
+            observation_time.resolve_offset_datetime(datetime!(2021-09-01 00:05 UTC)),
+            Some(datetime!(2021-08-31 23:50 UTC))
+        );
+        assert_eq!(
+            observation_time.as_offset_datetime(2021, Month::September),
+            None
+        );
+    }
+
+    #[test]
+    fn resolve_range_across_midnight() {
+        let range = TimeRange {
+            begin: MilitaryTime {
+                hour: 22,
+                minute: 0,
+            },
+            end: MilitaryTime {
+                hour: 24,
+                minute: 0,
+            },
+        };
+        let resolved = range
+            .resolve_offset_datetime(datetime!(2021-12-31 21:50 UTC))
+            .unwrap();
+        assert_eq!(resolved.end, datetime!(2022-01-01 00:00 UTC));
+        assert_eq!(resolved.end - resolved.begin, Duration::hours(2));
+    }
+
+    #[test]
+    fn resolve_taf_period_across_month_boundary() {
+        let day_time = |day_of_month, hour| DateTime {
+            day_of_month,
+            time: MilitaryTime { hour, minute: 0 },
+            is_zulu: true,
+        };
+        let period = TimeRange {
+            begin: day_time(31, 18),
+            end: day_time(1, 24),
+        };
+        let resolved = period
+            .resolve_offset_datetime(datetime!(2021-08-31 17:20 UTC))
+            .unwrap();
+        assert_eq!(resolved.begin, datetime!(2021-08-31 18:00 UTC));
+        assert_eq!(resolved.end, datetime!(2021-09-02 00:00 UTC));
+    }
+}
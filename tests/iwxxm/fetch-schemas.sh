@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+# Downloads the IWXXM 3.0 schema and everything it imports or includes, mirrored by host and path
+# so that catalog.xml can resolve them without network access.
+#
+# Already downloaded files are kept, so this only fetches what is missing.
+set -euo pipefail
+cd "$(dirname "$0")"
+
+fetch() {
+    local url=$1
+    local path=${url#*://}
+    if [ -f "$path" ]; then
+        return
+    fi
+    echo "$url" >&2
+    mkdir -p "$(dirname "$path")"
+    curl --fail --silent --show-error --location "$url" --output "$path"
+
+    local scheme=${url%%://*}
+    local directory=${path%/*}
+    local location
+    for location in $(grep -o 'schemaLocation="[^"]*"' "$path" | sed 's/^schemaLocation="\(.*\)"$/\1/'); do
+        case $location in
+        http://* | https://*) fetch "$location" ;;
+        *) fetch "$scheme://$(realpath --canonicalize-missing --relative-to=/ "/$directory/$location")" ;;
+        esac
+    done
+}
+
+fetch "${1:-http://schemas.wmo.int/iwxxm/3.0/iwxxm.xsd}"